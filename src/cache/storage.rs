@@ -1,6 +1,10 @@
 use std::collections::HashMap;
+use std::path::Path;
 use std::time::{SystemTime, UNIX_EPOCH};
+use anyhow::Result;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use crate::utils::{FileUtils, WriteOptions};
 
 #[derive(Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize)]
 pub struct CacheKey {
@@ -99,6 +103,25 @@ impl<T: Clone> Cache<T> {
     }
 }
 
+impl<T: Clone + Serialize + DeserializeOwned> Cache<T> {
+    pub fn load(path: &Path, max_entries: usize) -> Self {
+        let mut cache = Cache::new(max_entries);
+        let entries = std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<Vec<(CacheKey, CacheEntry<T>)>>(&content).ok())
+            .unwrap_or_default();
+
+        cache.storage.extend(entries);
+        cache.cleanup_expired();
+        cache
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let entries: Vec<(&CacheKey, &CacheEntry<T>)> = self.storage.iter().collect();
+        FileUtils::write_file_with(path, &serde_json::to_string(&entries)?, &WriteOptions::internal())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,5 +1,22 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use std::io::{self, Write};
+use crate::config::yaml_parser::YamlConfig;
+use crate::core::audit::AuditLog;
+use crate::core::executor;
+use crate::core::patch::{FileStatus, PatchApplier};
+use crate::core::refactor::{RenameKind, Refactoring, RustRefactor};
+use crate::index::repo_map::DEFAULT_REPO_MAP_TOKENS;
+use crate::index::{ImportGraph, RepoMap, SymbolIndex};
+use crate::integrations::git::GitManager;
+use crate::integrations::ollama::OllamaClient;
+use crate::integrations::test_runner::TestRunner;
+use crate::interactive::mode::InteractiveMode;
+use crate::llm::client::{hosted_client, LLMClient};
+use crate::utils::replace::SearchReplace;
+use crate::utils::search::SearchOptions;
+use crate::utils::{FileUtils, WriteOptions};
+use std::collections::BTreeSet;
+use std::path::Path;
 
 #[derive(Parser)]
 #[command(
@@ -11,6 +28,524 @@ use std::io::{self, Write};
 pub struct Cli {
     #[arg(value_name = "FILE", help = "Optional file or directory to load initially")]
     pub path: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+}
+
+#[derive(Subcommand)]
+pub enum Commands {
+    #[command(subcommand, about = "Manage local Ollama models")]
+    Models(ModelsCommand),
+    #[command(about = "Find where symbols are defined and referenced")]
+    Symbols {
+        #[arg(value_name = "NAME", help = "Symbol name (substring match unless --exact)")]
+        query: String,
+        #[arg(long, help = "Match the name exactly")]
+        exact: bool,
+        #[arg(short, long, help = "Also list references to the symbol")]
+        references: bool,
+    },
+    #[command(about = "Show the import graph or what depends on a file")]
+    Deps {
+        #[arg(value_name = "FILE", help = "Project-relative file to analyse")]
+        file: Option<String>,
+        #[arg(long, help = "Export the graph as Graphviz DOT")]
+        dot: bool,
+        #[arg(long, help = "Export the graph as JSON")]
+        json: bool,
+    },
+    #[command(about = "Regex search-and-replace across the project with preview")]
+    Replace {
+        #[arg(value_name = "PATTERN")]
+        pattern: String,
+        #[arg(value_name = "REPLACEMENT", help = "Replacement text; $1 or ${name} insert capture groups")]
+        replacement: String,
+        #[arg(short = 'i', long, help = "Case-insensitive matching")]
+        ignore_case: bool,
+        #[arg(short, long, help = "Only match whole words")]
+        word: bool,
+        #[arg(short = 'F', long, help = "Treat the pattern as a literal string")]
+        fixed_strings: bool,
+        #[arg(short = 'U', long, help = "Allow matches to span lines")]
+        multiline: bool,
+        #[arg(long, help = "Only print the diff")]
+        dry_run: bool,
+        #[arg(short, long, help = "Apply every change without reviewing")]
+        yes: bool,
+        #[arg(long, help = "Keep copies of the original files under .codeagent/backups")]
+        backup: bool,
+    },
+    #[command(about = "Apply a unified diff or SEARCH/REPLACE blocks")]
+    Apply {
+        #[arg(value_name = "PATCH", help = "Patch file, or - to read from stdin")]
+        patch: String,
+        #[arg(long, help = "Only print the resulting diff")]
+        dry_run: bool,
+        #[arg(long, help = "Keep copies of the original files under .codeagent/backups")]
+        backup: bool,
+    },
+    #[command(about = "Rust refactorings verified with cargo check before they are written")]
+    Refactor {
+        #[command(subcommand)]
+        action: RefactorCommand,
+        #[arg(long, global = true, help = "Only print the diff")]
+        dry_run: bool,
+        #[arg(long, global = true, help = "Keep copies of the original files under .codeagent/backups")]
+        backup: bool,
+    },
+    #[command(about = "Print the repository map included in prompts")]
+    Map {
+        #[arg(long, default_value_t = DEFAULT_REPO_MAP_TOKENS, help = "Approximate token budget")]
+        tokens: usize,
+    },
+    #[command(subcommand, about = "Inspect the audit log in .codeagent/audit.jsonl")]
+    Audit(AuditCommand),
+    #[command(about = "Run the project's test suites")]
+    Test {
+        #[arg(value_name = "TEST", help = "Test name, file or pytest node ID to run instead of everything")]
+        test: Option<String>,
+        #[arg(long, conflicts_with = "test", help = "Only run tests affected by uncommitted changes")]
+        changed: bool,
+        #[arg(long, value_name = "FILE", help = "Also write the results as JUnit XML")]
+        junit: Option<String>,
+    },
+    #[command(about = "Plan a task and carry out its steps")]
+    Run {
+        #[arg(value_name = "PROMPT")]
+        prompt: String,
+        #[arg(long, help = "Show the changes each step would make without writing them")]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AuditCommand {
+    #[command(about = "List recorded runs")]
+    List,
+    #[command(about = "Show everything recorded for a run")]
+    Show {
+        #[arg(value_name = "RUN", help = "Run ID, a unique prefix of one, or \"last\"")]
+        run: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ModelsCommand {
+    #[command(about = "List installed models")]
+    List,
+    #[command(about = "Download a model, showing progress")]
+    Pull {
+        #[arg(value_name = "MODEL")]
+        name: String,
+    },
+    #[command(about = "Show context length, parameters and template of a model")]
+    Show {
+        #[arg(value_name = "MODEL")]
+        name: String,
+    },
+    #[command(about = "Delete an installed model")]
+    Delete {
+        #[arg(value_name = "MODEL")]
+        name: String,
+    },
+    #[command(about = "List models currently loaded in memory")]
+    Ps,
+}
+
+#[derive(Subcommand)]
+pub enum RefactorCommand {
+    #[command(about = "Rename a function, type or field across the project")]
+    Rename {
+        #[arg(value_name = "KIND", value_parser = ["fn", "struct", "field"])]
+        kind: String,
+        #[arg(value_name = "OLD", help = "Current name; qualify as Type::name or module::name when it is defined more than once")]
+        old: String,
+        #[arg(value_name = "NEW")]
+        new: String,
+    },
+    #[command(about = "Extract a range of lines into a new function")]
+    Extract {
+        #[arg(value_name = "FILE")]
+        file: String,
+        #[arg(value_name = "START")]
+        start: usize,
+        #[arg(value_name = "END")]
+        end: usize,
+        #[arg(value_name = "NAME", help = "Name of the new function")]
+        name: String,
+    },
+    #[command(about = "Replace a variable with its value at every use")]
+    Inline {
+        #[arg(value_name = "FILE")]
+        file: String,
+        #[arg(value_name = "LINE", help = "Line of the let binding")]
+        line: usize,
+        #[arg(value_name = "NAME")]
+        name: String,
+    },
+}
+
+pub async fn run_command(
+    command: Commands,
+    project_path: &str,
+    ollama: &mut OllamaClient,
+) -> anyhow::Result<()> {
+    match command {
+        Commands::Models(cmd) => run_models_command(cmd, ollama).await,
+        Commands::Symbols { query, exact, references } => {
+            run_symbols_command(Path::new(project_path), &query, exact, references)
+        }
+        Commands::Deps { file, dot, json } => {
+            run_deps_command(Path::new(project_path), file.as_deref(), dot, json)
+        }
+        Commands::Replace { pattern, replacement, ignore_case, word, fixed_strings, multiline, dry_run, yes, backup } => {
+            let options = SearchOptions {
+                case_insensitive: ignore_case,
+                whole_word: word,
+                fixed_string: fixed_strings,
+                multiline,
+                ..SearchOptions::default()
+            };
+            let root = Path::new(project_path);
+            let write_options = if backup { WriteOptions::with_backups(root) } else { WriteOptions::for_project(root) };
+            run_replace_command(root, &pattern, &replacement, &options, &write_options, dry_run, yes)
+        }
+        Commands::Apply { patch, dry_run, backup } => {
+            let root = Path::new(project_path);
+            let write_options = if backup { WriteOptions::with_backups(root) } else { WriteOptions::for_project(root) };
+            run_apply_command(root, &patch, &write_options, dry_run)
+        }
+        Commands::Refactor { action, dry_run, backup } => {
+            let root = Path::new(project_path);
+            let refactoring = match action {
+                RefactorCommand::Rename { kind, old, new } => RustRefactor::rename(root, &old, &new, RenameKind::parse(&kind)?)?,
+                RefactorCommand::Extract { file, start, end, name } => {
+                    RustRefactor::extract_function(root, &file, start, end, &name)?
+                }
+                RefactorCommand::Inline { file, line, name } => RustRefactor::inline_variable(root, &file, line, &name)?,
+            };
+            let write_options = if backup { WriteOptions::with_backups(root) } else { WriteOptions::for_project(root) };
+            run_refactor_command(&refactoring, &write_options, dry_run)
+        }
+        Commands::Map { tokens } => {
+            print!("{}", RepoMap::cached(Path::new(project_path), tokens)?);
+            Ok(())
+        }
+        Commands::Audit(cmd) => run_audit_command(&FileUtils::project_root(Path::new(project_path)), cmd),
+        Commands::Test { test, changed, junit } => {
+            run_test_command(Path::new(project_path), test.as_deref(), changed, junit.as_deref()).await
+        }
+        Commands::Run { prompt, dry_run } => {
+            let root = Path::new(project_path);
+            let config = YamlConfig::load(&root.join(".codeagent.yml")).unwrap_or_default();
+            let hosted = hosted_client(&config)?;
+            let client: &dyn LLMClient = match &hosted {
+                Some(client) => client.as_ref(),
+                None => &*ollama,
+            };
+            executor::run_task(root, &prompt, dry_run, client).await?;
+            Ok(())
+        }
+    }
+}
+
+async fn run_test_command(root: &Path, test: Option<&str>, changed: bool, junit: Option<&str>) -> anyhow::Result<()> {
+    let report = match test {
+        Some(test) => TestRunner::run_specific_test(root, test).await?,
+        None if changed => {
+            let files = GitManager::new(root)?.get_status()?;
+            if files.is_empty() {
+                println!("No uncommitted changes");
+                return Ok(());
+            }
+            let report = TestRunner::run_affected_tests(root, &files).await?;
+            if report.suites.is_empty() {
+                println!("No tests are affected by {} changed files", files.len());
+                return Ok(());
+            }
+            report
+        }
+        None => TestRunner::run_all_tests(root).await?,
+    };
+    print!("{}", report.output());
+    if let Some(path) = junit {
+        FileUtils::write_file(Path::new(path), &report.junit())?;
+        println!("Wrote JUnit report to {}", path);
+    }
+
+    let summary = report.summary();
+    println!(
+        "{} passed, {} failed, {} skipped in {}ms",
+        summary.passed, summary.failed, summary.skipped, report.duration_ms
+    );
+    if !report.passed() {
+        return Err(anyhow::anyhow!("Tests failed (exit code {})", report.exit_code()));
+    }
+    Ok(())
+}
+
+fn run_audit_command(root: &Path, command: AuditCommand) -> anyhow::Result<()> {
+    let records = AuditLog::read(root)?;
+    match command {
+        AuditCommand::List => {
+            for (run_id, events) in AuditLog::runs(&records) {
+                println!("{}  {} events", run_id, events);
+            }
+        }
+        AuditCommand::Show { run } => {
+            let run_id = AuditLog::resolve_run(&records, &run)?;
+            print!("{}", AuditLog::render(&records, &run_id));
+        }
+    }
+    Ok(())
+}
+
+fn run_replace_command(
+    root: &Path,
+    pattern: &str,
+    replacement: &str,
+    options: &SearchOptions,
+    write_options: &WriteOptions,
+    dry_run: bool,
+    yes: bool,
+) -> anyhow::Result<()> {
+    let mut plan = SearchReplace::plan_project(pattern, replacement, root, options)?;
+    if plan.files.is_empty() {
+        println!("No matches for {}", pattern);
+        return Ok(());
+    }
+
+    println!("{} changes in {} files", plan.hunk_count(), plan.files.len());
+    if dry_run {
+        InteractiveMode::display_diff(&plan.diff());
+        return Ok(());
+    }
+
+    if !yes && !SearchReplace::review(&mut plan) {
+        println!("Aborted; no files were changed");
+        return Ok(());
+    }
+    if plan.accepted_count() == 0 {
+        println!("No changes accepted");
+        return Ok(());
+    }
+
+    let written = SearchReplace::apply(&plan, write_options)?;
+    println!("✓ Applied {} changes to {} files", plan.accepted_count(), written);
+    Ok(())
+}
+
+fn run_apply_command(root: &Path, patch: &str, write_options: &WriteOptions, dry_run: bool) -> anyhow::Result<()> {
+    let text = if patch == "-" {
+        io::read_to_string(io::stdin())?
+    } else {
+        std::fs::read_to_string(patch)?
+    };
+
+    let applier = PatchApplier::new(root).with_write_options(write_options.clone());
+    let report = applier.apply_text(&text, dry_run)?;
+
+    for file in &report.files {
+        for hunk in &file.hunks {
+            let marker = if hunk.applied { "✓" } else { "✗" };
+            println!("{} {} hunk {}: {}", marker, file.path, hunk.index + 1, hunk.message);
+        }
+        match file.status {
+            FileStatus::Created => println!("✓ {} created", file.path),
+            FileStatus::Deleted => println!("✓ {} deleted", file.path),
+            _ => {}
+        }
+    }
+
+    if report.has_conflicts() {
+        return Err(anyhow::anyhow!("Patch has conflicts; no files were changed"));
+    }
+    if dry_run {
+        InteractiveMode::display_diff(&report.diff);
+    } else {
+        println!("✓ Applied patch to {} files", report.files.len());
+    }
+    Ok(())
+}
+
+fn run_refactor_command(refactoring: &Refactoring, write_options: &WriteOptions, dry_run: bool) -> anyhow::Result<()> {
+    println!("{}", refactoring.description);
+    InteractiveMode::display_diff(&refactoring.diff());
+    if dry_run {
+        return Ok(());
+    }
+
+    println!("Running cargo check...");
+    let check = refactoring.verify()?;
+    if !check.success {
+        eprintln!("{}", check.output);
+        return Err(anyhow::anyhow!("cargo check failed; no files were changed"));
+    }
+
+    let written = refactoring.apply(write_options)?;
+    println!("✓ Updated {} files", written);
+    Ok(())
+}
+
+fn run_deps_command(root: &Path, file: Option<&str>, dot: bool, json: bool) -> anyhow::Result<()> {
+    let graph = ImportGraph::build(root)?;
+
+    let Some(file) = file else {
+        if json {
+            println!("{}", graph.to_json()?);
+        } else {
+            print!("{}", graph.to_dot());
+        }
+        return Ok(());
+    };
+
+    let file = file.trim_start_matches("./").to_string();
+    if !graph.edges.contains_key(&file) {
+        println!("{} is not part of the import graph", file);
+        return Ok(());
+    }
+
+    let direct = graph.dependents(&file);
+    let transitive = graph.transitive_dependents(std::slice::from_ref(&file));
+    if json || dot {
+        let report = serde_json::json!({
+            "file": file,
+            "imports": graph.dependencies(&file),
+            "dependents": direct,
+            "impacted": transitive,
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    println!("{} imports:", file);
+    for dependency in graph.dependencies(&file) {
+        println!("  {}", dependency);
+    }
+    println!("\nDepended on by:");
+    for dependent in &direct {
+        println!("  {}", dependent);
+    }
+    let indirect: Vec<&String> = transitive.iter().filter(|f| !direct.contains(f)).collect();
+    if !indirect.is_empty() {
+        println!("\nIndirectly impacted:");
+        for dependent in indirect {
+            println!("  {}", dependent);
+        }
+    }
+    Ok(())
+}
+
+fn run_symbols_command(root: &Path, query: &str, exact: bool, references: bool) -> anyhow::Result<()> {
+    let mut index = SymbolIndex::load(root);
+    index.refresh()?;
+
+    let definitions = if exact {
+        index.find_definitions(query)
+    } else {
+        index.search(query)
+    };
+
+    if definitions.is_empty() {
+        println!("No symbols matching '{}'", query);
+    }
+    for symbol in &definitions {
+        let container = symbol
+            .container
+            .as_ref()
+            .map(|c| format!("{}::", c))
+            .unwrap_or_default();
+        println!(
+            "{}:{}:{}  {:<9} {}{}",
+            symbol.file, symbol.line, symbol.column, symbol.kind, container, symbol.name
+        );
+    }
+
+    if references {
+        let names: BTreeSet<&str> = definitions.iter().map(|s| s.name.as_str()).collect();
+        for name in names {
+            let refs = index.find_references(name);
+            println!("\nReferences to {} ({}):", name, refs.len());
+            for reference in refs {
+                println!("  {}:{}:{}", reference.file, reference.line, reference.column);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn run_models_command(command: ModelsCommand, ollama: &mut OllamaClient) -> anyhow::Result<()> {
+    match command {
+        ModelsCommand::List => {
+            let models = ollama.get_available_models().await?;
+            if models.is_empty() {
+                println!("No models installed. Try: codeagent models pull mistral");
+            }
+            for model in models {
+                let marker = if model == ollama.model { "*" } else { " " };
+                println!("{} {}", marker, model);
+            }
+        }
+        ModelsCommand::Pull { name } => {
+            let mut last_status = String::new();
+            ollama
+                .pull(&name, |progress| {
+                    match progress.percent() {
+                        Some(percent) => {
+                            print!("\r{:<40} {:>3}%", progress.status, percent);
+                        }
+                        None if progress.status != last_status => {
+                            print!("\n{}", progress.status);
+                        }
+                        None => {}
+                    }
+                    last_status = progress.status.clone();
+                    io::stdout().flush().ok();
+                })
+                .await?;
+            println!("\n✓ Pulled {}", name);
+        }
+        ModelsCommand::Show { name } => {
+            let info = ollama.show(&name).await?;
+            println!("Model:          {}", info.name);
+            println!("Family:         {}", info.details.family);
+            println!("Parameters:     {}", info.details.parameter_size);
+            println!("Quantization:   {}", info.details.quantization_level);
+            match info.context_length {
+                Some(length) => println!("Context length: {}", length),
+                None => println!("Context length: unknown"),
+            }
+            if !info.parameters.is_empty() {
+                println!("\nParameters:\n{}", info.parameters);
+            }
+            if !info.template.is_empty() {
+                println!("\nTemplate:\n{}", info.template);
+            }
+        }
+        ModelsCommand::Delete { name } => {
+            ollama.delete(&name).await?;
+            println!("✓ Deleted {}", name);
+        }
+        ModelsCommand::Ps => {
+            let running = ollama.ps().await?;
+            if running.is_empty() {
+                println!("No models loaded");
+            }
+            for model in running {
+                println!(
+                    "{:<30} {:>8} MB  (VRAM {:>8} MB)  expires {}",
+                    model.name,
+                    model.size / 1_000_000,
+                    model.size_vram / 1_000_000,
+                    model.expires_at
+                );
+            }
+        }
+    }
+
+    Ok(())
 }
 
 pub struct InteractiveSession {
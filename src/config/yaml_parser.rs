@@ -2,6 +2,8 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::Path;
 use anyhow::Result;
+use crate::utils::walker::GlobRules;
+use crate::utils::{FileUtils, WriteOptions};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct YamlConfig {
@@ -15,25 +17,129 @@ pub struct YamlConfig {
     #[serde(default)]
     pub anthropic_api_key: Option<String>,
     #[serde(default)]
+    pub embedding_model: Option<String>,
+    #[serde(default)]
     pub interactive_mode: bool,
     #[serde(default)]
     pub auto_fix_enabled: bool,
     #[serde(default)]
     pub max_retry_attempts: u32,
-    #[serde(default)]
+    #[serde(default = "default_excluded_files")]
     pub excluded_files: Vec<String>,
-    #[serde(default)]
+    #[serde(default = "default_excluded_dirs")]
     pub excluded_dirs: Vec<String>,
     #[serde(default)]
     pub custom_tools: HashMap<String, CustomTool>,
+    #[serde(default)]
+    pub sandbox: SandboxConfig,
+    #[serde(default)]
+    pub policy: PolicyConfig,
+    #[serde(default)]
+    pub test_suites: Vec<TestSuiteConfig>,
+}
+
+/// Replaces test framework detection when any are configured.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestSuiteConfig {
+    pub framework: String,
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub command: Option<String>,
+    /// JUnit XML report the command writes, relative to `path`; read instead of the output.
+    #[serde(default)]
+    pub junit: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SandboxConfig {
+    #[serde(default)]
+    pub network_isolation: bool,
+    #[serde(default)]
+    pub cpu_seconds: Option<u64>,
+    #[serde(default)]
+    pub memory_mb: Option<u64>,
+    #[serde(default)]
+    pub max_output_bytes: Option<usize>,
+    #[serde(default)]
+    pub env_allowlist: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyMode {
+    #[default]
+    Allow,
+    Ask,
+    Deny,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AccessRules {
+    #[serde(default)]
+    pub allow: Vec<String>,
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PolicyModes {
+    #[serde(default)]
+    pub write: PolicyMode,
+    #[serde(default)]
+    pub command: PolicyMode,
+    #[serde(default)]
+    pub git_commit: PolicyMode,
+    #[serde(default)]
+    pub github: PolicyMode,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PolicyConfig {
+    #[serde(default)]
+    pub read: AccessRules,
+    #[serde(default)]
+    pub write: AccessRules,
+    #[serde(default)]
+    pub commands: AccessRules,
+    #[serde(default)]
+    pub max_files_changed: Option<usize>,
+    #[serde(default)]
+    pub modes: PolicyModes,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CustomTool {
     pub name: String,
     pub command: String,
+    #[serde(default)]
     pub args: Vec<String>,
+    #[serde(default = "default_enabled")]
     pub enabled: bool,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    #[serde(default)]
+    pub parser: Option<String>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_excluded_files() -> Vec<String> {
+    vec!["*.min.js".to_string(), "*.lock".to_string()]
+}
+
+// Build output is skipped even when a config omits the key and no .gitignore covers it.
+fn default_excluded_dirs() -> Vec<String> {
+    vec![
+        "node_modules".to_string(),
+        ".git".to_string(),
+        "__pycache__".to_string(),
+        "target".to_string(),
+    ]
 }
 
 impl YamlConfig {
@@ -54,8 +160,7 @@ impl YamlConfig {
 
     pub fn save(&self, path: &Path) -> Result<()> {
         let yaml = serde_yaml::to_string(self)?;
-        std::fs::write(path, yaml)?;
-        Ok(())
+        FileUtils::write_file_with(path, &yaml, &WriteOptions::internal())
     }
 
     pub fn validate(&self) -> Result<()> {
@@ -86,21 +191,23 @@ impl YamlConfig {
     }
 
     pub fn should_exclude_file(&self, file_path: &str) -> bool {
-        for pattern in &self.excluded_files {
-            if file_path.contains(pattern) {
-                return true;
+        match GlobRules::new(&self.excluded_files) {
+            Ok(rules) => rules.matches_file(file_path),
+            Err(e) => {
+                tracing::warn!("Invalid excluded_files pattern: {}", e);
+                false
             }
         }
-        false
     }
 
     pub fn should_exclude_dir(&self, dir_path: &str) -> bool {
-        for pattern in &self.excluded_dirs {
-            if dir_path.contains(pattern) {
-                return true;
+        match GlobRules::new(&self.excluded_dirs) {
+            Ok(rules) => rules.matches_dir(dir_path),
+            Err(e) => {
+                tracing::warn!("Invalid excluded_dirs pattern: {}", e);
+                false
             }
         }
-        false
     }
 }
 
@@ -112,20 +219,16 @@ impl Default for YamlConfig {
             ollama_model: "mistral".to_string(),
             openai_api_key: None,
             anthropic_api_key: None,
+            embedding_model: None,
             interactive_mode: false,
             auto_fix_enabled: false,
             max_retry_attempts: 3,
-            excluded_files: vec![
-                "*.min.js".to_string(),
-                "*.lock".to_string(),
-            ],
-            excluded_dirs: vec![
-                "node_modules".to_string(),
-                ".git".to_string(),
-                "__pycache__".to_string(),
-                "target".to_string(),
-            ],
+            excluded_files: default_excluded_files(),
+            excluded_dirs: default_excluded_dirs(),
             custom_tools: HashMap::new(),
+            sandbox: SandboxConfig::default(),
+            policy: PolicyConfig::default(),
+            test_suites: Vec::new(),
         }
     }
 }
@@ -0,0 +1,213 @@
+use crate::core::tools::ToolRegistry;
+use crate::index::RepoMap;
+use crate::llm::client::LLMClient;
+use anyhow::Result;
+use regex::Regex;
+use std::collections::HashMap;
+use std::path::Path;
+
+const MAX_TOOL_ROUNDS: usize = 8;
+const MAX_TOOL_OUTPUT_CHARS: usize = 8000;
+
+const AGENT_SYSTEM_PROMPT: &str = "You are CodeAgent, a coding assistant working inside the user's project. \
+When you need to look something up or change code, call a tool by replying with a fenced block tagged `tool` \
+that holds one JSON object, for example:\n\
+```tool\n{\"name\": \"find_symbol\", \"args\": {\"name\": \"validate_token\"}}\n```\n\
+You may call several tools in one reply. Their results come back in the next message. \
+Preview changes before applying them, and answer without a tool block once you are done.";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolCall {
+    pub name: String,
+    pub args: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ToolOutcome {
+    pub call: ToolCall,
+    pub output: String,
+    pub success: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AgentTurn {
+    pub answer: String,
+    pub tool_calls: Vec<ToolOutcome>,
+}
+
+/// Lets the model call the project's tools: each reply is scanned for tool blocks,
+/// the calls run through the registry and their output is sent back until the model
+/// answers without asking for more.
+pub struct Agent {
+    registry: ToolRegistry,
+    max_rounds: usize,
+    repo_map: String,
+}
+
+impl Agent {
+    pub fn new(registry: ToolRegistry) -> Self {
+        Agent {
+            registry,
+            max_rounds: MAX_TOOL_ROUNDS,
+            repo_map: String::new(),
+        }
+    }
+
+    pub fn for_project(project_root: &Path) -> Self {
+        let mut agent = Self::new(ToolRegistry::for_project(project_root.to_path_buf()));
+        agent.repo_map = RepoMap::system_prompt_section(project_root);
+        agent
+    }
+
+    pub fn with_max_rounds(mut self, max_rounds: usize) -> Self {
+        self.max_rounds = max_rounds;
+        self
+    }
+
+    pub fn system_prompt(&self) -> String {
+        format!("{}\n\n{}{}", AGENT_SYSTEM_PROMPT, self.registry.describe_for_prompt(), self.repo_map)
+    }
+
+    pub async fn run(&self, client: &dyn LLMClient, prompt: &str, context: &str) -> Result<AgentTurn> {
+        let system = self.system_prompt();
+        let mut transcript = if context.is_empty() {
+            format!("User: {}", prompt)
+        } else {
+            format!("{}\n\nUser: {}", context, prompt)
+        };
+        let mut turn = AgentTurn::default();
+
+        for round in 0..=self.max_rounds {
+            let response = client.generate_with_context(&system, &transcript).await?;
+            let calls = Self::parse_tool_calls(&response.content);
+            if calls.is_empty() || round == self.max_rounds {
+                turn.answer = Self::strip_tool_blocks(&response.content);
+                if !calls.is_empty() {
+                    turn.answer.push_str(&format!("\n\n(Stopped after {} rounds of tool calls.)", self.max_rounds));
+                }
+                return Ok(turn);
+            }
+
+            transcript.push_str(&format!("\n\nAssistant: {}\n\nTool results:", response.content.trim()));
+            for call in calls {
+                tracing::info!("Agent tool call: {} {:?}", call.name, call.args);
+                let (success, output) = match self.registry.execute(&call.name, &call.args) {
+                    Ok(output) => (true, output),
+                    Err(e) => (false, format!("Error: {}", e)),
+                };
+                let shown: String = output.chars().take(MAX_TOOL_OUTPUT_CHARS).collect();
+                transcript.push_str(&format!("\n[{}]\n{}", call.name, shown.trim_end()));
+                turn.tool_calls.push(ToolOutcome { call, output, success });
+            }
+        }
+
+        Ok(turn)
+    }
+
+    /// Tool blocks are ```tool fences holding `{"name": ..., "args": {...}}`; argument
+    /// values that are not strings are passed on in their JSON form.
+    pub fn parse_tool_calls(response: &str) -> Vec<ToolCall> {
+        let block_re = Regex::new(r"(?s)```tool[ \t]*\r?\n(.*?)```").unwrap();
+        block_re
+            .captures_iter(response)
+            .filter_map(|cap| {
+                let value: serde_json::Value = match serde_json::from_str(cap[1].trim()) {
+                    Ok(value) => value,
+                    Err(e) => {
+                        tracing::warn!("Ignoring malformed tool block: {}", e);
+                        return None;
+                    }
+                };
+                let name = value.get("name")?.as_str()?.to_string();
+                let args = value
+                    .get("args")
+                    .and_then(|a| a.as_object())
+                    .map(|args| {
+                        args.iter()
+                            .map(|(key, value)| {
+                                let value = match value {
+                                    serde_json::Value::String(s) => s.clone(),
+                                    other => other.to_string(),
+                                };
+                                (key.clone(), value)
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                Some(ToolCall { name, args })
+            })
+            .collect()
+    }
+
+    fn strip_tool_blocks(response: &str) -> String {
+        let block_re = Regex::new(r"(?s)```tool[ \t]*\r?\n.*?```").unwrap();
+        block_re.replace_all(response, "").trim().to_string()
+    }
+}
+
+impl ToolOutcome {
+    pub fn summary(&self) -> String {
+        let mut args: Vec<String> = self
+            .call
+            .args
+            .iter()
+            .map(|(key, value)| {
+                let value = value.lines().next().unwrap_or_default();
+                format!("{}={}", key, value.chars().take(60).collect::<String>())
+            })
+            .collect();
+        args.sort();
+        let status = if self.success { "✓" } else { "✗" };
+        let first_line = self.output.lines().next().unwrap_or_default();
+        format!("{} {}({}) → {}", status, self.call.name, args.join(", "), first_line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::tools::{AgentTool, ToolParameter, ToolSpec};
+    use crate::llm::client::ScriptedClient;
+
+    struct EchoTool;
+
+    impl AgentTool for EchoTool {
+        fn spec(&self) -> ToolSpec {
+            ToolSpec {
+                name: "echo".to_string(),
+                description: "Echo the text back".to_string(),
+                parameters: vec![ToolParameter {
+                    name: "text".to_string(),
+                    description: "Text to echo".to_string(),
+                    required: true,
+                }],
+            }
+        }
+
+        fn execute(&self, args: &HashMap<String, String>) -> Result<String> {
+            Ok(format!("echoed {}", args["text"]))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tool_calls_are_executed_and_fed_back() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(EchoTool));
+        let agent = Agent::new(registry);
+        assert!(agent.system_prompt().contains("- echo: Echo the text back"));
+
+        let client = ScriptedClient::new(&[
+            "Let me check.\n```tool\n{\"name\": \"echo\", \"args\": {\"text\": \"hi\"}}\n```\n```tool\n{\"name\": \"missing\", \"args\": {\"n\": 3}}\n```",
+            "The tool said hi.",
+        ]);
+        let turn = agent.run(&client, "say hi", "").await.unwrap();
+
+        assert_eq!(turn.answer, "The tool said hi.");
+        assert_eq!(turn.tool_calls.len(), 2);
+        assert!(turn.tool_calls[0].success);
+        assert_eq!(turn.tool_calls[1].call.args["n"], "3");
+        assert!(turn.tool_calls[1].output.contains("Unknown tool: missing"));
+        let prompts = client.prompts.lock().unwrap();
+        assert!(prompts[1].contains("Tool results:\n[echo]\nechoed hi\n[missing]\nError: Unknown tool: missing"));
+    }
+}
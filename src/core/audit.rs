@@ -0,0 +1,342 @@
+use crate::utils::FileUtils;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+static AUDIT: OnceLock<AuditLog> = OnceLock::new();
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum AuditEvent {
+    RunStarted {
+        argv: Vec<String>,
+        cwd: String,
+    },
+    LlmCall {
+        provider: String,
+        model: String,
+        prompt_hash: String,
+        prompt_tokens: Option<u32>,
+        completion_tokens: Option<u32>,
+        duration_ms: u64,
+        error: Option<String>,
+    },
+    FileWrite {
+        path: String,
+        before_hash: Option<String>,
+        after_hash: Option<String>,
+    },
+    Command {
+        argv: Vec<String>,
+        cwd: String,
+        exit_code: Option<i32>,
+        timed_out: bool,
+        duration_ms: u64,
+        error: Option<String>,
+    },
+    Git {
+        operation: String,
+        detail: String,
+        error: Option<String>,
+    },
+    GitHub {
+        operation: String,
+        error: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub timestamp: String,
+    pub run_id: String,
+    #[serde(flatten)]
+    pub event: AuditEvent,
+}
+
+/// Append-only JSON lines log at `.codeagent/audit.jsonl`. Each process is one run;
+/// its `run_started` line is written lazily with the first recorded event.
+pub struct AuditLog {
+    root: PathBuf,
+    run_id: String,
+    started: AtomicBool,
+    lock: Mutex<()>,
+}
+
+impl AuditLog {
+    pub fn new(root: &Path, run_id: &str) -> Self {
+        AuditLog {
+            root: root.to_path_buf(),
+            run_id: run_id.to_string(),
+            started: AtomicBool::new(false),
+            lock: Mutex::new(()),
+        }
+    }
+
+    pub fn path(root: &Path) -> PathBuf {
+        root.join(".codeagent").join("audit.jsonl")
+    }
+
+    /// Enables auditing for this process; later calls are ignored.
+    pub fn init(root: &Path) -> &'static AuditLog {
+        AUDIT.get_or_init(|| {
+            let run_id = format!(
+                "{}-{:05}",
+                chrono::Local::now().format("%Y%m%d-%H%M%S"),
+                std::process::id() % 100_000
+            );
+            Self::new(root, &run_id)
+        })
+    }
+
+    pub fn current() -> Option<&'static AuditLog> {
+        AUDIT.get()
+    }
+
+    pub fn run_id(&self) -> &str {
+        &self.run_id
+    }
+
+    /// Records to the process-wide log, if one was initialised. Failing to audit
+    /// never fails the action itself.
+    pub fn record(event: AuditEvent) {
+        if let Some(log) = Self::current() {
+            if let Err(e) = log.append(event) {
+                tracing::warn!("Could not write audit log: {}", e);
+            }
+        }
+    }
+
+    pub fn append(&self, event: AuditEvent) -> Result<()> {
+        let _guard = self.lock.lock().map_err(|_| anyhow::anyhow!("Audit log lock poisoned"))?;
+        let path = Self::path(&self.root);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(&path)?;
+
+        let mut lines = String::new();
+        if !self.started.swap(true, Ordering::SeqCst) {
+            let start = AuditEvent::RunStarted {
+                argv: std::env::args().collect(),
+                cwd: std::env::current_dir().unwrap_or_default().display().to_string(),
+            };
+            lines.push_str(&self.line(start)?);
+        }
+        lines.push_str(&self.line(event)?);
+        file.write_all(lines.as_bytes())?;
+        Ok(())
+    }
+
+    fn line(&self, event: AuditEvent) -> Result<String> {
+        let record = AuditRecord {
+            timestamp: chrono::Local::now().to_rfc3339(),
+            run_id: self.run_id.clone(),
+            event,
+        };
+        Ok(format!("{}\n", serde_json::to_string(&record)?))
+    }
+
+    pub fn hash_prompt<'a>(parts: impl IntoIterator<Item = &'a str>) -> String {
+        let text: Vec<&str> = parts.into_iter().collect();
+        FileUtils::hash_bytes(text.join("\n").as_bytes())
+    }
+
+    pub fn llm_call(
+        provider: &str,
+        model: &str,
+        prompt_hash: String,
+        tokens: (Option<u32>, Option<u32>),
+        started: Instant,
+        error: Option<&anyhow::Error>,
+    ) {
+        Self::record(AuditEvent::LlmCall {
+            provider: provider.to_string(),
+            model: model.to_string(),
+            prompt_hash,
+            prompt_tokens: tokens.0,
+            completion_tokens: tokens.1,
+            duration_ms: started.elapsed().as_millis() as u64,
+            error: error.map(|e| e.to_string()),
+        });
+    }
+
+    pub fn read(root: &Path) -> Result<Vec<AuditRecord>> {
+        let path = Self::path(root);
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| anyhow::anyhow!("Could not read {}: {}", path.display(), e))?;
+        let mut records = Vec::new();
+        for (number, line) in content.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+            match serde_json::from_str(line) {
+                Ok(record) => records.push(record),
+                Err(e) => tracing::warn!("Skipping malformed audit line {}: {}", number + 1, e),
+            }
+        }
+        Ok(records)
+    }
+
+    /// Run IDs in the order they first appear, with their event counts.
+    pub fn runs(records: &[AuditRecord]) -> Vec<(String, usize)> {
+        let mut runs: Vec<(String, usize)> = Vec::new();
+        for record in records {
+            match runs.iter_mut().find(|(id, _)| *id == record.run_id) {
+                Some((_, count)) => *count += 1,
+                None => runs.push((record.run_id.clone(), 1)),
+            }
+        }
+        runs
+    }
+
+    /// Resolves `last`, an exact run ID or a unique prefix of one.
+    pub fn resolve_run(records: &[AuditRecord], run: &str) -> Result<String> {
+        let runs = Self::runs(records);
+        if run == "last" || run == "latest" {
+            return runs
+                .last()
+                .map(|(id, _)| id.clone())
+                .ok_or_else(|| anyhow::anyhow!("The audit log is empty"));
+        }
+        if runs.iter().any(|(id, _)| id == run) {
+            return Ok(run.to_string());
+        }
+        let matches: Vec<&String> = runs.iter().map(|(id, _)| id).filter(|id| id.starts_with(run)).collect();
+        match matches.as_slice() {
+            [id] => Ok((*id).clone()),
+            [] => Err(anyhow::anyhow!("No run matching '{}' in the audit log", run)),
+            _ => Err(anyhow::anyhow!("'{}' matches {} runs; use a longer prefix", run, matches.len())),
+        }
+    }
+
+    pub fn render(records: &[AuditRecord], run_id: &str) -> String {
+        let records: Vec<&AuditRecord> = records.iter().filter(|r| r.run_id == run_id).collect();
+        let mut out = format!("Run {} ({} events)\n", run_id, records.len());
+        for record in records {
+            let time = chrono::DateTime::parse_from_rfc3339(&record.timestamp)
+                .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_else(|_| record.timestamp.clone());
+            let (kind, detail) = Self::describe(&record.event);
+            out.push_str(&format!("{}  {:<8} {}\n", time, kind, detail));
+        }
+        out
+    }
+
+    fn describe(event: &AuditEvent) -> (&'static str, String) {
+        let failed = |error: &Option<String>| match error {
+            Some(e) => format!(" ✗ {}", e),
+            None => String::new(),
+        };
+        match event {
+            AuditEvent::RunStarted { argv, cwd } => ("run", format!("{} (in {})", argv.join(" "), cwd)),
+            AuditEvent::LlmCall {
+                provider,
+                model,
+                prompt_hash,
+                prompt_tokens,
+                completion_tokens,
+                duration_ms,
+                error,
+            } => {
+                let count = |t: &Option<u32>| t.map(|t| t.to_string()).unwrap_or_else(|| "?".to_string());
+                (
+                    "llm",
+                    format!(
+                        "{}/{} prompt {} tokens {}+{} in {}ms{}",
+                        provider,
+                        model,
+                        &prompt_hash[..prompt_hash.len().min(12)],
+                        count(prompt_tokens),
+                        count(completion_tokens),
+                        duration_ms,
+                        failed(error)
+                    ),
+                )
+            }
+            AuditEvent::FileWrite { path, before_hash, after_hash } => {
+                let short = |h: &Option<String>| match h {
+                    Some(h) => h[..h.len().min(12)].to_string(),
+                    None => "∅".to_string(),
+                };
+                ("write", format!("{} {} → {}", path, short(before_hash), short(after_hash)))
+            }
+            AuditEvent::Command {
+                argv,
+                exit_code,
+                timed_out,
+                duration_ms,
+                error,
+                ..
+            } => {
+                let status = match (timed_out, exit_code) {
+                    (true, _) => "timed out".to_string(),
+                    (false, Some(code)) => format!("exit {}", code),
+                    (false, None) => "no exit code".to_string(),
+                };
+                (
+                    "command",
+                    format!("{} ({}, {}ms){}", argv.join(" "), status, duration_ms, failed(error)),
+                )
+            }
+            AuditEvent::Git { operation, detail, error } => ("git", format!("{} {}{}", operation, detail, failed(error))),
+            AuditEvent::GitHub { operation, error } => ("github", format!("{}{}", operation, failed(error))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_append_read_and_render_runs() {
+        let root = std::env::temp_dir().join(format!("codeagent-audit-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+
+        let first = AuditLog::new(&root, "20260101-120000-00001");
+        first
+            .append(AuditEvent::FileWrite {
+                path: "src/lib.rs".to_string(),
+                before_hash: Some("a".repeat(64)),
+                after_hash: Some("b".repeat(64)),
+            })
+            .unwrap();
+        let second = AuditLog::new(&root, "20260102-090000-00002");
+        second
+            .append(AuditEvent::Command {
+                argv: vec!["cargo".to_string(), "test".to_string()],
+                cwd: root.display().to_string(),
+                exit_code: Some(101),
+                timed_out: false,
+                duration_ms: 42,
+                error: None,
+            })
+            .unwrap();
+        second
+            .append(AuditEvent::Git {
+                operation: "commit".to_string(),
+                detail: "\"Fix parser\"".to_string(),
+                error: None,
+            })
+            .unwrap();
+
+        let records = AuditLog::read(&root).unwrap();
+        assert_eq!(records.len(), 5);
+        assert!(matches!(records[0].event, AuditEvent::RunStarted { .. }));
+        assert_eq!(
+            AuditLog::runs(&records),
+            vec![("20260101-120000-00001".to_string(), 2), ("20260102-090000-00002".to_string(), 3)]
+        );
+
+        assert_eq!(AuditLog::resolve_run(&records, "last").unwrap(), "20260102-090000-00002");
+        assert_eq!(AuditLog::resolve_run(&records, "20260101").unwrap(), "20260101-120000-00001");
+        assert!(AuditLog::resolve_run(&records, "2026").is_err());
+
+        let rendered = AuditLog::render(&records, "20260102-090000-00002");
+        assert!(rendered.contains("command  cargo test (exit 101, 42ms)"));
+        assert!(rendered.contains("git      commit \"Fix parser\""));
+        assert!(!rendered.contains("src/lib.rs"));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -1,5 +1,6 @@
 use std::path::PathBuf;
 use anyhow::Result;
+use crate::utils::FileUtils;
 
 pub struct ProjectConfig {
     pub project_root: PathBuf,
@@ -45,9 +46,52 @@ pub fn init_project(path: &std::path::Path) -> Result<()> {
 model_provider: ollama
 ollama_base_url: "http://localhost:11434"
 ollama_model: "mistral"
+
+# Commands the agent can run as plan steps or tools. {file} and {project_root}
+# are substituted; parser is one of clippy, eslint, pylint, cargo-test, jest, pytest, go-test.
+# custom_tools:
+#   lint:
+#     name: lint
+#     command: ./scripts/lint.sh
+#     args: ["{file}"]
+#     timeout_secs: 60
+#     parser: clippy
+
+# sandbox:
+#   network_isolation: false
+#   cpu_seconds: 600
+#   memory_mb: 4096
+#   max_output_bytes: 4194304
+#   env_allowlist: ["DATABASE_URL"]
+
+# policy:
+#   read:
+#     deny: [".env", "secrets/"]
+#   write:
+#     allow: ["src/**", "tests/**"]
+#     deny: ["Cargo.lock"]
+#   commands:
+#     allow: ["cargo", "npm test", "pytest", "go test"]
+#     deny: ["cargo publish"]
+#   max_files_changed: 20
+#   modes:
+#     write: allow
+#     command: allow
+#     git_commit: ask
+#     github: deny
+
+# Test suites are detected from Cargo.toml, package.json, go.mod, pytest config and
+# Makefiles; listing them here replaces detection.
+# test_suites:
+#   - framework: pytest
+#     path: backend
+#     command: python -m pytest -v
+#   - framework: cargo
+#     command: cargo nextest run --profile ci
+#     junit: target/nextest/ci/junit.xml   # read results from a JUnit report
 "#;
 
-    std::fs::write(&config_path, default_config)?;
+    FileUtils::write_file(&config_path, default_config)?;
     tracing::info!("Initialized CodeAgent project at {:?}", path);
     
     Ok(())
@@ -1,21 +1,36 @@
 use anyhow::Result;
-use crate::core::config::ProjectConfig;
-use crate::core::planner::{TaskDecomposer, ExecutionContext};
-use crate::utils::FileUtils;
+use crate::config::yaml_parser::YamlConfig;
+use crate::core::patch::{FileStatus, PatchApplier, PatchParser};
+use crate::core::planner::{TaskDecomposer, ExecutionContext, ExecutionStep, StepActionType, StepResult};
+use crate::core::policy::PolicyEnforcer;
+use crate::error_correction::healing::{HealingStatus, SelfHealingLoop};
+use crate::index::{RepoMap, SemanticIndex};
+use crate::integrations::custom_tools::CustomToolRunner;
+use crate::integrations::test_runner::TestRunner;
+use crate::llm::client::LLMClient;
+use crate::utils::walker::ProjectWalker;
+use crate::utils::{FileUtils, WriteOptions};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Instant;
 
-pub async fn run_task(prompt: &str, dry_run: bool) -> Result<()> {
-    let project_root = std::env::current_dir()?;
-    let config = ProjectConfig::load(project_root.clone())?;
+const MAX_CONTEXT_FILES: usize = 6;
+const MAX_FILE_CHARS: usize = 8000;
+const SEMANTIC_CONTEXT_CHUNKS: usize = 4;
+const SEMANTIC_CONTEXT_CHARS: usize = 3000;
+
+const MODIFY_SYSTEM_PROMPT: &str = "You edit an existing project to carry out the user's task. \
+Reply only with SEARCH/REPLACE blocks (the file path on its own line, then <<<<<<< SEARCH, the exact \
+lines to replace, =======, the new lines, >>>>>>> REPLACE) or with a unified diff. Change as little as possible.";
+
+pub async fn run_task(project_root: &Path, prompt: &str, dry_run: bool, client: &dyn LLMClient) -> Result<ExecutionContext> {
+    let config = YamlConfig::load(&project_root.join(".codeagent.yml")).unwrap_or_default();
 
     tracing::info!("Starting task execution");
     tracing::info!("Prompt: {}", prompt);
     tracing::info!("Dry run mode: {}", dry_run);
 
-    let provider_name = match &config.model_provider {
-        crate::core::config::ModelProvider::Ollama { base_url } => format!("Ollama ({})", base_url),
-        crate::core::config::ModelProvider::OpenAI { .. } => "OpenAI".to_string(),
-        crate::core::config::ModelProvider::Anthropic { .. } => "Anthropic".to_string(),
-    };
+    let provider_name = format!("{} ({})", client.provider_name(), client.model_name());
 
     println!("╔══════════════════════════════════════════════════════════════╗");
     println!("║               CodeAgent - Task Execution (Phase 2)           ║");
@@ -26,45 +41,361 @@ pub async fn run_task(prompt: &str, dry_run: bool) -> Result<()> {
 
     println!("Task: {}\n", prompt);
 
-    let plan = TaskDecomposer::decompose_task(prompt)?;
+    let plan = TaskDecomposer::decompose_task_for_project(prompt, project_root)?;
     
     println!("📋 Execution Plan ({} steps):", plan.steps.len());
     println!("   Complexity: {:?}", plan.total_complexity);
     println!("   Estimated Duration: ~{}ms\n", plan.estimated_duration_ms);
 
     for (i, step) in plan.steps.iter().enumerate() {
-        println!("   [Step {}] {} ({})", 
+        println!("   [Step {}] {} ({:?})", 
             i + 1, 
             step.description,
-            format!("{:?}", step.action_type)
+            step.action_type
         );
         if !step.dependencies.is_empty() {
             println!("            Depends on: {:?}", step.dependencies);
         }
+        if !step.target_files.is_empty() {
+            println!("            Files: {}", step.target_files.join(", "));
+        }
     }
 
     println!("\n📁 Project Analysis:");
-    let rust_files = FileUtils::find_files(&project_root, "rs")?;
-    println!("   Found {} Rust files", rust_files.len());
+    let project_files = ProjectWalker::for_project(project_root).walk()?;
+    let rust_files = project_files
+        .iter()
+        .filter(|p| p.extension().is_some_and(|ext| ext == "rs"))
+        .count();
+    println!("   Found {} files ({} Rust)", project_files.len(), rust_files);
 
     if plan.requires_user_approval {
         println!("\n⚠️  This operation requires careful handling.");
         println!("   Recommended: Run with --dry-run first to preview changes");
     }
 
-    let context = ExecutionContext::new(plan, dry_run);
+    let mut context = ExecutionContext::new(plan, dry_run);
 
-    println!("\n🚀 Execution Status:");
-    println!("   Steps to Execute: {}", context.plan.steps.len());
-    println!("   Progress: {}%", context.progress_percentage());
-    
     if dry_run {
         println!("\n🔍 [DRY RUN MODE] - Changes would be applied but not persisted");
         println!("   All modifications are simulated without actual file changes");
     }
 
-    println!("\n✅ Status: Ready for execution\n");
+    println!("\n🚀 Executing {} steps", context.plan.steps.len());
+    while let Some(step) = context.next_step().cloned() {
+        if !context.can_proceed_to_next() {
+            return Err(anyhow::anyhow!("Step {} depends on {:?}, which did not complete", step.id, step.dependencies));
+        }
+        println!("\n▶ [{}%] {}", context.progress_percentage(), step.description);
+
+        let result = execute_step(&mut context, project_root, prompt, client, &config).await?;
+        if !result.output.trim().is_empty() {
+            println!("{}", result.output.trim_end());
+        }
+        if !result.success {
+            let reason = result.error_message.unwrap_or_else(|| "step failed".to_string());
+            println!("\n❌ {} failed:\n{}", step.id, reason.trim_end());
+            return Err(anyhow::anyhow!("Task stopped at step {}", step.id));
+        }
+    }
+
+    if !context.changes_staged.is_empty() {
+        println!("\n📝 Changed files: {}", context.changes_staged.join(", "));
+    }
+    println!("\n✅ Status: All {} steps completed\n", context.plan.steps.len());
 
     tracing::info!("Task execution completed");
-    Ok(())
+    Ok(context)
+}
+
+/// Runs the context's next step, which the caller has checked is ready.
+pub async fn execute_step(
+    context: &mut ExecutionContext,
+    project_root: &Path,
+    prompt: &str,
+    client: &dyn LLMClient,
+    config: &YamlConfig,
+) -> Result<StepResult> {
+    let step = context
+        .next_step()
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("No remaining steps in plan"))?;
+    let start = Instant::now();
+
+    match &step.action_type {
+        StepActionType::Analyze => {
+            let output = format!("{} relevant files", step.target_files.len());
+            Ok(finish_step(context, &step, true, output, start))
+        }
+        StepActionType::Modify => {
+            let request = modify_prompt(project_root, prompt, &step.target_files).await;
+            tracing::info!("Requesting edits for {} from {}", step.id, client.provider_name());
+            let system = format!("{}{}", MODIFY_SYSTEM_PROMPT, RepoMap::system_prompt_section(project_root));
+            let response = client.generate_with_context(&system, &request).await?;
+            apply_modify_step_with_healing(context, project_root, &response.content, client, config).await
+        }
+        StepActionType::TestRun if context.is_dry_run => {
+            Ok(finish_step(context, &step, true, "Skipped in dry run".to_string(), start))
+        }
+        StepActionType::TestRun => {
+            let report = if context.changes_staged.is_empty() {
+                TestRunner::run_all_tests(project_root).await?
+            } else {
+                TestRunner::run_affected_tests(project_root, &context.changes_staged).await?
+            };
+            let mut result = finish_step(context, &step, report.passed(), report.output(), start);
+            if !result.success {
+                result.error_message = Some(format!("Tests failed (exit code {})", report.exit_code()));
+                context.completed_steps.remove(&step.id);
+            }
+            Ok(result)
+        }
+        StepActionType::Custom(_) if context.is_dry_run => {
+            Ok(finish_step(context, &step, true, "Skipped in dry run".to_string(), start))
+        }
+        StepActionType::Custom(_) => execute_custom_step(context, project_root, config),
+        StepActionType::LintCheck | StepActionType::Commit | StepActionType::Rollback => {
+            let output = format!("Skipped: {:?} steps are not automated", step.action_type);
+            Ok(finish_step(context, &step, true, output, start))
+        }
+    }
+}
+
+fn finish_step(context: &mut ExecutionContext, step: &ExecutionStep, success: bool, output: String, start: Instant) -> StepResult {
+    let result = StepResult {
+        step_id: step.id.clone(),
+        success,
+        output,
+        duration_ms: start.elapsed().as_millis() as u64,
+        error_message: None,
+    };
+    context.mark_step_complete(result.clone());
+    result
+}
+
+async fn modify_prompt(project_root: &Path, prompt: &str, target_files: &[String]) -> String {
+    let mut request = format!("## Task\n{}\n\n", prompt);
+    let policy = PolicyEnforcer::for_project(project_root);
+    for file in target_files.iter().take(MAX_CONTEXT_FILES) {
+        if !policy.can_read(Path::new(file)) {
+            continue;
+        }
+        let Ok(content) = FileUtils::read_file(&project_root.join(file)) else {
+            continue;
+        };
+        let content: String = content.chars().take(MAX_FILE_CHARS).collect();
+        request.push_str(&format!("## {}\n```\n{}\n```\n\n", file, content));
+    }
+
+    let mut semantic = SemanticIndex::for_project(project_root).await;
+    match semantic.refreshed_context(prompt, SEMANTIC_CONTEXT_CHUNKS, SEMANTIC_CONTEXT_CHARS).await {
+        Ok(related) if !related.is_empty() => {
+            request.push_str(&format!("## Related code elsewhere in the project\n{}", related));
+        }
+        Ok(_) => {}
+        Err(e) => tracing::warn!("Could not search the semantic index: {}", e),
+    }
+    request.push_str("Reply with the edits that carry out the task.");
+    request
+}
+
+pub fn apply_modify_step(context: &mut ExecutionContext, project_root: &Path, model_output: &str) -> Result<StepResult> {
+    let step = context
+        .next_step()
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("No remaining steps in plan"))?;
+    if step.action_type != StepActionType::Modify {
+        return Err(anyhow::anyhow!("Step {} is not a Modify step", step.id));
+    }
+
+    let start = Instant::now();
+    let patches = PatchParser::parse(model_output)?;
+    for patch in &patches {
+        if !step.target_files.is_empty() && !step.target_files.iter().any(|f| f == patch.path()) {
+            tracing::warn!("Patch touches {} which is not a target of step {}", patch.path(), step.id);
+        }
+    }
+
+    let applier = PatchApplier::new(project_root).with_write_options(WriteOptions::with_backups(project_root));
+    let report = applier.apply(&patches, context.is_dry_run)?;
+
+    if report.applied {
+        for file in report.files.iter().filter(|f| f.status != FileStatus::Conflict) {
+            if !context.changes_staged.contains(&file.path) {
+                context.changes_staged.push(file.path.clone());
+            }
+        }
+    }
+
+    let result = StepResult {
+        step_id: step.id.clone(),
+        success: !report.has_conflicts(),
+        output: report.diff.clone(),
+        duration_ms: start.elapsed().as_millis() as u64,
+        error_message: report.has_conflicts().then(|| report.conflict_summary()),
+    };
+    if result.success {
+        context.mark_step_complete(result.clone());
+    }
+    Ok(result)
+}
+
+pub async fn apply_modify_step_with_healing(
+    context: &mut ExecutionContext,
+    project_root: &Path,
+    model_output: &str,
+    client: &dyn LLMClient,
+    config: &YamlConfig,
+) -> Result<StepResult> {
+    let mut result = apply_modify_step(context, project_root, model_output)?;
+    if !result.success || context.is_dry_run || !config.auto_fix_enabled {
+        return Ok(result);
+    }
+
+    let start = Instant::now();
+    let report = SelfHealingLoop::new(project_root, config)
+        .run(client, &context.changes_staged)
+        .await?;
+    for file in &report.changed_files {
+        if !context.changes_staged.contains(file) {
+            context.changes_staged.push(file.clone());
+        }
+    }
+
+    let summary = report.summary();
+    result.output.push_str(&format!("\n{}\n", summary));
+    result.duration_ms += start.elapsed().as_millis() as u64;
+    if report.status != HealingStatus::Passed {
+        result.success = false;
+        result.error_message = Some(format!("{}\n{}", summary, report.last_output));
+    }
+    context.completed_steps.insert(result.step_id.clone(), result.clone());
+    Ok(result)
+}
+
+pub fn execute_custom_step(context: &mut ExecutionContext, project_root: &Path, config: &YamlConfig) -> Result<StepResult> {
+    let step = context
+        .next_step()
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("No remaining steps in plan"))?;
+    let StepActionType::Custom(name) = &step.action_type else {
+        return Err(anyhow::anyhow!("Step {} is not a custom tool step", step.id));
+    };
+    let tool = config
+        .custom_tools
+        .values()
+        .find(|t| &t.name == name)
+        .ok_or_else(|| anyhow::anyhow!("Custom tool '{}' is not configured in .codeagent.yml", name))?;
+
+    let start = Instant::now();
+    let per_file = CustomToolRunner::placeholders(tool).iter().any(|p| p == "file");
+    let runs: Vec<HashMap<String, String>> = if per_file {
+        step.target_files
+            .iter()
+            .map(|file| HashMap::from([("file".to_string(), file.clone())]))
+            .collect()
+    } else {
+        vec![HashMap::new()]
+    };
+
+    let mut output = String::new();
+    let mut success = true;
+    for args in &runs {
+        let run = CustomToolRunner::run(tool, project_root, args)?;
+        success &= run.success();
+        output.push_str(&run.to_text());
+    }
+
+    let result = StepResult {
+        step_id: step.id.clone(),
+        success,
+        error_message: (!success).then(|| format!("Custom tool '{}' reported failures", name)),
+        output,
+        duration_ms: start.elapsed().as_millis() as u64,
+    };
+    context.mark_step_complete(result.clone());
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::client::ScriptedClient;
+
+    #[tokio::test]
+    async fn test_run_task_applies_the_model_patch() {
+        let root = std::env::temp_dir().join(format!("codeagent-executor-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/greet.py"), "def greet():\n    return 'helo'\n").unwrap();
+        std::fs::write(
+            root.join(".codeagent.yml"),
+            "model_provider: ollama\ncustom_tools:\n  spelling:\n    name: spellcheck\n    command: sh\n    args: [\"-c\", \"grep hello src/greet.py\"]\n",
+        )
+        .unwrap();
+
+        let client = ScriptedClient::new(&[
+            "src/greet.py\n<<<<<<< SEARCH\n    return 'helo'\n=======\n    return 'hello'\n>>>>>>> REPLACE\n",
+        ]);
+        let context = run_task(&root, "refactor greet to fix its spelling, then run spellcheck", false, &client).await.unwrap();
+
+        assert_eq!(context.changes_staged, vec!["src/greet.py"]);
+        assert_eq!(context.completed_steps.len(), context.plan.steps.len());
+        let custom = context.completed_steps.values().find(|r| r.step_id.starts_with("custom_")).unwrap();
+        assert!(custom.output.contains("return 'hello'"));
+        let prompt = client.prompts.lock().unwrap()[0].clone();
+        assert!(prompt.contains("return 'helo'"));
+        assert!(prompt.contains("## Related code elsewhere in the project\n// src/greet.py:1"));
+        let system = client.system_prompts.lock().unwrap()[0].clone();
+        assert!(system.contains("## Repository map\n") && system.contains("greet.py"));
+        assert_eq!(std::fs::read_to_string(root.join("src/greet.py")).unwrap(), "def greet():\n    return 'hello'\n");
+
+        let client = ScriptedClient::new(&["no edits here"]);
+        assert!(run_task(&root, "refactor greet", false, &client).await.is_err());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn test_modify_step_heals_a_broken_build() {
+        let root = std::env::temp_dir().join(format!("codeagent-healing-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(
+            root.join("Cargo.toml"),
+            "[package]\nname = \"healdemo\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[workspace]\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("src/lib.rs"), "pub fn double(x: i32) -> i32 {\n    x * 2\n}\n").unwrap();
+        std::fs::write(
+            root.join(".codeagent.yml"),
+            "model_provider: ollama\nauto_fix_enabled: true\nmax_retry_attempts: 2\n",
+        )
+        .unwrap();
+        let config = YamlConfig::load(&root.join(".codeagent.yml")).unwrap();
+
+        // The first reply breaks the build; the second is the fix the loop asks for.
+        let client = ScriptedClient::new(&[
+            "src/lib.rs\n<<<<<<< SEARCH\n    x * 2\n=======\n    y * 2\n>>>>>>> REPLACE\n",
+            "src/lib.rs\n<<<<<<< SEARCH\n    y * 2\n=======\n    x * 2\n>>>>>>> REPLACE\n",
+        ]);
+        let plan = TaskDecomposer::decompose_task_for_project("refactor double", &root).unwrap();
+        let mut context = ExecutionContext::new(plan, false);
+        context.mark_step_complete(StepResult {
+            step_id: "analyze_0".to_string(),
+            success: true,
+            output: String::new(),
+            duration_ms: 0,
+            error_message: None,
+        });
+
+        let result = execute_step(&mut context, &root, "refactor double", &client, &config).await.unwrap();
+
+        assert!(result.success, "{:?}", result.error_message);
+        assert!(result.output.contains("Build and tests passed after 1 fix attempts"));
+        assert!(context.completed_steps.contains_key("modify_1"));
+        let prompts = client.prompts.lock().unwrap();
+        assert!(prompts[1].contains("undefined_reference"));
+        assert_eq!(std::fs::read_to_string(root.join("src/lib.rs")).unwrap(), "pub fn double(x: i32) -> i32 {\n    x * 2\n}\n");
+        let _ = std::fs::remove_dir_all(&root);
+    }
 }
@@ -1,4 +1,10 @@
+pub mod agent;
+pub mod audit;
 pub mod config;
 pub mod executor;
 pub mod planner;
 pub mod context;
+pub mod patch;
+pub mod policy;
+pub mod refactor;
+pub mod tools;
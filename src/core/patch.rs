@@ -0,0 +1,605 @@
+use crate::utils::{FileUtils, WriteOptions};
+use anyhow::Result;
+use similar::TextDiff;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+const SEARCH_MARKER: &str = "<<<<<<< SEARCH";
+const DIVIDER_MARKER: &str = "=======";
+const REPLACE_MARKER: &str = ">>>>>>> REPLACE";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HunkLine {
+    Context(String),
+    Removed(String),
+    Added(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hunk {
+    pub old_start: usize,
+    pub lines: Vec<HunkLine>,
+}
+
+impl Hunk {
+    fn before(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter_map(|l| match l {
+                HunkLine::Context(s) | HunkLine::Removed(s) => Some(s.as_str()),
+                HunkLine::Added(_) => None,
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchReplaceBlock {
+    pub search: String,
+    pub replace: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilePatch {
+    Modify { path: String, hunks: Vec<Hunk> },
+    Replace { path: String, blocks: Vec<SearchReplaceBlock> },
+    Create { path: String, content: String },
+    Delete { path: String },
+}
+
+impl FilePatch {
+    pub fn path(&self) -> &str {
+        match self {
+            FilePatch::Modify { path, .. }
+            | FilePatch::Replace { path, .. }
+            | FilePatch::Create { path, .. }
+            | FilePatch::Delete { path } => path,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HunkOutcome {
+    pub index: usize,
+    pub applied: bool,
+    pub line: Option<usize>,
+    pub offset: isize,
+    pub fuzzy: bool,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileStatus {
+    Modified,
+    Created,
+    Deleted,
+    Conflict,
+}
+
+#[derive(Debug, Clone)]
+pub struct FilePatchReport {
+    pub path: String,
+    pub status: FileStatus,
+    pub hunks: Vec<HunkOutcome>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PatchReport {
+    pub files: Vec<FilePatchReport>,
+    pub diff: String,
+    pub applied: bool,
+}
+
+impl PatchReport {
+    pub fn has_conflicts(&self) -> bool {
+        self.files.iter().any(|f| f.status == FileStatus::Conflict)
+    }
+
+    pub fn conflict_summary(&self) -> String {
+        let mut summary = String::new();
+        for file in self.files.iter().filter(|f| f.status == FileStatus::Conflict) {
+            for hunk in file.hunks.iter().filter(|h| !h.applied) {
+                summary.push_str(&format!("{} hunk {}: {}\n", file.path, hunk.index + 1, hunk.message));
+            }
+        }
+        summary
+    }
+}
+
+pub struct PatchParser;
+
+impl PatchParser {
+    pub fn parse(text: &str) -> Result<Vec<FilePatch>> {
+        let mut patches = Self::parse_unified(text)?;
+        patches.extend(Self::parse_search_replace(text)?);
+        if patches.is_empty() {
+            return Err(anyhow::anyhow!("No unified diff or SEARCH/REPLACE blocks found"));
+        }
+        Ok(patches)
+    }
+
+    pub fn parse_unified(text: &str) -> Result<Vec<FilePatch>> {
+        let lines: Vec<&str> = text.lines().collect();
+        let mut patches = Vec::new();
+        let mut i = 0;
+
+        while i < lines.len() {
+            if !(lines[i].starts_with("--- ") && lines.get(i + 1).is_some_and(|l| l.starts_with("+++ "))) {
+                i += 1;
+                continue;
+            }
+
+            let old_path = Self::diff_path(&lines[i][4..]);
+            let new_path = Self::diff_path(&lines[i + 1][4..]);
+            i += 2;
+
+            let mut hunks = Vec::new();
+            while i < lines.len() && lines[i].starts_with("@@") {
+                let old_start = Self::hunk_start(lines[i])
+                    .ok_or_else(|| anyhow::anyhow!("Malformed hunk header: {}", lines[i]))?;
+                i += 1;
+
+                let mut hunk_lines = Vec::new();
+                while i < lines.len() && !lines[i].starts_with("@@") && !Self::is_file_header(&lines, i) {
+                    let line = lines[i];
+                    match line.chars().next() {
+                        Some('+') => hunk_lines.push(HunkLine::Added(line[1..].to_string())),
+                        Some('-') => hunk_lines.push(HunkLine::Removed(line[1..].to_string())),
+                        Some(' ') => hunk_lines.push(HunkLine::Context(line[1..].to_string())),
+                        None => hunk_lines.push(HunkLine::Context(String::new())),
+                        Some('\\') => {}
+                        _ => break,
+                    }
+                    i += 1;
+                }
+                while matches!(hunk_lines.last(), Some(HunkLine::Context(s)) if s.is_empty()) {
+                    hunk_lines.pop();
+                }
+                hunks.push(Hunk { old_start, lines: hunk_lines });
+            }
+
+            let patch = match (old_path, new_path) {
+                (None, Some(path)) => FilePatch::Create {
+                    path,
+                    content: Self::added_content(&hunks),
+                },
+                (Some(path), None) => FilePatch::Delete { path },
+                (Some(_), Some(path)) => FilePatch::Modify { path, hunks },
+                (None, None) => continue,
+            };
+            patches.push(patch);
+        }
+
+        Ok(patches)
+    }
+
+    pub fn parse_search_replace(text: &str) -> Result<Vec<FilePatch>> {
+        let lines: Vec<&str> = text.lines().collect();
+        let mut blocks: Vec<(String, SearchReplaceBlock)> = Vec::new();
+        let mut i = 0;
+        let mut previous_end = 0;
+
+        while i < lines.len() {
+            if lines[i].trim_end() != SEARCH_MARKER {
+                i += 1;
+                continue;
+            }
+
+            let path = lines[previous_end..i]
+                .iter()
+                .rev()
+                .map(|l| l.trim())
+                .find(|l| !l.is_empty() && !l.starts_with("```"))
+                .map(|l| l.trim_matches('`').trim_end_matches(':').to_string())
+                .or_else(|| blocks.last().map(|(p, _)| p.clone()))
+                .ok_or_else(|| anyhow::anyhow!("SEARCH block without a file path"))?;
+
+            let divider = (i + 1..lines.len())
+                .find(|&j| lines[j].trim_end() == DIVIDER_MARKER)
+                .ok_or_else(|| anyhow::anyhow!("SEARCH block for {} is missing =======", path))?;
+            let end = (divider + 1..lines.len())
+                .find(|&j| lines[j].trim_end() == REPLACE_MARKER)
+                .ok_or_else(|| anyhow::anyhow!("SEARCH block for {} is missing >>>>>>> REPLACE", path))?;
+
+            blocks.push((
+                path,
+                SearchReplaceBlock {
+                    search: lines[i + 1..divider].join("\n"),
+                    replace: lines[divider + 1..end].join("\n"),
+                },
+            ));
+            i = end + 1;
+            previous_end = i;
+        }
+
+        let mut patches: Vec<FilePatch> = Vec::new();
+        for (path, block) in blocks {
+            match patches.iter_mut().find(|p| p.path() == path) {
+                Some(FilePatch::Replace { blocks, .. }) => blocks.push(block),
+                _ => patches.push(FilePatch::Replace { path, blocks: vec![block] }),
+            }
+        }
+        Ok(patches)
+    }
+
+    fn is_file_header(lines: &[&str], i: usize) -> bool {
+        lines[i].starts_with("diff --git")
+            || (lines[i].starts_with("--- ") && lines.get(i + 1).is_some_and(|l| l.starts_with("+++ ")))
+    }
+
+    fn diff_path(raw: &str) -> Option<String> {
+        let path = raw.split('\t').next().unwrap_or(raw).trim();
+        if path == "/dev/null" {
+            return None;
+        }
+        Some(
+            path.strip_prefix("a/")
+                .or_else(|| path.strip_prefix("b/"))
+                .unwrap_or(path)
+                .to_string(),
+        )
+    }
+
+    fn hunk_start(header: &str) -> Option<usize> {
+        let old = header.split_whitespace().nth(1)?.strip_prefix('-')?;
+        old.split(',').next()?.parse().ok()
+    }
+
+    fn added_content(hunks: &[Hunk]) -> String {
+        let mut content = String::new();
+        for line in hunks.iter().flat_map(|h| h.lines.iter()) {
+            if let HunkLine::Added(s) | HunkLine::Context(s) = line {
+                content.push_str(s);
+                content.push('\n');
+            }
+        }
+        content
+    }
+}
+
+pub struct PatchApplier {
+    root: PathBuf,
+    write_options: WriteOptions,
+}
+
+enum Planned {
+    Write(String),
+    Delete,
+}
+
+impl PatchApplier {
+    pub fn new(root: &Path) -> Self {
+        PatchApplier {
+            root: root.to_path_buf(),
+            write_options: WriteOptions::for_project(root),
+        }
+    }
+
+    pub fn with_write_options(mut self, options: WriteOptions) -> Self {
+        self.write_options = options;
+        self
+    }
+
+    pub fn apply_text(&self, model_output: &str, dry_run: bool) -> Result<PatchReport> {
+        let patches = PatchParser::parse(model_output)?;
+        self.apply(&patches, dry_run)
+    }
+
+    pub fn apply(&self, patches: &[FilePatch], dry_run: bool) -> Result<PatchReport> {
+        let mut reports = Vec::new();
+        let mut planned: Vec<(String, Planned)> = Vec::new();
+        let mut originals: HashMap<String, String> = HashMap::new();
+        let mut diff = String::new();
+
+        for patch in patches {
+            let path = patch.path().to_string();
+            let full_path = self.root.join(&path);
+            let current = match planned.iter().rev().find(|(p, _)| *p == path) {
+                Some((_, Planned::Write(content))) => Some(content.clone()),
+                Some((_, Planned::Delete)) => None,
+                None => FileUtils::read_file(&full_path).ok(),
+            };
+            if let Some(original) = &current {
+                originals.entry(path.clone()).or_insert_with(|| original.clone());
+            }
+
+            let (status, hunks, result) = match (patch, current) {
+                (FilePatch::Create { content, .. }, None) => (FileStatus::Created, Vec::new(), Some(Planned::Write(content.clone()))),
+                (FilePatch::Create { .. }, Some(_)) => (
+                    FileStatus::Conflict,
+                    vec![Self::failure(0, "file already exists".to_string())],
+                    None,
+                ),
+                (FilePatch::Delete { .. }, Some(_)) => (FileStatus::Deleted, Vec::new(), Some(Planned::Delete)),
+                (FilePatch::Replace { blocks, .. }, None) if blocks.iter().all(|b| b.search.trim().is_empty()) => {
+                    let content: Vec<&str> = blocks.iter().map(|b| b.replace.as_str()).collect();
+                    (FileStatus::Created, Vec::new(), Some(Planned::Write(content.join("\n") + "\n")))
+                }
+                (_, None) => (
+                    FileStatus::Conflict,
+                    vec![Self::failure(0, "file does not exist".to_string())],
+                    None,
+                ),
+                (FilePatch::Modify { hunks, .. }, Some(content)) => Self::apply_hunks(&content, hunks),
+                (FilePatch::Replace { blocks, .. }, Some(content)) => Self::apply_blocks(&content, blocks),
+            };
+
+            if let Some(result) = result {
+                planned.retain(|(p, _)| *p != path);
+                planned.push((path.clone(), result));
+            }
+            reports.push(FilePatchReport { path, status, hunks });
+        }
+
+        for (path, result) in &planned {
+            let old = originals.get(path).map(|s| s.as_str()).unwrap_or("");
+            let new = match result {
+                Planned::Write(content) => content.as_str(),
+                Planned::Delete => "",
+            };
+            let old_header = if originals.contains_key(path) { format!("a/{}", path) } else { "/dev/null".to_string() };
+            let new_header = match result {
+                Planned::Delete => "/dev/null".to_string(),
+                Planned::Write(_) => format!("b/{}", path),
+            };
+            diff.push_str(
+                &TextDiff::from_lines(old, new)
+                    .unified_diff()
+                    .context_radius(3)
+                    .header(&old_header, &new_header)
+                    .to_string(),
+            );
+        }
+
+        let mut report = PatchReport {
+            files: reports,
+            diff,
+            applied: false,
+        };
+        if dry_run || report.has_conflicts() {
+            return Ok(report);
+        }
+
+        let writes: Vec<(PathBuf, String)> = planned
+            .iter()
+            .filter_map(|(path, result)| match result {
+                Planned::Write(content) => Some((self.root.join(path), content.clone())),
+                Planned::Delete => None,
+            })
+            .collect();
+
+        let removals: Vec<PathBuf> = planned
+            .iter()
+            .filter(|(_, result)| matches!(result, Planned::Delete))
+            .map(|(path, _)| self.root.join(path))
+            .collect();
+        // Deletes are part of the same all-or-nothing update as the writes.
+        FileUtils::update_files_atomic(&writes, &removals, &self.write_options)?;
+
+        report.applied = true;
+        tracing::info!("Applied patch to {} files", planned.len());
+        Ok(report)
+    }
+
+    fn apply_hunks(content: &str, hunks: &[Hunk]) -> (FileStatus, Vec<HunkOutcome>, Option<Planned>) {
+        let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+        let mut outcomes = Vec::new();
+        let mut delta: isize = 0;
+
+        for (index, hunk) in hunks.iter().enumerate() {
+            let before = hunk.before();
+            let expected = (hunk.old_start.saturating_sub(1) as isize + delta).max(0) as usize;
+
+            let Some((pos, fuzzy)) = Self::locate(&lines, &before, expected) else {
+                outcomes.push(Self::failure(index, Self::describe_conflict(&lines, &before, expected)));
+                continue;
+            };
+
+            let mut replacement = Vec::new();
+            let mut cursor = pos;
+            for line in &hunk.lines {
+                match line {
+                    HunkLine::Context(_) => {
+                        replacement.push(lines[cursor].clone());
+                        cursor += 1;
+                    }
+                    HunkLine::Removed(_) => cursor += 1,
+                    HunkLine::Added(s) => replacement.push(s.clone()),
+                }
+            }
+            let added = replacement.len() as isize - before.len() as isize;
+            lines.splice(pos..pos + before.len(), replacement);
+
+            outcomes.push(HunkOutcome {
+                index,
+                applied: true,
+                line: Some(pos + 1),
+                offset: pos as isize - expected as isize,
+                fuzzy,
+                message: if fuzzy { "applied ignoring whitespace".to_string() } else { "applied".to_string() },
+            });
+            delta += pos as isize - expected as isize + added;
+        }
+
+        Self::finish(content, lines, outcomes)
+    }
+
+    fn apply_blocks(content: &str, blocks: &[SearchReplaceBlock]) -> (FileStatus, Vec<HunkOutcome>, Option<Planned>) {
+        let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+        let mut outcomes = Vec::new();
+
+        for (index, block) in blocks.iter().enumerate() {
+            let search: Vec<&str> = block.search.lines().collect();
+            let replace: Vec<String> = block.replace.lines().map(|l| l.to_string()).collect();
+
+            if search.iter().all(|l| l.trim().is_empty()) {
+                lines.extend(replace);
+                outcomes.push(HunkOutcome {
+                    index,
+                    applied: true,
+                    line: Some(lines.len()),
+                    offset: 0,
+                    fuzzy: false,
+                    message: "appended".to_string(),
+                });
+                continue;
+            }
+
+            match Self::locate(&lines, &search, 0) {
+                Some((pos, fuzzy)) => {
+                    lines.splice(pos..pos + search.len(), replace);
+                    outcomes.push(HunkOutcome {
+                        index,
+                        applied: true,
+                        line: Some(pos + 1),
+                        offset: 0,
+                        fuzzy,
+                        message: if fuzzy { "applied ignoring whitespace".to_string() } else { "applied".to_string() },
+                    });
+                }
+                None => outcomes.push(Self::failure(index, Self::describe_conflict(&lines, &search, 0))),
+            }
+        }
+
+        Self::finish(content, lines, outcomes)
+    }
+
+    fn finish(content: &str, lines: Vec<String>, outcomes: Vec<HunkOutcome>) -> (FileStatus, Vec<HunkOutcome>, Option<Planned>) {
+        if outcomes.iter().any(|o| !o.applied) {
+            return (FileStatus::Conflict, outcomes, None);
+        }
+        let mut updated = lines.join("\n");
+        if content.ends_with('\n') || content.is_empty() {
+            updated.push('\n');
+        }
+        (FileStatus::Modified, outcomes, Some(Planned::Write(updated)))
+    }
+
+    fn locate(lines: &[String], needle: &[&str], expected: usize) -> Option<(usize, bool)> {
+        if needle.is_empty() {
+            return Some((expected.min(lines.len()), false));
+        }
+        if needle.len() > lines.len() {
+            return None;
+        }
+
+        let last = lines.len() - needle.len();
+        let expected = expected.min(last);
+        let mut candidates: Vec<usize> = (0..=last).collect();
+        candidates.sort_by_key(|&pos| pos.abs_diff(expected));
+
+        let exact = |pos: usize| needle.iter().enumerate().all(|(i, l)| lines[pos + i] == *l);
+        if let Some(&pos) = candidates.iter().find(|&&pos| exact(pos)) {
+            return Some((pos, false));
+        }
+
+        let fuzzy = |pos: usize| {
+            needle
+                .iter()
+                .enumerate()
+                .all(|(i, l)| Self::normalize_whitespace(&lines[pos + i]) == Self::normalize_whitespace(l))
+        };
+        candidates.into_iter().find(|&pos| fuzzy(pos)).map(|pos| (pos, true))
+    }
+
+    fn describe_conflict(lines: &[String], needle: &[&str], expected: usize) -> String {
+        let best = (0..lines.len().saturating_sub(needle.len()) + 1)
+            .map(|pos| {
+                let matching = needle
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, l)| {
+                        lines.get(pos + i).is_some_and(|line| {
+                            Self::normalize_whitespace(line) == Self::normalize_whitespace(l)
+                        })
+                    })
+                    .count();
+                (matching, pos)
+            })
+            .max_by_key(|&(matching, pos)| (matching, std::cmp::Reverse(pos.abs_diff(expected))));
+
+        let first = needle.first().map(|l| l.trim()).unwrap_or_default();
+        match best {
+            Some((matching, pos)) if matching > 0 => format!(
+                "context not found near line {} (closest: line {}, {}/{} lines match); expected \"{}\"",
+                expected + 1,
+                pos + 1,
+                matching,
+                needle.len(),
+                first
+            ),
+            _ => format!("context not found; expected \"{}\"", first),
+        }
+    }
+
+    fn normalize_whitespace(line: &str) -> String {
+        line.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    fn failure(index: usize, message: String) -> HunkOutcome {
+        HunkOutcome {
+            index,
+            applied: false,
+            line: None,
+            offset: 0,
+            fuzzy: false,
+            message,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "fn main() {\n    let a = 1;\n    let b = 2;\n    println!(\"{}\", a + b);\n}\n";
+
+    #[test]
+    fn test_unified_hunk_applies_with_offset_and_whitespace_fuzz() {
+        let diff = "--- a/src/main.rs\n+++ b/src/main.rs\n@@ -10,3 +10,3 @@\n     let a = 1;\n-  let b = 2;\n+    let b = 3;\n     println!(\"{}\", a + b);\n";
+        let patches = PatchParser::parse(diff).unwrap();
+        let FilePatch::Modify { hunks, .. } = &patches[0] else {
+            panic!("expected a modify patch");
+        };
+
+        let (status, outcomes, result) = PatchApplier::apply_hunks(SOURCE, hunks);
+        assert_eq!(status, FileStatus::Modified);
+        assert!(outcomes[0].fuzzy);
+        assert_eq!(outcomes[0].line, Some(2));
+        let Some(Planned::Write(updated)) = result else {
+            panic!("expected new content");
+        };
+        assert_eq!(updated, SOURCE.replace("let b = 2", "let b = 3"));
+    }
+
+    #[test]
+    fn test_search_replace_blocks_and_conflict_report() {
+        let output = "src/main.rs\n```rust\n<<<<<<< SEARCH\n    let a = 1;\n=======\n    let a = 10;\n>>>>>>> REPLACE\n<<<<<<< SEARCH\n    let c = 3;\n=======\n    let c = 4;\n>>>>>>> REPLACE\n```\n";
+        let patches = PatchParser::parse(output).unwrap();
+        assert_eq!(patches.len(), 1);
+        let FilePatch::Replace { blocks, .. } = &patches[0] else {
+            panic!("expected search/replace blocks");
+        };
+
+        let (status, outcomes, _) = PatchApplier::apply_blocks(SOURCE, blocks);
+        assert_eq!(status, FileStatus::Conflict);
+        assert!(outcomes[0].applied);
+        assert!(!outcomes[1].applied);
+        assert!(outcomes[1].message.contains("let c = 3;"));
+    }
+
+    #[test]
+    fn test_create_and_delete_headers() {
+        let diff = "--- /dev/null\n+++ b/src/new.rs\n@@ -0,0 +1,2 @@\n+pub fn hello() {}\n+\n--- a/src/old.rs\n+++ /dev/null\n@@ -1 +0,0 @@\n-fn old() {}\n";
+        let patches = PatchParser::parse(diff).unwrap();
+
+        assert_eq!(
+            patches[0],
+            FilePatch::Create {
+                path: "src/new.rs".to_string(),
+                content: "pub fn hello() {}\n\n".to_string()
+            }
+        );
+        assert_eq!(patches[1], FilePatch::Delete { path: "src/old.rs".to_string() });
+    }
+}
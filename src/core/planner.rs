@@ -1,5 +1,11 @@
+use crate::config::yaml_parser::YamlConfig;
+use crate::index::{ImportGraph, RelevanceRanker};
 use anyhow::Result;
 use std::collections::HashMap;
+use std::path::Path;
+
+const MAX_TARGET_FILES: usize = 8;
+const MAX_IMPACTED_FILES: usize = 8;
 
 #[derive(Debug, Clone)]
 pub struct ExecutionStep {
@@ -20,6 +26,7 @@ pub enum StepActionType {
     LintCheck,
     Commit,
     Rollback,
+    Custom(String),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -48,6 +55,70 @@ impl TaskDecomposer {
         Ok(plan)
     }
 
+    pub fn decompose_task_for_project(prompt: &str, project_root: &Path) -> Result<ExecutionPlan> {
+        let ranker = RelevanceRanker::build(project_root)?;
+        let mut target_files: Vec<String> = ranker
+            .rank(prompt, MAX_TARGET_FILES)
+            .into_iter()
+            .map(|f| f.path)
+            .collect();
+
+        match ImportGraph::build(project_root) {
+            Ok(graph) => {
+                let impacted = graph.transitive_dependents(&target_files);
+                target_files.extend(impacted.into_iter().take(MAX_IMPACTED_FILES));
+            }
+            Err(e) => tracing::warn!("Import graph unavailable: {}", e),
+        }
+
+        let config = YamlConfig::load(&project_root.join(".codeagent.yml")).unwrap_or_default();
+        let mut steps = Self::analyze_prompt(prompt);
+        steps.extend(Self::custom_tool_steps(prompt, &config, &steps));
+        for step in &mut steps {
+            if matches!(
+                step.action_type,
+                StepActionType::Analyze | StepActionType::Modify | StepActionType::Custom(_)
+            ) {
+                step.target_files = target_files.clone();
+            }
+        }
+        Self::sequence_steps(steps)
+    }
+
+    fn custom_tool_steps(prompt: &str, config: &YamlConfig, existing: &[ExecutionStep]) -> Vec<ExecutionStep> {
+        let prompt_lower = prompt.to_lowercase();
+        let after = existing
+            .iter()
+            .rev()
+            .find(|s| s.action_type == StepActionType::Modify)
+            .or(existing.first())
+            .map(|s| s.id.clone());
+
+        let mut tools: Vec<_> = config
+            .custom_tools
+            .values()
+            .filter(|t| t.enabled && prompt_lower.contains(&t.name.to_lowercase()))
+            .collect();
+        tools.sort_by(|a, b| a.name.cmp(&b.name));
+
+        tools
+            .into_iter()
+            .enumerate()
+            .map(|(i, tool)| ExecutionStep {
+                id: format!("custom_{}", existing.len() + i),
+                description: tool
+                    .description
+                    .clone()
+                    .unwrap_or_else(|| format!("Run custom tool {}", tool.name)),
+                action_type: StepActionType::Custom(tool.name.clone()),
+                target_files: vec![],
+                dependencies: after.clone().into_iter().collect(),
+                estimated_complexity: Complexity::Simple,
+                rollback_enabled: false,
+            })
+            .collect()
+    }
+
     fn analyze_prompt(prompt: &str) -> Vec<ExecutionStep> {
         let prompt_lower = prompt.to_lowercase();
         let mut steps = Vec::new();
@@ -62,7 +133,10 @@ impl TaskDecomposer {
             rollback_enabled: false,
         });
 
-        if prompt_lower.contains("refactor") || prompt_lower.contains("replace") {
+        let modifies = prompt_lower.contains("refactor") || prompt_lower.contains("replace");
+        // Checks follow the modification when there is one, otherwise the analysis.
+        let checked_step = if modifies { "modify_1" } else { "analyze_0" };
+        if modifies {
             steps.push(ExecutionStep {
                 id: "modify_1".to_string(),
                 description: "Apply code modifications".to_string(),
@@ -80,7 +154,7 @@ impl TaskDecomposer {
                 description: "Run tests to verify changes".to_string(),
                 action_type: StepActionType::TestRun,
                 target_files: vec![],
-                dependencies: vec!["modify_1".to_string()],
+                dependencies: vec![checked_step.to_string()],
                 estimated_complexity: Complexity::Moderate,
                 rollback_enabled: false,
             });
@@ -92,7 +166,7 @@ impl TaskDecomposer {
                 description: "Run linter checks".to_string(),
                 action_type: StepActionType::LintCheck,
                 target_files: vec![],
-                dependencies: vec!["modify_1".to_string()],
+                dependencies: vec![checked_step.to_string()],
                 estimated_complexity: Complexity::Simple,
                 rollback_enabled: false,
             });
@@ -0,0 +1,413 @@
+use crate::config::yaml_parser::{AccessRules, PolicyConfig, PolicyMode, YamlConfig};
+use crate::interactive::mode::{InteractiveMode, UserChoice};
+use crate::utils::walker::GlobRules;
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+type Approver = dyn Fn(&str) -> UserChoice + Send + Sync;
+
+static ENFORCERS: OnceLock<Mutex<HashMap<PathBuf, Arc<PolicyEnforcer>>>> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PolicyAction {
+    Write,
+    Command,
+    GitCommit,
+    GitHub,
+}
+
+impl PolicyAction {
+    fn label(&self) -> &'static str {
+        match self {
+            PolicyAction::Write => "file write",
+            PolicyAction::Command => "command",
+            PolicyAction::GitCommit => "git commit",
+            PolicyAction::GitHub => "GitHub call",
+        }
+    }
+}
+
+struct PathRules {
+    allow: GlobRules,
+    deny: GlobRules,
+}
+
+impl PathRules {
+    fn new(rules: &AccessRules) -> Result<Self> {
+        Ok(PathRules {
+            allow: GlobRules::new(&rules.allow)?,
+            deny: GlobRules::new(&rules.deny)?,
+        })
+    }
+
+    fn check(&self, relative: &str) -> std::result::Result<(), &'static str> {
+        if Self::matches(&self.deny, relative) {
+            Err("matches a deny pattern")
+        } else if !self.allow.is_empty() && !Self::matches(&self.allow, relative) {
+            Err("is not covered by an allow pattern")
+        } else {
+            Ok(())
+        }
+    }
+
+    // A pattern naming a directory covers everything below it.
+    fn matches(rules: &GlobRules, relative: &str) -> bool {
+        rules.matches_file(relative)
+            || relative
+                .match_indices('/')
+                .any(|(i, _)| rules.matches_dir(&relative[..i]))
+    }
+}
+
+#[derive(Default)]
+struct PolicyState {
+    changed_files: HashSet<String>,
+    approved: HashSet<String>,
+}
+
+/// Guardrails from the `policy` section of `.codeagent.yml`. One enforcer is shared
+/// per project so the changed-file budget and "ask" approvals last for the whole run.
+pub struct PolicyEnforcer {
+    root: PathBuf,
+    canonical_root: Option<PathBuf>,
+    config: PolicyConfig,
+    read: PathRules,
+    write: PathRules,
+    approver: Option<Arc<Approver>>,
+    state: Mutex<PolicyState>,
+}
+
+impl std::fmt::Debug for PolicyEnforcer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PolicyEnforcer")
+            .field("root", &self.root)
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+impl PolicyEnforcer {
+    pub fn new(root: &Path, config: PolicyConfig) -> Result<Self> {
+        Ok(PolicyEnforcer {
+            root: root.to_path_buf(),
+            canonical_root: root.canonicalize().ok(),
+            read: PathRules::new(&config.read)?,
+            write: PathRules::new(&config.write)?,
+            config,
+            approver: None,
+            state: Mutex::new(PolicyState::default()),
+        })
+    }
+
+    pub fn for_project(root: &Path) -> Arc<Self> {
+        let key = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+        let registry = ENFORCERS.get_or_init(|| Mutex::new(HashMap::new()));
+        let mut registry = registry.lock().unwrap_or_else(|e| e.into_inner());
+        registry
+            .entry(key)
+            .or_insert_with(|| {
+                let config = YamlConfig::load(&root.join(".codeagent.yml")).unwrap_or_default();
+                let enforcer = Self::new(root, config.policy).unwrap_or_else(|e| {
+                    tracing::error!("Invalid policy in .codeagent.yml, denying all agent actions: {}", e);
+                    Self::deny_all(root)
+                });
+                Arc::new(enforcer)
+            })
+            .clone()
+    }
+
+    fn deny_all(root: &Path) -> Self {
+        let mut config = PolicyConfig::default();
+        config.read.deny = vec!["*".to_string()];
+        config.modes.write = PolicyMode::Deny;
+        config.modes.command = PolicyMode::Deny;
+        config.modes.git_commit = PolicyMode::Deny;
+        config.modes.github = PolicyMode::Deny;
+        Self::new(root, config).expect("static deny-all policy is valid")
+    }
+
+    pub fn with_approver(mut self, approver: impl Fn(&str) -> UserChoice + Send + Sync + 'static) -> Self {
+        self.approver = Some(Arc::new(approver));
+        self
+    }
+
+    /// The enforcer of the innermost project containing `path`: one already loaded,
+    /// or the nearest ancestor with a `.codeagent.yml`.
+    pub fn covering(path: &Path) -> Option<Arc<Self>> {
+        let absolute = if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            std::env::current_dir().ok()?.join(path)
+        };
+        for dir in absolute.ancestors().skip(1) {
+            let key = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+            let loaded = {
+                let registry = ENFORCERS.get_or_init(|| Mutex::new(HashMap::new()));
+                let registry = registry.lock().unwrap_or_else(|e| e.into_inner());
+                registry.get(&key).cloned()
+            };
+            if loaded.is_some() {
+                return loaded;
+            }
+            if dir.join(".codeagent.yml").is_file() {
+                return Some(Self::for_project(dir));
+            }
+        }
+        None
+    }
+
+    pub fn can_read(&self, path: &Path) -> bool {
+        match self.relative(path) {
+            Some(relative) => self.read.check(&relative).is_ok(),
+            None => false,
+        }
+    }
+
+    pub fn check_writes(&self, paths: &[PathBuf]) -> Result<()> {
+        let mut relatives = Vec::with_capacity(paths.len());
+        for path in paths {
+            let relative = self.relative(path).ok_or_else(|| {
+                anyhow::anyhow!("Policy denies writing {}: it is outside the project", path.display())
+            })?;
+            if let Err(reason) = self.write.check(&relative) {
+                return Err(anyhow::anyhow!("Policy denies writing {}: it {}", relative, reason));
+            }
+            relatives.push(relative);
+        }
+
+        if let Some(max) = self.config.max_files_changed {
+            let state = self.lock_state()?;
+            let total = state
+                .changed_files
+                .iter()
+                .chain(relatives.iter())
+                .collect::<HashSet<_>>()
+                .len();
+            if total > max {
+                return Err(anyhow::anyhow!(
+                    "Policy allows at most {} changed files per run; this change would make it {}",
+                    max,
+                    total
+                ));
+            }
+        }
+
+        self.resolve(PolicyAction::Write, &format!("write {}", relatives.join(", ")))?;
+        self.lock_state()?.changed_files.extend(relatives);
+        Ok(())
+    }
+
+    pub fn check_command(&self, argv: &[String]) -> Result<()> {
+        let line = argv.join(" ");
+        let rules = &self.config.commands;
+        if rules.deny.iter().any(|p| Self::command_matches(p, argv)) {
+            return Err(anyhow::anyhow!("Policy denies running `{}`: it is on the command denylist", line));
+        }
+        if !rules.allow.is_empty() && !rules.allow.iter().any(|p| Self::command_matches(p, argv)) {
+            return Err(anyhow::anyhow!("Policy denies running `{}`: it is not on the command allowlist", line));
+        }
+        self.resolve(PolicyAction::Command, &format!("run `{}`", line))
+    }
+
+    /// Staging puts files on the way into a commit, so it needs the same write
+    /// access to each path and falls under the git commit mode.
+    pub fn check_stage(&self, paths: &[PathBuf]) -> Result<()> {
+        let mut relatives = Vec::with_capacity(paths.len());
+        for path in paths {
+            let relative = self.relative(path).ok_or_else(|| {
+                anyhow::anyhow!("Policy denies staging {}: it is outside the project", path.display())
+            })?;
+            if let Err(reason) = self.write.check(&relative) {
+                return Err(anyhow::anyhow!("Policy denies staging {}: it {}", relative, reason));
+            }
+            relatives.push(relative);
+        }
+        if relatives.is_empty() {
+            return Ok(());
+        }
+        self.resolve(PolicyAction::GitCommit, &format!("stage {}", relatives.join(", ")))
+    }
+
+    pub fn check_git_commit(&self, message: &str) -> Result<()> {
+        let subject = message.lines().next().unwrap_or_default();
+        self.resolve(PolicyAction::GitCommit, &format!("commit \"{}\"", subject))
+    }
+
+    pub fn check_github(&self, operation: &str) -> Result<()> {
+        self.resolve(PolicyAction::GitHub, operation)
+    }
+
+    fn resolve(&self, action: PolicyAction, subject: &str) -> Result<()> {
+        let mode = match action {
+            PolicyAction::Write => self.config.modes.write,
+            PolicyAction::Command => self.config.modes.command,
+            PolicyAction::GitCommit => self.config.modes.git_commit,
+            PolicyAction::GitHub => self.config.modes.github,
+        };
+        match mode {
+            PolicyMode::Allow => Ok(()),
+            PolicyMode::Deny => Err(anyhow::anyhow!("Policy denies {}: {}", action.label(), subject)),
+            PolicyMode::Ask => {
+                let key = format!("{:?}:{}", action, subject);
+                if self.lock_state()?.approved.contains(&key) {
+                    return Ok(());
+                }
+                let message = format!("🔒 The agent wants to {}.", subject);
+                let choice = match &self.approver {
+                    Some(approver) => approver(&message),
+                    None if InteractiveMode::can_prompt() => InteractiveMode::prompt_for_approval(&message),
+                    None => {
+                        return Err(anyhow::anyhow!(
+                            "{} needs approval but no terminal prompt is available: {}; set its policy mode to allow or deny",
+                            action.label(),
+                            subject
+                        ))
+                    }
+                };
+                if choice != UserChoice::Approve {
+                    return Err(anyhow::anyhow!("{} was not approved: {}", action.label(), subject));
+                }
+                self.lock_state()?.approved.insert(key);
+                Ok(())
+            }
+        }
+    }
+
+    /// A pattern matches a command when its words are a prefix of the argv;
+    /// the program is compared by file name and `*` matches any single word.
+    fn command_matches(pattern: &str, argv: &[String]) -> bool {
+        let words: Vec<&str> = pattern.split_whitespace().collect();
+        if words.is_empty() || words.len() > argv.len() {
+            return false;
+        }
+        words.iter().zip(argv).enumerate().all(|(i, (word, arg))| {
+            let arg = if i == 0 {
+                Path::new(arg).file_name().and_then(|n| n.to_str()).unwrap_or(arg)
+            } else {
+                arg.as_str()
+            };
+            *word == "*" || *word == arg || (i == 0 && *word == argv[0])
+        })
+    }
+
+    fn relative(&self, path: &Path) -> Option<String> {
+        let joined = if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            self.root.join(path)
+        };
+        let relative = joined
+            .strip_prefix(&self.root)
+            .ok()
+            .or_else(|| joined.strip_prefix(self.canonical_root.as_ref()?).ok())?;
+
+        let mut parts: Vec<String> = Vec::new();
+        for component in relative.components() {
+            match component {
+                Component::Normal(part) => parts.push(part.to_string_lossy().into_owned()),
+                Component::ParentDir => {
+                    parts.pop()?;
+                }
+                Component::CurDir => {}
+                _ => return None,
+            }
+        }
+        Some(parts.join("/"))
+    }
+
+    fn lock_state(&self) -> Result<std::sync::MutexGuard<'_, PolicyState>> {
+        self.state
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Policy state lock poisoned"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(yaml: &str) -> PolicyConfig {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_write_rules_budget_and_ask_mode() {
+        let root = Path::new("/project");
+        let config = policy(
+            "write: {allow: [\"src/**\", \"tests\"], deny: [\"src/generated\"]}\nmax_files_changed: 2\nmodes: {write: ask}",
+        );
+        let prompts = Arc::new(Mutex::new(0));
+        let counter = prompts.clone();
+        let enforcer = PolicyEnforcer::new(root, config).unwrap().with_approver(move |message| {
+            *counter.lock().unwrap() += 1;
+            if message.contains("tests/") {
+                UserChoice::Reject
+            } else {
+                UserChoice::Approve
+            }
+        });
+
+        let file = |p: &str| vec![root.join(p)];
+        assert!(enforcer.check_writes(&file("Cargo.toml")).is_err());
+        assert!(enforcer.check_writes(&file("src/generated/api.rs")).is_err());
+        assert!(enforcer.check_writes(&file("src/../../etc/passwd")).is_err());
+        assert!(enforcer.check_writes(&file("/etc/passwd")).is_err());
+
+        enforcer.check_writes(&file("src/lib.rs")).unwrap();
+        enforcer.check_writes(&file("src/lib.rs")).unwrap();
+        assert_eq!(*prompts.lock().unwrap(), 1);
+
+        let rejected = enforcer.check_writes(&file("tests/api.rs")).unwrap_err();
+        assert!(rejected.to_string().contains("not approved"));
+        enforcer.check_writes(&file("src/main.rs")).unwrap();
+        let over_budget = enforcer.check_writes(&file("src/other.rs")).unwrap_err();
+        assert!(over_budget.to_string().contains("at most 2"));
+    }
+
+    #[test]
+    fn test_command_lists_and_modes() {
+        let config = policy(
+            "read: {deny: [\".env\", \"secrets\"]}\ncommands: {allow: [\"cargo\", \"npm test\"], deny: [\"cargo publish\"]}\nmodes: {git_commit: deny}",
+        );
+        let enforcer = PolicyEnforcer::new(Path::new("/project"), config).unwrap();
+        let argv = |line: &str| line.split_whitespace().map(String::from).collect::<Vec<_>>();
+
+        assert!(enforcer.check_command(&argv("/usr/bin/cargo test --quiet")).is_ok());
+        assert!(enforcer.check_command(&argv("npm test -- src/a.test.js")).is_ok());
+        assert!(enforcer.check_command(&argv("npm install left-pad")).is_err());
+        assert!(enforcer.check_command(&argv("cargo publish")).is_err());
+        assert!(enforcer.check_command(&argv("curl https://example.com")).is_err());
+
+        assert!(enforcer.check_git_commit("Fix parser\n\nDetails").is_err());
+        assert!(enforcer.check_github("create pull request").is_ok());
+        assert!(!enforcer.can_read(Path::new("/project/.env")));
+        assert!(!enforcer.can_read(Path::new("secrets/key.pem")));
+        assert!(enforcer.can_read(Path::new("/project/src/lib.rs")));
+        assert!(!enforcer.can_read(Path::new("/etc/passwd")));
+        assert!(!enforcer.can_read(Path::new("../outside.rs")));
+    }
+
+    #[test]
+    fn test_plain_writes_follow_the_project_policy() {
+        let root = std::env::temp_dir().join(format!("codeagent-policy-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(
+            root.join(".codeagent.yml"),
+            "model_provider: ollama\npolicy:\n  write: {deny: [\"src/generated\"]}\n  modes: {command: ask}\n",
+        )
+        .unwrap();
+
+        let denied = crate::utils::FileUtils::write_file(&root.join("src/generated/api.rs"), "x").unwrap_err();
+        assert!(denied.to_string().contains("deny pattern"));
+        crate::utils::FileUtils::write_file(&root.join("src/lib.rs"), "x").unwrap();
+
+        InteractiveMode::set_terminal_ui_active(true);
+        let asked = PolicyEnforcer::for_project(&root).check_command(&["cargo".to_string(), "test".to_string()]);
+        InteractiveMode::set_terminal_ui_active(false);
+        assert!(asked.unwrap_err().to_string().contains("no terminal prompt"));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
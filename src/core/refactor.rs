@@ -0,0 +1,1476 @@
+use crate::index::symbols::SymbolKind;
+use crate::index::SymbolIndex;
+use crate::integrations::sandbox::{CommandRunner, CommandSpec};
+use crate::utils::walker::{ProjectWalker, WalkOptions};
+use crate::utils::{FileUtils, WriteOptions};
+use anyhow::Result;
+use similar::TextDiff;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tree_sitter::{Node, Parser, Tree};
+
+const COPY_TYPES: &[&str] = &[
+    "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128", "usize", "f32", "f64", "bool", "char",
+];
+
+const NUMERIC_SUFFIXES: &[&str] = &[
+    "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128", "usize", "f32", "f64",
+];
+
+const UNPARENTHESIZED_EXPRESSIONS: &[&str] = &[
+    "identifier",
+    "self",
+    "integer_literal",
+    "float_literal",
+    "string_literal",
+    "raw_string_literal",
+    "boolean_literal",
+    "char_literal",
+    "call_expression",
+    "field_expression",
+    "index_expression",
+    "scoped_identifier",
+    "macro_invocation",
+    "parenthesized_expression",
+    "array_expression",
+    "tuple_expression",
+    "try_expression",
+    "await_expression",
+];
+
+const PATTERN_PARENTS: &[&str] = &[
+    "parameter",
+    "closure_parameters",
+    "tuple_pattern",
+    "tuple_struct_pattern",
+    "struct_pattern",
+    "field_pattern",
+    "slice_pattern",
+    "or_pattern",
+    "ref_pattern",
+    "mut_pattern",
+    "captured_pattern",
+    "match_pattern",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenameKind {
+    Function,
+    Type,
+    Field,
+}
+
+impl RenameKind {
+    pub fn parse(kind: &str) -> Result<Self> {
+        match kind {
+            "fn" | "function" | "method" => Ok(RenameKind::Function),
+            "struct" | "enum" | "trait" | "type" => Ok(RenameKind::Type),
+            "field" => Ok(RenameKind::Field),
+            other => Err(anyhow::anyhow!("Unknown rename kind '{}' (expected fn, struct or field)", other)),
+        }
+    }
+
+    fn definition_kinds(&self) -> &'static [&'static str] {
+        match self {
+            RenameKind::Function => &["function_item", "function_signature_item"],
+            RenameKind::Type => &["struct_item", "enum_item", "trait_item", "type_item", "union_item"],
+            RenameKind::Field => &["field_declaration"],
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            RenameKind::Function => "function",
+            RenameKind::Type => "type",
+            RenameKind::Field => "field",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FileEdit {
+    pub path: PathBuf,
+    pub original: String,
+    pub updated: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    pub success: bool,
+    pub output: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Refactoring {
+    pub root: PathBuf,
+    pub description: String,
+    pub edits: Vec<FileEdit>,
+}
+
+impl Refactoring {
+    pub fn diff(&self) -> String {
+        let mut diff = String::new();
+        for edit in &self.edits {
+            let relative = edit.path.strip_prefix(&self.root).unwrap_or(&edit.path).display().to_string();
+            diff.push_str(
+                &TextDiff::from_lines(&edit.original, &edit.updated)
+                    .unified_diff()
+                    .context_radius(3)
+                    .header(&format!("a/{}", relative), &format!("b/{}", relative))
+                    .to_string(),
+            );
+        }
+        diff
+    }
+
+    /// Runs `cargo check` against a scratch copy of the project with the edits applied,
+    /// so the working tree is never left in a broken state.
+    pub fn verify(&self) -> Result<CheckResult> {
+        if !self.root.join("Cargo.toml").exists() {
+            return Err(anyhow::anyhow!("cargo check needs a Cargo.toml at {}", self.root.display()));
+        }
+
+        let stamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+        let scratch = std::env::temp_dir().join(format!("codeagent-check-{}-{}", std::process::id(), stamp));
+        let result = self.check_in(&scratch);
+        let _ = std::fs::remove_dir_all(&scratch);
+        result
+    }
+
+    fn check_in(&self, scratch: &Path) -> Result<CheckResult> {
+        // Gitignored inputs such as Cargo.lock or generated sources still matter to the
+        // build, so copy everything except the root build directory (`.git` is always skipped).
+        let options = WalkOptions {
+            excluded_dirs: vec!["/target".to_string()],
+            max_file_size: None,
+            skip_binary: false,
+            include_hidden: true,
+            respect_ignore_files: false,
+            ..WalkOptions::default()
+        };
+        for path in ProjectWalker::new(&self.root).with_options(options).walk()? {
+            let Ok(relative) = path.strip_prefix(&self.root) else {
+                continue;
+            };
+            let target = scratch.join(relative);
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::copy(&path, &target)?;
+        }
+
+        for edit in &self.edits {
+            let relative = edit.path.strip_prefix(&self.root).unwrap_or(&edit.path);
+            std::fs::write(scratch.join(relative), &edit.updated)?;
+        }
+
+        // Reuse the project's build cache instead of compiling the scratch copy from nothing.
+        let target_dir = std::env::var_os("CARGO_TARGET_DIR")
+            .map(PathBuf::from)
+            .map(|dir| if dir.is_absolute() { dir } else { self.root.join(dir) })
+            .unwrap_or_else(|| self.root.join("target"));
+        let mut spec = CommandSpec::for_project(
+            "cargo",
+            &["check", "--all-targets", "--quiet", "--message-format", "short"],
+            &self.root,
+        )
+        .with_env("CARGO_TARGET_DIR", &target_dir.display().to_string());
+        spec.cwd = scratch.to_path_buf();
+        let output = CommandRunner::run_blocking(&spec)?;
+
+        let scratch_prefix = format!("{}/", scratch.display());
+        Ok(CheckResult {
+            success: output.success(),
+            output: output.stderr.replace(&scratch_prefix, ""),
+        })
+    }
+
+    pub fn apply(&self, options: &WriteOptions) -> Result<usize> {
+        for edit in &self.edits {
+            if FileUtils::read_file(&edit.path)? != edit.original {
+                return Err(anyhow::anyhow!(
+                    "{} changed since the refactoring was planned; nothing was written",
+                    edit.path.display()
+                ));
+            }
+        }
+
+        let writes: Vec<(PathBuf, String)> = self.edits.iter().map(|e| (e.path.clone(), e.updated.clone())).collect();
+        FileUtils::write_files_atomic(&writes, options)?;
+        tracing::info!("{}: updated {} files", self.description, writes.len());
+        Ok(writes.len())
+    }
+}
+
+struct RenameOutcome {
+    updated: String,
+    occurrences: usize,
+    defines_old: bool,
+    unresolved: Vec<usize>,
+}
+
+/// The one definition a rename applies to, plus the owners and modules of every other
+/// definition sharing its name, which decide whether an unresolved use is ambiguous.
+#[derive(Debug, Clone)]
+struct RenameTarget {
+    name: String,
+    kind: RenameKind,
+    /// Impl type or trait owning a method, or the struct owning a field.
+    owner: Option<String>,
+    module: String,
+    file: String,
+    line: usize,
+    /// Whether a method takes `self`, i.e. can be called with `value.name()`.
+    has_receiver: bool,
+    other_owners: Vec<String>,
+    other_modules: Vec<String>,
+}
+
+impl RenameTarget {
+    fn qualified_name(&self) -> String {
+        match &self.owner {
+            Some(owner) => format!("{}::{}", owner, self.name),
+            None if self.module == "crate" => self.name.clone(),
+            None => format!("{}::{}", self.module, self.name),
+        }
+    }
+
+    fn same_scope(&self, other: &RenameTarget) -> bool {
+        self.owner == other.owner && (self.owner.is_some() || self.module == other.module)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Resolution {
+    Target,
+    Other,
+    Unknown,
+}
+
+#[derive(Debug, Clone)]
+struct Binding {
+    ty: Option<String>,
+    mutable: bool,
+}
+
+pub struct RustRefactor;
+
+impl RustRefactor {
+    /// `old` may be qualified as `Type::name` (methods and fields) or `module::name` when
+    /// more than one definition shares the name; only uses bound to that definition change.
+    pub fn rename(root: &Path, old: &str, new: &str, kind: RenameKind) -> Result<Refactoring> {
+        if !Self::is_identifier(new) {
+            return Err(anyhow::anyhow!("'{}' is not a valid Rust identifier", new));
+        }
+
+        let mut index = SymbolIndex::load(root);
+        index.refresh()?;
+        let target = Self::resolve_target(&index, root, old, kind)?;
+        if target.name == new {
+            return Err(anyhow::anyhow!("New name is the same as the old name"));
+        }
+        if let Some(clash) = Self::rename_targets(&index, root, new, kind)?
+            .into_iter()
+            .find(|t| t.same_scope(&target))
+        {
+            return Err(anyhow::anyhow!(
+                "A {} named `{}` already exists in {}:{}",
+                kind.label(),
+                clash.qualified_name(),
+                clash.file,
+                clash.line
+            ));
+        }
+
+        let files = ProjectWalker::for_project(root).with_extensions(&["rs"]).walk()?;
+        let mut edits = Vec::new();
+        let mut defined = false;
+        let mut occurrences = 0;
+        let mut unresolved = Vec::new();
+
+        for path in files {
+            let Ok(source) = FileUtils::read_file(&path) else {
+                continue;
+            };
+            let relative = path.strip_prefix(root).unwrap_or(&path).to_string_lossy().replace('\\', "/");
+            let outcome = Self::rename_source(&source, &relative, &target, new)?;
+            defined |= outcome.defines_old;
+            occurrences += outcome.occurrences;
+            unresolved.extend(outcome.unresolved.iter().map(|line| format!("{}:{}", relative, line)));
+            if outcome.occurrences > 0 {
+                edits.push(FileEdit {
+                    path,
+                    original: source,
+                    updated: outcome.updated,
+                });
+            }
+        }
+
+        if !unresolved.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Cannot tell whether these uses of `{}` refer to `{}`: {}. Give the receivers explicit types or rename them by hand",
+                target.name,
+                target.qualified_name(),
+                unresolved.join(", ")
+            ));
+        }
+        if !defined {
+            return Err(anyhow::anyhow!("No {} named `{}` is defined in this project", kind.label(), old));
+        }
+
+        Ok(Refactoring {
+            root: root.to_path_buf(),
+            description: format!(
+                "Rename {} `{}` to `{}` ({} occurrences in {} files)",
+                kind.label(),
+                target.qualified_name(),
+                new,
+                occurrences,
+                edits.len()
+            ),
+            edits,
+        })
+    }
+
+    pub fn extract_function(root: &Path, file: &str, start_line: usize, end_line: usize, name: &str) -> Result<Refactoring> {
+        let path = root.join(file);
+        let source = FileUtils::read_file(&path)?;
+        let updated = Self::extract_function_source(&source, start_line, end_line, name)?;
+        Ok(Refactoring {
+            root: root.to_path_buf(),
+            description: format!("Extract lines {}-{} of {} into `{}`", start_line, end_line, file, name),
+            edits: vec![FileEdit { path, original: source, updated }],
+        })
+    }
+
+    pub fn inline_variable(root: &Path, file: &str, line: usize, name: &str) -> Result<Refactoring> {
+        let path = root.join(file);
+        let source = FileUtils::read_file(&path)?;
+        let updated = Self::inline_variable_source(&source, line, name)?;
+        Ok(Refactoring {
+            root: root.to_path_buf(),
+            description: format!("Inline `{}` declared at {}:{}", name, file, line),
+            edits: vec![FileEdit { path, original: source, updated }],
+        })
+    }
+
+    fn resolve_target(index: &SymbolIndex, root: &Path, old: &str, kind: RenameKind) -> Result<RenameTarget> {
+        let (qualifier, name) = match old.rsplit_once("::").or_else(|| old.rsplit_once('.')) {
+            Some((path, name)) => (Some(path.rsplit("::").next().unwrap_or(path)), name),
+            None => (None, old),
+        };
+        let candidates = Self::rename_targets(index, root, name, kind)?;
+        let matching: Vec<&RenameTarget> = candidates
+            .iter()
+            .filter(|t| match qualifier {
+                Some(q) => t.owner.as_deref() == Some(q) || (t.owner.is_none() && t.module == q),
+                None => true,
+            })
+            .collect();
+
+        match matching.as_slice() {
+            [] => Err(anyhow::anyhow!("No {} named `{}` is defined in this project", kind.label(), old)),
+            [target] => {
+                let mut target = (*target).clone();
+                for other in candidates.iter().filter(|t| t.file != target.file || t.line != target.line) {
+                    match &other.owner {
+                        Some(owner) if other.has_receiver || kind == RenameKind::Field => target.other_owners.push(owner.clone()),
+                        Some(_) => {}
+                        None => target.other_modules.push(other.module.clone()),
+                    }
+                }
+                Ok(target)
+            }
+            several => {
+                let listed: Vec<String> = several
+                    .iter()
+                    .map(|t| format!("{} ({}:{})", t.qualified_name(), t.file, t.line))
+                    .collect();
+                Err(anyhow::anyhow!(
+                    "`{}` is defined {} times: {}. Qualify the name, e.g. `{}`",
+                    old,
+                    several.len(),
+                    listed.join(", "),
+                    several[0].qualified_name()
+                ))
+            }
+        }
+    }
+
+    /// Every Rust definition of `name` for this kind. The symbol index narrows down which
+    /// files to parse; fields are not indexed, so their structs' files are used instead.
+    fn rename_targets(index: &SymbolIndex, root: &Path, name: &str, kind: RenameKind) -> Result<Vec<RenameTarget>> {
+        let symbol_kinds: &[SymbolKind] = match kind {
+            RenameKind::Function => &[SymbolKind::Function, SymbolKind::Method],
+            RenameKind::Type | RenameKind::Field => &[SymbolKind::Struct, SymbolKind::Enum, SymbolKind::Trait, SymbolKind::Type],
+        };
+        let mut files: Vec<&str> = index
+            .files
+            .iter()
+            .filter(|(file, _)| file.ends_with(".rs"))
+            .filter(|(_, symbols)| {
+                symbols
+                    .definitions
+                    .iter()
+                    .any(|d| symbol_kinds.contains(&d.kind) && (kind == RenameKind::Field || d.name == name))
+            })
+            .map(|(file, _)| file.as_str())
+            .collect();
+        files.sort();
+
+        let mut targets = Vec::new();
+        for file in files {
+            let Ok(source) = FileUtils::read_file(&root.join(file)) else {
+                continue;
+            };
+            let tree = Self::parse(&source)?;
+            Self::visit(tree.root_node(), &mut |node| {
+                if !kind.definition_kinds().contains(&node.kind())
+                    || node.child_by_field_name("name").map(|n| Self::text(n, &source)) != Some(name)
+                {
+                    return;
+                }
+                let owner = match kind {
+                    RenameKind::Field => Self::ancestor(node, "struct_item")
+                        .or_else(|| Self::ancestor(node, "union_item"))
+                        .and_then(|s| s.child_by_field_name("name"))
+                        .map(|n| Self::text(n, &source).to_string()),
+                    _ => Self::owner_of(node, &source),
+                };
+                if kind == RenameKind::Field && owner.is_none() {
+                    return;
+                }
+                targets.push(RenameTarget {
+                    name: name.to_string(),
+                    kind,
+                    owner,
+                    module: Self::enclosing_module(node, &source, file),
+                    file: file.to_string(),
+                    line: node.start_position().row + 1,
+                    has_receiver: node
+                        .child_by_field_name("parameters")
+                        .is_some_and(|p| Self::has_child(p, "self_parameter")),
+                    other_owners: Vec::new(),
+                    other_modules: Vec::new(),
+                });
+            });
+        }
+        Ok(targets)
+    }
+
+    fn rename_source(source: &str, file: &str, target: &RenameTarget, new: &str) -> Result<RenameOutcome> {
+        let tree = Self::parse(source)?;
+        let mut replacements = Vec::new();
+        let mut defines_old = false;
+        let mut unresolved = Vec::new();
+
+        Self::visit(tree.root_node(), &mut |node| {
+            let Some(text) = Self::renamed_text(node, source, &target.name, new, target.kind) else {
+                return;
+            };
+            match Self::resolve(node, source, file, target) {
+                Resolution::Target => {
+                    defines_old |= Self::is_definition_name(node, target.kind);
+                    replacements.push((node.start_byte(), node.end_byte(), text));
+                }
+                Resolution::Unknown => unresolved.push(node.start_position().row + 1),
+                Resolution::Other => {}
+            }
+        });
+
+        Ok(RenameOutcome {
+            occurrences: replacements.len(),
+            updated: Self::replace_ranges(source, replacements),
+            defines_old,
+            unresolved,
+        })
+    }
+
+    fn renamed_text(node: Node, source: &str, old: &str, new: &str, kind: RenameKind) -> Option<String> {
+        if node.child_count() > 0 || Self::text(node, source) != old {
+            return None;
+        }
+
+        let parent = node.parent();
+        let parent_kind = parent.map(|p| p.kind()).unwrap_or_default();
+        let is_method_call = parent_kind == "field_expression"
+            && parent
+                .and_then(|p| p.parent())
+                .is_some_and(|gp| gp.kind() == "call_expression" && gp.child_by_field_name("function") == parent);
+        let after_dot = node.prev_sibling().is_some_and(|s| s.kind() == ".");
+        let shorthand = parent_kind == "shorthand_field_initializer";
+
+        match (kind, node.kind()) {
+            (RenameKind::Field, "identifier") if shorthand => Some(format!("{}: {}", new, old)),
+            (RenameKind::Field, "identifier") if parent_kind == "token_tree" && after_dot => Some(new.to_string()),
+            (RenameKind::Field, "field_identifier") if !is_method_call => Some(new.to_string()),
+            (RenameKind::Field, "shorthand_field_identifier") => Some(format!("{}: {}", new, old)),
+            (RenameKind::Function | RenameKind::Type, "identifier") if shorthand => Some(format!("{}: {}", old, new)),
+            (RenameKind::Function, "identifier") => Some(new.to_string()),
+            (RenameKind::Function, "field_identifier") if is_method_call => Some(new.to_string()),
+            (RenameKind::Type, "identifier" | "type_identifier") => Some(new.to_string()),
+            _ => None,
+        }
+    }
+
+    fn is_definition_name(node: Node, kind: RenameKind) -> bool {
+        node.parent().is_some_and(|p| {
+            kind.definition_kinds().contains(&p.kind()) && p.child_by_field_name("name") == Some(node)
+        })
+    }
+
+    fn resolve(node: Node, source: &str, file: &str, target: &RenameTarget) -> Resolution {
+        if Self::is_definition_name(node, target.kind) {
+            let definition = node.parent().map(|p| p.start_position().row + 1);
+            return if file == target.file && definition == Some(target.line) {
+                Resolution::Target
+            } else {
+                Resolution::Other
+            };
+        }
+        match &target.owner {
+            Some(owner) => Self::resolve_member(node, source, owner, target),
+            None => Self::resolve_item(node, source, file, target),
+        }
+    }
+
+    /// Methods and fields are bound through the type of the receiver, path or struct
+    /// literal they are used with.
+    fn resolve_member(node: Node, source: &str, owner: &str, target: &RenameTarget) -> Resolution {
+        let Some(parent) = node.parent() else {
+            return Resolution::Other;
+        };
+        let method_syntax = matches!(parent.kind(), "field_expression" | "token_tree");
+        if method_syntax && target.kind == RenameKind::Function && !target.has_receiver {
+            return Resolution::Other;
+        }
+        let ty = match parent.kind() {
+            "field_expression" => parent.child_by_field_name("value").and_then(|v| Self::expression_type(v, source)),
+            "token_tree" => node
+                .prev_sibling()
+                .and_then(|dot| dot.prev_sibling())
+                .and_then(|receiver| Self::expression_type(receiver, source)),
+            "scoped_identifier" => parent.child_by_field_name("path").and_then(|p| Self::path_type(p, source)),
+            "field_initializer" | "shorthand_field_initializer" | "field_pattern" => {
+                let mut current = parent.parent();
+                while let Some(candidate) = current {
+                    if matches!(candidate.kind(), "struct_expression" | "struct_pattern") {
+                        break;
+                    }
+                    current = candidate.parent();
+                }
+                current
+                    .and_then(|s| s.child_by_field_name("name").or_else(|| s.child_by_field_name("type")))
+                    .and_then(|t| Self::type_name(t, source))
+            }
+            _ => return Resolution::Other,
+        };
+
+        match ty {
+            Some(ty) if ty == owner => Resolution::Target,
+            Some(ty) if target.other_owners.contains(&ty) || !Self::is_type_parameter(node, source, &ty) => Resolution::Other,
+            _ if target.other_owners.is_empty() => Resolution::Target,
+            _ => Resolution::Unknown,
+        }
+    }
+
+    /// Free functions and types are bound through the path they are named with, the
+    /// module they are used in, or the `use` that imports them.
+    fn resolve_item(node: Node, source: &str, file: &str, target: &RenameTarget) -> Resolution {
+        let parent = node.parent();
+        let parent_kind = parent.map(|p| p.kind()).unwrap_or_default();
+        if parent_kind == "field_expression" {
+            return Resolution::Other;
+        }
+        if target.kind == RenameKind::Function && (Self::is_binding(node) || Self::local_declaration(node, source).is_some()) {
+            return Resolution::Other;
+        }
+
+        let qualifier = match parent_kind {
+            "scoped_identifier" | "scoped_type_identifier" => match parent.and_then(|p| p.child_by_field_name("path")) {
+                Some(path) if parent.and_then(|p| p.child_by_field_name("name")) == Some(node) => Some(path),
+                _ if target.kind == RenameKind::Function => return Resolution::Other,
+                _ => None,
+            },
+            "use_list" => parent.and_then(|p| p.parent()).and_then(|p| p.child_by_field_name("path")),
+            _ => None,
+        };
+        if let Some(path) = qualifier {
+            return Self::resolve_module_path(node, path, source, file, target);
+        }
+
+        if Self::enclosing_module(node, source, file) == target.module {
+            return Resolution::Target;
+        }
+        let mut imported = None;
+        Self::visit(Self::source_root(node), &mut |candidate| {
+            if imported.is_some()
+                || candidate.kind() != node.kind()
+                || Self::text(candidate, source) != target.name
+                || Self::ancestor(candidate, "use_declaration").is_none()
+            {
+                return;
+            }
+            let path = candidate.parent().and_then(|p| match p.kind() {
+                "scoped_identifier" => p.child_by_field_name("path"),
+                "use_list" => p.parent().and_then(|list| list.child_by_field_name("path")),
+                _ => None,
+            });
+            if let Some(path) = path {
+                imported = Some(Self::resolve_module_path(candidate, path, source, file, target));
+            }
+        });
+        match imported {
+            Some(resolution) => resolution,
+            None if target.other_modules.is_empty() => Resolution::Target,
+            None => Resolution::Unknown,
+        }
+    }
+
+    fn resolve_module_path(node: Node, path: Node, source: &str, file: &str, target: &RenameTarget) -> Resolution {
+        let segment = Self::last_segment(path, source);
+        let module = match segment.as_str() {
+            "crate" => "crate".to_string(),
+            "self" => Self::enclosing_module(node, source, file),
+            "super" if target.other_modules.is_empty() => return Resolution::Target,
+            "super" => return Resolution::Unknown,
+            // `Type::name` is an associated item, not the module-level one.
+            other if other.starts_with(char::is_uppercase) => return Resolution::Other,
+            other => other.to_string(),
+        };
+        if module == target.module {
+            Resolution::Target
+        } else {
+            Resolution::Other
+        }
+    }
+
+    fn owner_of(node: Node, source: &str) -> Option<String> {
+        let container = node.parent().filter(|p| p.kind() == "declaration_list")?.parent()?;
+        match container.kind() {
+            "impl_item" => container.child_by_field_name("type").and_then(|t| Self::type_name(t, source)),
+            "trait_item" => container.child_by_field_name("name").map(|n| Self::text(n, source).to_string()),
+            _ => None,
+        }
+    }
+
+    fn enclosing_module(node: Node, source: &str, file: &str) -> String {
+        if let Some(name) = Self::ancestor(node, "mod_item").and_then(|m| m.child_by_field_name("name")) {
+            return Self::text(name, source).to_string();
+        }
+        let path = Path::new(file);
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+        let parent = path.parent().and_then(|p| p.file_name()).and_then(|p| p.to_str()).unwrap_or_default();
+        match stem {
+            "lib" | "main" if parent == "src" || parent == "bin" => "crate".to_string(),
+            "mod" => parent.to_string(),
+            _ => stem.to_string(),
+        }
+    }
+
+    fn self_type(node: Node, source: &str) -> Option<String> {
+        let mut current = node.parent();
+        while let Some(parent) = current {
+            match parent.kind() {
+                "impl_item" => return parent.child_by_field_name("type").and_then(|t| Self::type_name(t, source)),
+                "trait_item" => return parent.child_by_field_name("name").map(|n| Self::text(n, source).to_string()),
+                _ => current = parent.parent(),
+            }
+        }
+        None
+    }
+
+    fn type_name(node: Node, source: &str) -> Option<String> {
+        match node.kind() {
+            "type_identifier" | "identifier" if Self::text(node, source) == "Self" => Self::self_type(node, source),
+            "type_identifier" | "identifier" => Some(Self::text(node, source).to_string()),
+            "scoped_type_identifier" | "scoped_identifier" => Self::type_name(node.child_by_field_name("name")?, source),
+            "generic_type" | "generic_type_with_turbofish" | "reference_type" | "pointer_type" => {
+                Self::type_name(node.child_by_field_name("type")?, source)
+            }
+            "abstract_type" | "dynamic_type" => Self::type_name(node.child_by_field_name("trait")?, source),
+            _ => None,
+        }
+    }
+
+    fn path_type(path: Node, source: &str) -> Option<String> {
+        Self::type_name(path, source).filter(|name| name.starts_with(char::is_uppercase))
+    }
+
+    fn last_segment(path: Node, source: &str) -> String {
+        match path.kind() {
+            "scoped_identifier" | "scoped_type_identifier" => path
+                .child_by_field_name("name")
+                .map(|n| Self::text(n, source).to_string())
+                .unwrap_or_default(),
+            _ => Self::text(path, source).to_string(),
+        }
+    }
+
+    /// Best-effort static type of a receiver: `self`, locals with an annotation or a
+    /// constructor call / struct literal initializer, and those expressions themselves.
+    fn expression_type(expr: Node, source: &str) -> Option<String> {
+        match expr.kind() {
+            "self" => Self::self_type(expr, source),
+            "identifier" => {
+                let declaration = Self::local_declaration(expr, source)?;
+                match declaration.child_by_field_name("type") {
+                    Some(ty) => Self::type_name(ty, source),
+                    None => Self::expression_type(declaration.child_by_field_name("value")?, source),
+                }
+            }
+            "struct_expression" => Self::type_name(expr.child_by_field_name("name")?, source),
+            "call_expression" => {
+                let function = expr.child_by_field_name("function")?;
+                if function.kind() != "scoped_identifier" {
+                    return None;
+                }
+                Self::path_type(function.child_by_field_name("path")?, source)
+            }
+            "reference_expression" => Self::expression_type(expr.child_by_field_name("value")?, source),
+            "parenthesized_expression" | "try_expression" => Self::expression_type(expr.named_child(0)?, source),
+            _ => None,
+        }
+    }
+
+    /// The parameter or earlier `let` in the enclosing function that binds this identifier.
+    fn local_declaration<'t>(node: Node<'t>, source: &str) -> Option<Node<'t>> {
+        let name = Self::text(node, source);
+        let function = Self::ancestor(node, "function_item")?;
+        let mut declaration = None;
+
+        if let Some(parameters) = function.child_by_field_name("parameters") {
+            for parameter in Self::named_children(parameters).into_iter().filter(|p| p.kind() == "parameter") {
+                if parameter.child_by_field_name("pattern").is_some_and(|p| Self::text(p, source) == name) {
+                    declaration = Some(parameter);
+                }
+            }
+        }
+        if let Some(body) = function.child_by_field_name("body") {
+            Self::visit(body, &mut |candidate| {
+                let in_scope = candidate.end_byte() <= node.start_byte()
+                    && candidate
+                        .parent()
+                        .is_some_and(|p| p.start_byte() <= node.start_byte() && p.end_byte() >= node.end_byte());
+                if candidate.kind() == "let_declaration"
+                    && in_scope
+                    && candidate
+                        .child_by_field_name("pattern")
+                        .is_some_and(|p| p.kind() == "identifier" && Self::text(p, source) == name)
+                {
+                    declaration = Some(candidate);
+                }
+            });
+        }
+        declaration
+    }
+
+    fn is_type_parameter(node: Node, source: &str, name: &str) -> bool {
+        let mut current = node.parent();
+        while let Some(parent) = current {
+            if let Some(parameters) = parent.child_by_field_name("type_parameters") {
+                let mut found = false;
+                Self::visit(parameters, &mut |p| {
+                    found |= p.kind() == "type_identifier" && Self::text(p, source) == name;
+                });
+                if found {
+                    return true;
+                }
+            }
+            current = parent.parent();
+        }
+        false
+    }
+
+    pub fn extract_function_source(source: &str, start_line: usize, end_line: usize, name: &str) -> Result<String> {
+        if !Self::is_identifier(name) {
+            return Err(anyhow::anyhow!("'{}' is not a valid Rust identifier", name));
+        }
+        let tree = Self::parse(source)?;
+        let (line_start, line_end) = Self::line_range(source, start_line, end_line)?;
+        let selected_text = &source[line_start..line_end];
+        let sel_start = line_start + (selected_text.len() - selected_text.trim_start().len());
+        let sel_end = line_start + selected_text.trim_end().len();
+        if sel_start >= sel_end {
+            return Err(anyhow::anyhow!("Lines {}-{} are empty", start_line, end_line));
+        }
+
+        let mut block = tree
+            .root_node()
+            .descendant_for_byte_range(sel_start, sel_end)
+            .ok_or_else(|| anyhow::anyhow!("Selection is not inside a function body"))?;
+        while block.kind() != "block" {
+            block = block
+                .parent()
+                .ok_or_else(|| anyhow::anyhow!("Selection is not inside a function body"))?;
+        }
+
+        let children = Self::named_children(block);
+        let selected: Vec<Node> = children
+            .iter()
+            .copied()
+            .filter(|c| c.start_byte() >= sel_start && c.end_byte() <= sel_end)
+            .collect();
+        let partial = children.iter().any(|c| {
+            c.start_byte() < sel_end && c.end_byte() > sel_start && !(c.start_byte() >= sel_start && c.end_byte() <= sel_end)
+        });
+        if selected.is_empty() || partial {
+            return Err(anyhow::anyhow!(
+                "Lines {}-{} must cover complete statements of a single block",
+                start_line,
+                end_line
+            ));
+        }
+
+        let function = Self::ancestor(block, "function_item")
+            .ok_or_else(|| anyhow::anyhow!("Selection is not inside a function body"))?;
+        let in_impl = function
+            .parent()
+            .and_then(|p| p.parent())
+            .is_some_and(|p| p.kind() == "impl_item");
+
+        let mut uses_self = false;
+        let mut is_async = function.child(0).is_some_and(|c| Self::text(c, source).contains("async"));
+        let mut control_flow = None;
+        for stmt in &selected {
+            Self::visit(*stmt, &mut |node| match node.kind() {
+                "self" => uses_self = true,
+                "await_expression" => is_async = true,
+                "return_expression" | "try_expression"
+                    if !Self::inside(node, *stmt, &["closure_expression", "function_item", "async_block"]) =>
+                {
+                    control_flow = Some("`return` or `?`");
+                }
+                "break_expression" | "continue_expression"
+                    if !Self::inside(node, *stmt, &["loop_expression", "while_expression", "for_expression", "closure_expression"]) =>
+                {
+                    control_flow = Some("`break` or `continue`");
+                }
+                _ => {}
+            });
+        }
+        if let Some(what) = control_flow {
+            return Err(anyhow::anyhow!("Cannot extract lines containing {} that leaves the selection", what));
+        }
+
+        let bindings = Self::bindings_before(function, block, source, sel_start, sel_end);
+
+        let mut used: Vec<String> = Vec::new();
+        let mut assigned: Vec<String> = Vec::new();
+        let mut method_receivers: Vec<String> = Vec::new();
+        for stmt in &selected {
+            Self::visit(*stmt, &mut |node| {
+                if node.kind() != "identifier" || !Self::is_variable_use(node) {
+                    return;
+                }
+                let text = Self::text(node, source).to_string();
+                if !bindings.contains_key(&text) {
+                    return;
+                }
+                if Self::is_assignment_target(node) && !assigned.contains(&text) {
+                    assigned.push(text.clone());
+                }
+                if Self::is_method_receiver(node) && !method_receivers.contains(&text) {
+                    method_receivers.push(text.clone());
+                }
+                if !used.contains(&text) {
+                    used.push(text);
+                }
+            });
+        }
+
+        let mut params = Vec::new();
+        let mut args = Vec::new();
+        let mut deref = Vec::new();
+        if uses_self {
+            let self_param = function
+                .child_by_field_name("parameters")
+                .map(Self::named_children)
+                .and_then(|p| p.into_iter().find(|c| c.kind() == "self_parameter"))
+                .ok_or_else(|| anyhow::anyhow!("Selection uses `self` outside of a method"))?;
+            params.push(if Self::text(self_param, source).contains("mut") { "&mut self" } else { "&self" }.to_string());
+        }
+        for name in &used {
+            let binding = &bindings[name];
+            let ty = binding.ty.clone().ok_or_else(|| {
+                anyhow::anyhow!("Cannot infer the type of `{}`; annotate its binding before extracting", name)
+            })?;
+            if assigned.contains(name) {
+                params.push(format!("{}: &mut {}", name, ty));
+                args.push(format!("&mut {}", name));
+                deref.push(name.clone());
+            } else if ty.starts_with('&') || COPY_TYPES.contains(&ty.as_str()) {
+                params.push(format!("{}: {}", name, ty));
+                args.push(name.clone());
+            } else if binding.mutable && method_receivers.contains(name) {
+                params.push(format!("{}: &mut {}", name, ty));
+                args.push(format!("&mut {}", name));
+            } else {
+                params.push(format!("{}: &{}", name, ty));
+                args.push(format!("&{}", name));
+            }
+        }
+
+        let after: Vec<String> = children
+            .iter()
+            .filter(|c| c.start_byte() >= sel_end)
+            .flat_map(|c| Self::identifiers(*c, source))
+            .collect();
+        let mut outputs: Vec<(String, String, bool)> = Vec::new();
+        for stmt in selected.iter().filter(|s| s.kind() == "let_declaration") {
+            let Some(pattern) = stmt.child_by_field_name("pattern").filter(|p| p.kind() == "identifier") else {
+                continue;
+            };
+            let name = Self::text(pattern, source).to_string();
+            if !after.contains(&name) {
+                continue;
+            }
+            let ty = Self::declared_type(*stmt, source).ok_or_else(|| {
+                anyhow::anyhow!("Cannot infer the type of `{}` returned from the extracted lines; annotate it", name)
+            })?;
+            outputs.push((name, ty, Self::has_child(*stmt, "mutable_specifier")));
+        }
+
+        let last = *selected.last().unwrap_or(&block);
+        let is_tail = children.iter().rev().find(|c| !c.kind().ends_with("comment")) == Some(&last)
+            && !matches!(last.kind(), "expression_statement" | "let_declaration" | "empty_statement")
+            && !last.kind().ends_with("_item");
+        let return_type = if is_tail {
+            if function.child_by_field_name("body") != Some(block) {
+                return Err(anyhow::anyhow!("Cannot infer the type of the block's final expression"));
+            }
+            if !outputs.is_empty() {
+                return Err(anyhow::anyhow!("Cannot both return a value and define variables used later"));
+            }
+            function.child_by_field_name("return_type").map(|t| Self::text(t, source).to_string())
+        } else {
+            match outputs.len() {
+                0 => None,
+                1 => Some(outputs[0].1.clone()),
+                _ => Some(format!("({})", outputs.iter().map(|o| o.1.as_str()).collect::<Vec<_>>().join(", "))),
+            }
+        };
+
+        let mut body_replacements = Vec::new();
+        for stmt in &selected {
+            Self::visit(*stmt, &mut |node| {
+                if node.kind() == "identifier"
+                    && Self::is_variable_use(node)
+                    && deref.iter().any(|d| d == Self::text(node, source))
+                    && !Self::is_method_receiver(node)
+                {
+                    body_replacements.push((
+                        node.start_byte() - line_start,
+                        node.end_byte() - line_start,
+                        format!("*{}", Self::text(node, source)),
+                    ));
+                }
+            });
+        }
+        let body = Self::replace_ranges(selected_text, body_replacements);
+
+        let indent = Self::indentation(source, function.start_byte());
+        let body_indent = format!("{}    ", indent);
+        let dedent = body
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| l.len() - l.trim_start().len())
+            .min()
+            .unwrap_or(0);
+
+        let mut extracted = format!(
+            "{}{}fn {}({}){} {{\n",
+            indent,
+            if is_async { "async " } else { "" },
+            name,
+            params.join(", "),
+            return_type.map(|t| format!(" -> {}", t)).unwrap_or_default()
+        );
+        for line in body.lines() {
+            if line.trim().is_empty() {
+                extracted.push('\n');
+            } else {
+                extracted.push_str(&format!("{}{}\n", body_indent, &line[dedent.min(line.len())..]));
+            }
+        }
+        match outputs.len() {
+            0 => {}
+            1 => extracted.push_str(&format!("{}{}\n", body_indent, outputs[0].0)),
+            _ => extracted.push_str(&format!(
+                "{}({})\n",
+                body_indent,
+                outputs.iter().map(|o| o.0.as_str()).collect::<Vec<_>>().join(", ")
+            )),
+        }
+        extracted.push_str(&format!("{}}}", indent));
+
+        let receiver = if uses_self {
+            "self."
+        } else if in_impl {
+            "Self::"
+        } else {
+            ""
+        };
+        let call = format!(
+            "{}{}({}){}",
+            receiver,
+            name,
+            args.join(", "),
+            if is_async { ".await" } else { "" }
+        );
+        let binding = |o: &(String, String, bool)| format!("{}{}", if o.2 { "mut " } else { "" }, o.0);
+        let call_statement = match outputs.len() {
+            0 if is_tail => call,
+            0 => format!("{};", call),
+            1 => format!("let {} = {};", binding(&outputs[0]), call),
+            _ => format!(
+                "let ({}) = {};",
+                outputs.iter().map(binding).collect::<Vec<_>>().join(", "),
+                call
+            ),
+        };
+
+        let call_indent = Self::indentation(source, sel_start);
+        Ok(Self::replace_ranges(
+            source,
+            vec![
+                (line_start, line_start + selected_text.trim_end_matches('\n').len(), format!("{}{}", call_indent, call_statement)),
+                (function.end_byte(), function.end_byte(), format!("\n\n{}", extracted)),
+            ],
+        ))
+    }
+
+    pub fn inline_variable_source(source: &str, line: usize, name: &str) -> Result<String> {
+        let tree = Self::parse(source)?;
+        let mut declaration = None;
+        Self::visit(tree.root_node(), &mut |node| {
+            if declaration.is_none()
+                && node.kind() == "let_declaration"
+                && node.start_position().row + 1 == line
+                && node.child_by_field_name("pattern").is_some_and(|p| Self::text(p, source) == name)
+            {
+                declaration = Some(node);
+            }
+        });
+
+        let declaration = declaration.ok_or_else(|| anyhow::anyhow!("No `let {}` found on line {}", name, line))?;
+        if Self::has_child(declaration, "mutable_specifier") {
+            return Err(anyhow::anyhow!("Cannot inline mutable variable `{}`", name));
+        }
+        let value = declaration
+            .child_by_field_name("value")
+            .ok_or_else(|| anyhow::anyhow!("`{}` has no initializer to inline", name))?;
+        let block = declaration
+            .parent()
+            .ok_or_else(|| anyhow::anyhow!("`{}` is not declared in a block", name))?;
+
+        let mut uses = Vec::new();
+        let mut nested_shadow = false;
+        for child in Self::named_children(block)
+            .into_iter()
+            .filter(|c| c.start_byte() >= declaration.end_byte())
+        {
+            let mut shadowed = false;
+            Self::visit(child, &mut |node| {
+                if node.kind() != "identifier" || Self::text(node, source) != name {
+                    return;
+                }
+                if Self::is_binding(node) {
+                    if child.kind() == "let_declaration" && child.child_by_field_name("pattern") == Some(node) {
+                        shadowed = true;
+                    } else {
+                        nested_shadow = true;
+                    }
+                } else if Self::is_variable_use(node) {
+                    uses.push(node);
+                }
+            });
+            if shadowed {
+                break;
+            }
+        }
+
+        if nested_shadow {
+            return Err(anyhow::anyhow!("`{}` is shadowed in a nested scope; inline it manually", name));
+        }
+        if uses.is_empty() {
+            return Err(anyhow::anyhow!("`{}` is never used after line {}", name, line));
+        }
+        let mut side_effects = false;
+        Self::visit(value, &mut |node| {
+            side_effects |= matches!(
+                node.kind(),
+                "call_expression" | "macro_invocation" | "await_expression" | "assignment_expression" | "compound_assignment_expr"
+            );
+        });
+        if uses.len() > 1 && side_effects {
+            return Err(anyhow::anyhow!(
+                "`{}` is used {} times and its value calls functions or macros; inlining would evaluate it repeatedly",
+                name,
+                uses.len()
+            ));
+        }
+
+        let value_text = Self::text(value, source);
+        let inlined = if UNPARENTHESIZED_EXPRESSIONS.contains(&value.kind()) {
+            value_text.to_string()
+        } else {
+            format!("({})", value_text)
+        };
+
+        let mut replacements: Vec<(usize, usize, String)> = uses
+            .iter()
+            .map(|node| {
+                let text = if node.parent().is_some_and(|p| p.kind() == "shorthand_field_initializer") {
+                    format!("{}: {}", name, inlined)
+                } else {
+                    inlined.clone()
+                };
+                (node.start_byte(), node.end_byte(), text)
+            })
+            .collect();
+
+        let line_start = source[..declaration.start_byte()].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let line_end = source[declaration.end_byte()..]
+            .find('\n')
+            .map(|i| declaration.end_byte() + i + 1)
+            .unwrap_or(source.len());
+        let owns_line = source[line_start..declaration.start_byte()].trim().is_empty()
+            && source[declaration.end_byte()..line_end].trim().is_empty();
+        replacements.push(if owns_line {
+            (line_start, line_end, String::new())
+        } else {
+            (declaration.start_byte(), declaration.end_byte(), String::new())
+        });
+
+        Ok(Self::replace_ranges(source, replacements))
+    }
+
+    fn bindings_before(function: Node, block: Node, source: &str, sel_start: usize, sel_end: usize) -> HashMap<String, Binding> {
+        let mut bindings = HashMap::new();
+
+        if let Some(parameters) = function.child_by_field_name("parameters") {
+            for parameter in Self::named_children(parameters).into_iter().filter(|p| p.kind() == "parameter") {
+                let ty = parameter.child_by_field_name("type").map(|t| Self::text(t, source).to_string());
+                let mutable = Self::has_child(parameter, "mutable_specifier");
+                if let Some(pattern) = parameter.child_by_field_name("pattern") {
+                    Self::bind_pattern(pattern, source, ty, mutable, &mut bindings);
+                }
+            }
+        }
+
+        let mut scope = Some(block);
+        let mut scopes = Vec::new();
+        while let Some(node) = scope {
+            scopes.push(node);
+            if node == function {
+                break;
+            }
+            scope = node.parent();
+        }
+        for node in scopes.into_iter().rev() {
+            let pattern = match node.kind() {
+                "for_expression" | "let_condition" | "match_arm" => node.child_by_field_name("pattern"),
+                "closure_expression" => node.child_by_field_name("parameters"),
+                "if_expression" | "while_expression" => node
+                    .child_by_field_name("condition")
+                    .filter(|c| c.kind() == "let_condition")
+                    .and_then(|c| c.child_by_field_name("pattern")),
+                _ => None,
+            };
+            if let Some(pattern) = pattern {
+                Self::bind_pattern(pattern, source, None, false, &mut bindings);
+            }
+        }
+
+        if let Some(body) = function.child_by_field_name("body") {
+            Self::visit(body, &mut |node| {
+                if node.kind() != "let_declaration" || node.end_byte() > sel_start {
+                    return;
+                }
+                let in_scope = node
+                    .parent()
+                    .is_some_and(|p| p.start_byte() <= sel_start && p.end_byte() >= sel_end);
+                if let (true, Some(pattern)) = (in_scope, node.child_by_field_name("pattern")) {
+                    let ty = Self::declared_type(node, source);
+                    Self::bind_pattern(pattern, source, ty, Self::has_child(node, "mutable_specifier"), &mut bindings);
+                }
+            });
+        }
+
+        bindings
+    }
+
+    fn bind_pattern(pattern: Node, source: &str, ty: Option<String>, mutable: bool, bindings: &mut HashMap<String, Binding>) {
+        if pattern.kind() == "identifier" {
+            bindings.insert(Self::text(pattern, source).to_string(), Binding { ty, mutable });
+            return;
+        }
+        Self::visit(pattern, &mut |node| {
+            if node.kind() == "identifier" && Self::is_binding(node) {
+                bindings.insert(Self::text(node, source).to_string(), Binding { ty: None, mutable: false });
+            }
+        });
+    }
+
+    fn declared_type(declaration: Node, source: &str) -> Option<String> {
+        if let Some(ty) = declaration.child_by_field_name("type") {
+            return Some(Self::text(ty, source).to_string());
+        }
+        let value = declaration.child_by_field_name("value")?;
+        let text = Self::text(value, source);
+        match value.kind() {
+            "integer_literal" | "float_literal" => NUMERIC_SUFFIXES
+                .iter()
+                .find(|s| text.ends_with(*s))
+                .map(|s| s.to_string()),
+            "string_literal" | "raw_string_literal" => Some("&str".to_string()),
+            "boolean_literal" => Some("bool".to_string()),
+            "char_literal" => Some("char".to_string()),
+            "struct_expression" => value.child_by_field_name("name").map(|n| Self::text(n, source).to_string()),
+            "macro_invocation" if text.starts_with("format!") => Some("String".to_string()),
+            "call_expression" if text.starts_with("String::") || text.ends_with(".to_string()") || text.ends_with(".to_owned()") => {
+                Some("String".to_string())
+            }
+            _ => None,
+        }
+    }
+
+    fn is_variable_use(node: Node) -> bool {
+        let Some(parent) = node.parent() else {
+            return true;
+        };
+        match parent.kind() {
+            "scoped_identifier" | "scoped_type_identifier" | "scoped_use_list" | "use_declaration" => false,
+            "macro_invocation" => parent.child_by_field_name("macro") != Some(node),
+            "token_tree" => !node
+                .prev_sibling()
+                .is_some_and(|s| matches!(s.kind(), "." | "::") || s.kind() == "identifier" && s.end_byte() == node.start_byte()),
+            "call_expression" | "field_expression" | "arguments" | "binary_expression" | "unary_expression"
+            | "reference_expression" | "assignment_expression" | "compound_assignment_expr" | "index_expression"
+            | "shorthand_field_initializer" | "field_initializer" | "return_expression" | "expression_statement"
+            | "let_declaration" | "block" | "if_expression" | "while_expression" | "for_expression" | "match_expression"
+            | "tuple_expression" | "array_expression" | "parenthesized_expression" | "range_expression" | "try_expression"
+            | "await_expression" | "type_cast_expression" | "let_condition" | "match_arm" | "closure_expression" => {
+                !Self::is_binding(node)
+            }
+            _ => false,
+        }
+    }
+
+    fn is_binding(node: Node) -> bool {
+        let Some(parent) = node.parent() else {
+            return false;
+        };
+        match parent.kind() {
+            "let_declaration" | "for_expression" | "let_condition" => parent.child_by_field_name("pattern") == Some(node),
+            "closure_expression" => false,
+            kind => PATTERN_PARENTS.contains(&kind),
+        }
+    }
+
+    fn is_assignment_target(node: Node) -> bool {
+        node.parent().is_some_and(|p| match p.kind() {
+            "assignment_expression" | "compound_assignment_expr" => p.child_by_field_name("left") == Some(node),
+            "reference_expression" => Self::has_child(p, "mutable_specifier"),
+            _ => false,
+        })
+    }
+
+    fn is_method_receiver(node: Node) -> bool {
+        node.parent()
+            .is_some_and(|p| p.kind() == "field_expression" && p.child_by_field_name("value") == Some(node))
+    }
+
+    fn identifiers(node: Node, source: &str) -> Vec<String> {
+        let mut found = Vec::new();
+        Self::visit(node, &mut |n| {
+            if n.kind() == "identifier" {
+                found.push(Self::text(n, source).to_string());
+            }
+        });
+        found
+    }
+
+    fn inside(node: Node, stop: Node, kinds: &[&str]) -> bool {
+        let mut current = node.parent();
+        while let Some(parent) = current {
+            if kinds.contains(&parent.kind()) {
+                return true;
+            }
+            if parent == stop {
+                break;
+            }
+            current = parent.parent();
+        }
+        false
+    }
+
+    fn ancestor<'t>(node: Node<'t>, kind: &str) -> Option<Node<'t>> {
+        let mut current = node.parent();
+        while let Some(parent) = current {
+            if parent.kind() == kind {
+                return Some(parent);
+            }
+            current = parent.parent();
+        }
+        None
+    }
+
+    fn source_root(node: Node) -> Node {
+        let mut root = node;
+        while let Some(parent) = root.parent() {
+            root = parent;
+        }
+        root
+    }
+
+    fn has_child(node: Node, kind: &str) -> bool {
+        let mut cursor = node.walk();
+        let found = node.children(&mut cursor).any(|c| c.kind() == kind);
+        found
+    }
+
+    fn named_children(node: Node) -> Vec<Node> {
+        let mut cursor = node.walk();
+        node.named_children(&mut cursor).collect()
+    }
+
+    fn visit<'t>(node: Node<'t>, f: &mut dyn FnMut(Node<'t>)) {
+        f(node);
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            Self::visit(child, f);
+        }
+    }
+
+    fn line_range(source: &str, start_line: usize, end_line: usize) -> Result<(usize, usize)> {
+        if start_line == 0 || end_line < start_line {
+            return Err(anyhow::anyhow!("Invalid line range {}-{}", start_line, end_line));
+        }
+        let mut offsets = vec![0];
+        offsets.extend(source.match_indices('\n').map(|(i, _)| i + 1));
+        let start = *offsets
+            .get(start_line - 1)
+            .ok_or_else(|| anyhow::anyhow!("Line {} is past the end of the file", start_line))?;
+        let end = offsets.get(end_line).copied().unwrap_or(source.len());
+        Ok((start, end))
+    }
+
+    fn indentation(source: &str, byte: usize) -> String {
+        let line_start = source[..byte].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        source[line_start..]
+            .chars()
+            .take_while(|c| *c == ' ' || *c == '\t')
+            .collect()
+    }
+
+    fn replace_ranges(source: &str, mut replacements: Vec<(usize, usize, String)>) -> String {
+        replacements.sort_by_key(|r| std::cmp::Reverse(r.0));
+        let mut updated = source.to_string();
+        let mut floor = usize::MAX;
+        for (start, end, text) in replacements {
+            if end > floor {
+                continue;
+            }
+            updated.replace_range(start..end, &text);
+            floor = start;
+        }
+        updated
+    }
+
+    fn is_identifier(name: &str) -> bool {
+        let mut chars = name.chars();
+        chars.next().is_some_and(|c| c.is_alphabetic() || c == '_') && chars.all(|c| c.is_alphanumeric() || c == '_')
+    }
+
+    fn text<'s>(node: Node, source: &'s str) -> &'s str {
+        &source[node.byte_range()]
+    }
+
+    fn parse(source: &str) -> Result<Tree> {
+        let mut parser = Parser::new();
+        parser.set_language(tree_sitter_rust::language())?;
+        parser
+            .parse(source, None)
+            .ok_or_else(|| anyhow::anyhow!("Failed to parse Rust source"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rename_field_expands_shorthand() {
+        let source = "struct Point { x: i32 }\nfn make(x: i32) -> Point {\n    Point { x }\n}\nfn read(p: &Point) -> i32 {\n    let Point { x } = p;\n    p.x + *x\n}\n";
+        let target = RenameTarget {
+            name: "x".to_string(),
+            kind: RenameKind::Field,
+            owner: Some("Point".to_string()),
+            module: "crate".to_string(),
+            file: "src/lib.rs".to_string(),
+            line: 1,
+            has_receiver: false,
+            other_owners: Vec::new(),
+            other_modules: Vec::new(),
+        };
+        let outcome = RustRefactor::rename_source(source, "src/lib.rs", &target, "left").unwrap();
+
+        assert!(outcome.defines_old);
+        assert_eq!(
+            outcome.updated,
+            "struct Point { left: i32 }\nfn make(x: i32) -> Point {\n    Point { left: x }\n}\nfn read(p: &Point) -> i32 {\n    let Point { left: x } = p;\n    p.left + *x\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_rename_only_touches_the_resolved_member() {
+        let root = std::env::temp_dir().join(format!("codeagent-rename-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(
+            root.join("src/lib.rs"),
+            "pub struct Token { raw: String }\npub struct Session;\nimpl Token {\n    pub fn validate(&self) -> bool { !self.raw.is_empty() }\n}\nimpl Session {\n    pub fn validate(&self) -> bool { Token::validate(&Token { raw: String::new() }) }\n}\npub fn check(token: &Token, session: Session) -> bool {\n    let other = Token { raw: String::new() };\n    token.validate() && session.validate() && other.validate()\n}\n",
+        )
+        .unwrap();
+
+        let ambiguous = RustRefactor::rename(&root, "validate", "verify", RenameKind::Function).unwrap_err();
+        assert!(ambiguous.to_string().contains("Token::validate (src/lib.rs:4), Session::validate (src/lib.rs:7)"));
+
+        let refactoring = RustRefactor::rename(&root, "Token::validate", "verify", RenameKind::Function).unwrap();
+        assert_eq!(
+            refactoring.edits[0].updated,
+            "pub struct Token { raw: String }\npub struct Session;\nimpl Token {\n    pub fn verify(&self) -> bool { !self.raw.is_empty() }\n}\nimpl Session {\n    pub fn validate(&self) -> bool { Token::verify(&Token { raw: String::new() }) }\n}\npub fn check(token: &Token, session: Session) -> bool {\n    let other = Token { raw: String::new() };\n    token.verify() && session.validate() && other.verify()\n}\n"
+        );
+
+        std::fs::write(root.join("src/relay.rs"), "fn relay(items: Vec<crate::Token>) -> bool {\n    items[0].validate()\n}\n").unwrap();
+        let unresolved = RustRefactor::rename(&root, "Token::validate", "verify", RenameKind::Function).unwrap_err();
+        assert!(unresolved.to_string().contains("src/relay.rs:2"));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_extract_function_passes_inputs_and_returns_outputs() {
+        let source = "fn total(items: &[u32]) -> u32 {\n    let mut sum: u32 = 0;\n    let base = 10u32;\n    for item in items {\n        sum += item * base;\n    }\n    let doubled: u32 = sum * 2;\n    doubled + 1\n}\n";
+        let updated = RustRefactor::extract_function_source(source, 4, 7, "accumulate").unwrap();
+
+        assert_eq!(
+            updated,
+            "fn total(items: &[u32]) -> u32 {\n    let mut sum: u32 = 0;\n    let base = 10u32;\n    let doubled = accumulate(items, &mut sum, base);\n    doubled + 1\n}\n\nfn accumulate(items: &[u32], sum: &mut u32, base: u32) -> u32 {\n    for item in items {\n        *sum += item * base;\n    }\n    let doubled: u32 = *sum * 2;\n    doubled\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_inline_variable() {
+        let source = "fn area(w: u32, h: u32) -> u32 {\n    let size = w * h;\n    let total = size + 1;\n    total * 2\n}\n";
+        let updated = RustRefactor::inline_variable_source(source, 2, "size").unwrap();
+        assert_eq!(updated, "fn area(w: u32, h: u32) -> u32 {\n    let total = (w * h) + 1;\n    total * 2\n}\n");
+
+        let repeated = "fn f() -> u32 {\n    let v = compute();\n    v + v\n}\n";
+        assert!(RustRefactor::inline_variable_source(repeated, 2, "v").is_err());
+    }
+}
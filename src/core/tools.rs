@@ -0,0 +1,386 @@
+use crate::config::yaml_parser::{CustomTool, YamlConfig};
+use crate::core::patch::PatchApplier;
+use crate::core::refactor::{RenameKind, RustRefactor};
+use crate::index::SymbolIndex;
+use crate::integrations::custom_tools::CustomToolRunner;
+use crate::utils::replace::SearchReplace;
+use crate::utils::search::SearchOptions;
+use crate::utils::WriteOptions;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone)]
+pub struct ToolParameter {
+    pub name: String,
+    pub description: String,
+    pub required: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct ToolSpec {
+    pub name: String,
+    pub description: String,
+    pub parameters: Vec<ToolParameter>,
+}
+
+pub trait AgentTool: Send + Sync {
+    fn spec(&self) -> ToolSpec;
+
+    fn execute(&self, args: &HashMap<String, String>) -> Result<String>;
+}
+
+#[derive(Default)]
+pub struct ToolRegistry {
+    tools: Vec<Box<dyn AgentTool>>,
+}
+
+impl ToolRegistry {
+    pub fn new() -> Self {
+        ToolRegistry { tools: Vec::new() }
+    }
+
+    pub fn with_builtin_tools(project_root: PathBuf) -> Self {
+        let mut registry = Self::new();
+        registry.register(Box::new(SymbolLookupTool::new(project_root.clone())));
+        registry.register(Box::new(SearchReplaceTool::new(project_root.clone())));
+        registry.register(Box::new(ApplyPatchTool::new(project_root.clone())));
+        registry.register(Box::new(RustRefactorTool::new(project_root)));
+        registry
+    }
+
+    pub fn for_project(project_root: PathBuf) -> Self {
+        let config = YamlConfig::load(&project_root.join(".codeagent.yml")).unwrap_or_default();
+        let mut registry = Self::with_builtin_tools(project_root.clone());
+        registry.register_custom_tools(&project_root, &config);
+        registry
+    }
+
+    pub fn register_custom_tools(&mut self, project_root: &std::path::Path, config: &YamlConfig) {
+        for tool in config.custom_tools.values().filter(|t| t.enabled) {
+            if self.tools.iter().any(|t| t.spec().name == tool.name) {
+                tracing::warn!("Custom tool '{}' clashes with a built-in tool and was skipped", tool.name);
+                continue;
+            }
+            self.register(Box::new(CustomCommandTool::new(tool.clone(), project_root.to_path_buf())));
+        }
+    }
+
+    pub fn register(&mut self, tool: Box<dyn AgentTool>) {
+        let name = tool.spec().name;
+        self.tools.retain(|t| t.spec().name != name);
+        self.tools.push(tool);
+    }
+
+    pub fn specs(&self) -> Vec<ToolSpec> {
+        self.tools.iter().map(|t| t.spec()).collect()
+    }
+
+    pub fn execute(&self, name: &str, args: &HashMap<String, String>) -> Result<String> {
+        let tool = self
+            .tools
+            .iter()
+            .find(|t| t.spec().name == name)
+            .ok_or_else(|| anyhow::anyhow!("Unknown tool: {}", name))?;
+
+        for param in tool.spec().parameters.iter().filter(|p| p.required) {
+            if !args.contains_key(&param.name) {
+                return Err(anyhow::anyhow!(
+                    "Tool {} is missing required argument: {}",
+                    name,
+                    param.name
+                ));
+            }
+        }
+
+        tool.execute(args)
+    }
+
+    pub fn describe_for_prompt(&self) -> String {
+        let mut description = String::from("Available tools:\n");
+        for spec in self.specs() {
+            description.push_str(&format!("- {}: {}\n", spec.name, spec.description));
+            for param in &spec.parameters {
+                description.push_str(&format!(
+                    "    {}{}: {}\n",
+                    param.name,
+                    if param.required { "" } else { " (optional)" },
+                    param.description
+                ));
+            }
+        }
+        description
+    }
+}
+
+pub struct SymbolLookupTool {
+    project_root: PathBuf,
+}
+
+impl SymbolLookupTool {
+    pub fn new(project_root: PathBuf) -> Self {
+        SymbolLookupTool { project_root }
+    }
+}
+
+impl AgentTool for SymbolLookupTool {
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: "find_symbol".to_string(),
+            description: "Find where a function, type or method is defined and, optionally, where it is referenced".to_string(),
+            parameters: vec![
+                ToolParameter {
+                    name: "name".to_string(),
+                    description: "Exact symbol name, e.g. validate_token".to_string(),
+                    required: true,
+                },
+                ToolParameter {
+                    name: "references".to_string(),
+                    description: "\"true\" to also list references".to_string(),
+                    required: false,
+                },
+            ],
+        }
+    }
+
+    fn execute(&self, args: &HashMap<String, String>) -> Result<String> {
+        let name = args.get("name").map(|s| s.as_str()).unwrap_or_default();
+        let with_references = args.get("references").is_some_and(|v| v == "true");
+
+        let mut index = SymbolIndex::load(&self.project_root);
+        index.refresh()?;
+
+        let mut output = String::new();
+        let definitions = index.find_definitions(name);
+        if definitions.is_empty() {
+            output.push_str(&format!("No definition found for {}\n", name));
+        }
+        for symbol in definitions {
+            output.push_str(&format!(
+                "{}:{}:{} {} {}\n",
+                symbol.file, symbol.line, symbol.column, symbol.kind, symbol.signature
+            ));
+        }
+
+        if with_references {
+            let references = index.find_references(name);
+            output.push_str(&format!("\n{} references:\n", references.len()));
+            for reference in references {
+                output.push_str(&format!(
+                    "{}:{}:{}\n",
+                    reference.file, reference.line, reference.column
+                ));
+            }
+        }
+
+        Ok(output)
+    }
+}
+
+pub struct SearchReplaceTool {
+    project_root: PathBuf,
+}
+
+impl SearchReplaceTool {
+    pub fn new(project_root: PathBuf) -> Self {
+        SearchReplaceTool { project_root }
+    }
+}
+
+impl AgentTool for SearchReplaceTool {
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: "search_replace".to_string(),
+            description: "Regex search-and-replace across the project; returns a diff and only writes when apply is true".to_string(),
+            parameters: vec![
+                ToolParameter {
+                    name: "pattern".to_string(),
+                    description: "Regular expression to search for".to_string(),
+                    required: true,
+                },
+                ToolParameter {
+                    name: "replacement".to_string(),
+                    description: "Replacement text; $1 or ${name} insert capture groups".to_string(),
+                    required: true,
+                },
+                ToolParameter {
+                    name: "apply".to_string(),
+                    description: "\"true\" to write the changes, otherwise preview only".to_string(),
+                    required: false,
+                },
+            ],
+        }
+    }
+
+    fn execute(&self, args: &HashMap<String, String>) -> Result<String> {
+        let pattern = args.get("pattern").map(|s| s.as_str()).unwrap_or_default();
+        let replacement = args.get("replacement").map(|s| s.as_str()).unwrap_or_default();
+        let apply = args.get("apply").is_some_and(|v| v == "true");
+
+        let plan = SearchReplace::plan_project(pattern, replacement, &self.project_root, &SearchOptions::default())?;
+        if plan.files.is_empty() {
+            return Ok(format!("No matches for {}", pattern));
+        }
+
+        let mut output = plan.diff();
+        if apply {
+            let written = SearchReplace::apply(&plan, &WriteOptions::with_backups(&self.project_root))?;
+            output.push_str(&format!("\nApplied {} changes to {} files\n", plan.accepted_count(), written));
+        }
+        Ok(output)
+    }
+}
+
+pub struct ApplyPatchTool {
+    project_root: PathBuf,
+}
+
+impl ApplyPatchTool {
+    pub fn new(project_root: PathBuf) -> Self {
+        ApplyPatchTool { project_root }
+    }
+}
+
+impl AgentTool for ApplyPatchTool {
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: "apply_patch".to_string(),
+            description: "Apply a unified diff or SEARCH/REPLACE blocks; reports conflicts per hunk and only writes when every hunk applies".to_string(),
+            parameters: vec![
+                ToolParameter {
+                    name: "patch".to_string(),
+                    description: "Unified diff or file path followed by <<<<<<< SEARCH / ======= / >>>>>>> REPLACE blocks".to_string(),
+                    required: true,
+                },
+                ToolParameter {
+                    name: "dry_run".to_string(),
+                    description: "\"true\" to return the resulting diff without writing".to_string(),
+                    required: false,
+                },
+            ],
+        }
+    }
+
+    fn execute(&self, args: &HashMap<String, String>) -> Result<String> {
+        let patch = args.get("patch").map(|s| s.as_str()).unwrap_or_default();
+        let dry_run = args.get("dry_run").is_some_and(|v| v == "true");
+
+        let applier = PatchApplier::new(&self.project_root).with_write_options(WriteOptions::with_backups(&self.project_root));
+        let report = applier.apply_text(patch, dry_run)?;
+        if report.has_conflicts() {
+            return Ok(format!("Patch not applied, conflicts:\n{}", report.conflict_summary()));
+        }
+
+        let mut output = report.diff.clone();
+        if report.applied {
+            output.push_str(&format!("\nApplied patch to {} files\n", report.files.len()));
+        }
+        Ok(output)
+    }
+}
+
+pub struct RustRefactorTool {
+    project_root: PathBuf,
+}
+
+impl RustRefactorTool {
+    pub fn new(project_root: PathBuf) -> Self {
+        RustRefactorTool { project_root }
+    }
+}
+
+impl AgentTool for RustRefactorTool {
+    fn spec(&self) -> ToolSpec {
+        let parameter = |name: &str, description: &str, required: bool| ToolParameter {
+            name: name.to_string(),
+            description: description.to_string(),
+            required,
+        };
+        ToolSpec {
+            name: "rust_refactor".to_string(),
+            description: "Rename, extract function or inline variable in Rust code; every change is checked with cargo check and only written when it passes".to_string(),
+            parameters: vec![
+                parameter("action", "rename, extract or inline", true),
+                parameter("kind", "For rename: fn, struct or field", false),
+                parameter("old", "For rename: current name, qualified as Type::name or module::name if it is defined more than once", false),
+                parameter("new", "For rename: new name", false),
+                parameter("file", "For extract and inline: project-relative file", false),
+                parameter("start", "For extract: first line", false),
+                parameter("end", "For extract: last line", false),
+                parameter("line", "For inline: line of the let binding", false),
+                parameter("name", "For extract: new function name; for inline: variable name", false),
+                parameter("apply", "\"true\" to write the verified changes, otherwise preview only", false),
+            ],
+        }
+    }
+
+    fn execute(&self, args: &HashMap<String, String>) -> Result<String> {
+        let arg = |name: &str| {
+            args.get(name)
+                .map(|s| s.as_str())
+                .ok_or_else(|| anyhow::anyhow!("Missing argument '{}'", name))
+        };
+        let line = |name: &str| -> Result<usize> { Ok(arg(name)?.parse()?) };
+        let root = &self.project_root;
+
+        let refactoring = match arg("action")? {
+            "rename" => RustRefactor::rename(root, arg("old")?, arg("new")?, RenameKind::parse(arg("kind")?)?)?,
+            "extract" => RustRefactor::extract_function(root, arg("file")?, line("start")?, line("end")?, arg("name")?)?,
+            "inline" => RustRefactor::inline_variable(root, arg("file")?, line("line")?, arg("name")?)?,
+            other => return Err(anyhow::anyhow!("Unknown refactoring '{}'", other)),
+        };
+
+        // Previews are checked too, so the model never proposes a change that does not build.
+        let mut output = format!("{}\n{}", refactoring.description, refactoring.diff());
+        let check = refactoring.verify()?;
+        if !check.success {
+            output.push_str(&format!("\ncargo check failed; nothing was written:\n{}", check.output));
+            return Ok(output);
+        }
+        if args.get("apply").is_some_and(|v| v == "true") {
+            let written = refactoring.apply(&WriteOptions::with_backups(root))?;
+            output.push_str(&format!("\ncargo check passed; applied to {} files\n", written));
+        } else {
+            output.push_str("\ncargo check passed; preview only, nothing was written\n");
+        }
+        Ok(output)
+    }
+}
+
+pub struct CustomCommandTool {
+    tool: CustomTool,
+    project_root: PathBuf,
+}
+
+impl CustomCommandTool {
+    pub fn new(tool: CustomTool, project_root: PathBuf) -> Self {
+        CustomCommandTool { tool, project_root }
+    }
+}
+
+impl AgentTool for CustomCommandTool {
+    fn spec(&self) -> ToolSpec {
+        ToolSpec {
+            name: self.tool.name.clone(),
+            description: self
+                .tool
+                .description
+                .clone()
+                .unwrap_or_else(|| format!("Run `{} {}` in the project", self.tool.command, self.tool.args.join(" "))),
+            parameters: CustomToolRunner::placeholders(&self.tool)
+                .into_iter()
+                .filter(|name| name != "project_root")
+                .map(|name| ToolParameter {
+                    description: format!("Value substituted for {{{}}}", name),
+                    name,
+                    required: true,
+                })
+                .collect(),
+        }
+    }
+
+    fn execute(&self, args: &HashMap<String, String>) -> Result<String> {
+        let output = CustomToolRunner::run(&self.tool, &self.project_root, args)?;
+        Ok(output.to_text())
+    }
+}
@@ -1,3 +1,4 @@
+use crate::parsers::lint_parser::LintSeverity;
 use crate::parsers::{TestResult, TestStatus, LintResult};
 use std::collections::HashMap;
 
@@ -55,6 +56,10 @@ impl ErrorAnalyzer {
     }
 
     pub fn analyze_lint_issue(lint: &LintResult) -> Option<FixSuggestion> {
+        if let Some(suggestion) = Self::analyze_compiler_error(lint) {
+            return Some(suggestion);
+        }
+
         if lint.suggestion.is_some() {
             return Some(FixSuggestion {
                 error_pattern: lint.rule.clone(),
@@ -93,6 +98,38 @@ impl ErrorAnalyzer {
         None
     }
 
+    fn analyze_compiler_error(lint: &LintResult) -> Option<FixSuggestion> {
+        if lint.severity < LintSeverity::Error || !lint.rule.starts_with('E') {
+            return None;
+        }
+
+        let (error_pattern, suggested_fix, confidence) = match lint.rule.as_str() {
+            "E0425" | "E0433" | "E0412" | "E0432" => (
+                "undefined_reference",
+                "Import or define the missing item, or fix the misspelled path",
+                0.85,
+            ),
+            "E0308" => ("type_mismatch", "Convert the value or change the declared type so both sides agree", 0.85),
+            "E0599" | "E0609" => ("unknown_member", "Call a method or field that exists on this type", 0.8),
+            "E0061" | "E0107" => ("wrong_arity", "Pass the number of arguments the signature expects", 0.85),
+            "E0382" | "E0499" | "E0502" | "E0505" | "E0506" => (
+                "borrow_error",
+                "Clone the value, shorten the borrow or restructure ownership",
+                0.7,
+            ),
+            _ => ("compile_error", "Fix the compiler error at this location", 0.6),
+        };
+
+        Some(FixSuggestion {
+            error_pattern: error_pattern.to_string(),
+            suggested_fix: format!("{}: {}", suggested_fix, lint.message),
+            confidence,
+            file: Some(lint.file.clone()),
+            line: Some(lint.line),
+            auto_fixable: true,
+        })
+    }
+
     pub fn correlate_errors(
         test_failures: &[TestResult],
         lint_issues: &[LintResult],
@@ -139,14 +176,24 @@ impl ErrorAnalyzer {
     pub fn generate_retry_strategy(
         suggestions: &[FixSuggestion],
         attempt: usize,
+    ) -> RetryStrategy {
+        Self::generate_retry_strategy_with_limit(suggestions, attempt, 3)
+    }
+
+    pub fn generate_retry_strategy_with_limit(
+        suggestions: &[FixSuggestion],
+        attempt: usize,
+        max_attempts: usize,
     ) -> RetryStrategy {
         let auto_fixable_count = suggestions.iter().filter(|s| s.auto_fixable).count();
         let high_confidence = suggestions.iter().filter(|s| s.confidence > 0.8).count();
 
         RetryStrategy {
-            retry_recommended: attempt < 3 && !suggestions.is_empty(),
+            retry_recommended: attempt < max_attempts && !suggestions.is_empty(),
             apply_auto_fixes: auto_fixable_count > 0 && high_confidence >= auto_fixable_count / 2,
-            escalate_to_user: high_confidence == 0 || attempt >= 3,
+            // Low-confidence diagnoses only stop the loop once a fix has already been tried.
+            escalate_to_user: attempt >= max_attempts
+                || (attempt > 0 && !suggestions.is_empty() && high_confidence == 0),
             suggested_delay_ms: 100 * (attempt as u64 + 1),
         }
     }
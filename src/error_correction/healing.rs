@@ -0,0 +1,347 @@
+use super::analyzer::{ErrorAnalyzer, FixSuggestion};
+use crate::config::yaml_parser::YamlConfig;
+use crate::integrations::sandbox::{CommandRunner, CommandSpec};
+use crate::integrations::test_frameworks::{TestFramework, TestFrameworkDetector};
+use crate::core::patch::{FileStatus, PatchApplier};
+use crate::core::policy::PolicyEnforcer;
+use crate::interactive::mode::{InteractiveMode, UserChoice};
+use crate::llm::client::LLMClient;
+use crate::parsers::lint_parser::{LintParser, LintSeverity};
+use crate::parsers::{LintResult, TestResult, TestStatus};
+use crate::utils::{FileUtils, WriteOptions};
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+const MAX_OUTPUT_CHARS: usize = 6000;
+const MAX_FILE_CHARS: usize = 8000;
+const MAX_CONTEXT_FILES: usize = 6;
+
+const FIX_SYSTEM_PROMPT: &str = "You fix build and test failures in an existing project. \
+Reply only with SEARCH/REPLACE blocks (the file path on its own line, then <<<<<<< SEARCH, the exact \
+lines to replace, =======, the new lines, >>>>>>> REPLACE) or with a unified diff. Change as little as possible.";
+
+#[derive(Debug, Clone)]
+pub struct VerificationOutcome {
+    pub passed: bool,
+    pub output: String,
+    pub test_results: Vec<TestResult>,
+    pub lint_issues: Vec<LintResult>,
+}
+
+impl VerificationOutcome {
+    pub fn failures(&self) -> Vec<TestResult> {
+        self.test_results
+            .iter()
+            .filter(|t| t.status == TestStatus::Failed)
+            .cloned()
+            .collect()
+    }
+}
+
+pub struct Verifier;
+
+impl Verifier {
+    pub fn run(project_root: &Path) -> Result<VerificationOutcome> {
+        let suites = TestFrameworkDetector::suites(project_root)?;
+        if suites.is_empty() {
+            return Ok(VerificationOutcome {
+                passed: true,
+                output: "No build or test setup detected".to_string(),
+                test_results: Vec::new(),
+                lint_issues: Vec::new(),
+            });
+        }
+
+        for suite in &suites {
+            let build: Option<&[&str]> = match suite.framework {
+                TestFramework::Cargo => Some(&["cargo", "build", "--all-targets", "--message-format", "short"]),
+                TestFramework::GoTest => Some(&["go", "build", "./..."]),
+                _ => None,
+            };
+            if let Some(args) = build {
+                let (success, output) = Self::execute(&project_root.join(&suite.dir), args)?;
+                if !success {
+                    return Ok(VerificationOutcome {
+                        passed: false,
+                        lint_issues: LintParser::parse_clippy_output(&output)
+                            .into_iter()
+                            .filter(|l| l.severity >= LintSeverity::Error)
+                            .collect(),
+                        test_results: Vec::new(),
+                        output,
+                    });
+                }
+            }
+        }
+
+        let mut outcome = VerificationOutcome {
+            passed: true,
+            output: String::new(),
+            test_results: Vec::new(),
+            lint_issues: Vec::new(),
+        };
+        for suite in &suites {
+            let command = suite.report_command();
+            let command: Vec<&str> = command.iter().map(|s| s.as_str()).collect();
+            let (success, output) = Self::execute(&project_root.join(&suite.dir), &command)?;
+            let test_results = suite.framework.parse_output(&output);
+            outcome.passed &= success;
+            if suites.len() > 1 {
+                outcome.output.push_str(&format!("=== {} ===\n", suite.label()));
+            }
+            outcome.output.push_str(&output);
+            outcome.test_results.extend(test_results);
+        }
+
+        Ok(outcome)
+    }
+
+    fn execute(project_root: &Path, args: &[&str]) -> Result<(bool, String)> {
+        let spec = CommandSpec::for_project(args[0], &args[1..], project_root);
+        let output = CommandRunner::run_blocking(&spec)?;
+        let mut combined = format!("{}{}", output.stdout, output.stderr);
+        if output.timed_out {
+            combined.push_str(&format!("\n{} timed out after {:?}\n", spec.display(), spec.timeout));
+        }
+        Ok((output.success(), combined))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HealingStatus {
+    Passed,
+    Exhausted,
+    Escalated,
+    Disabled,
+}
+
+#[derive(Debug, Clone)]
+pub struct HealingAttempt {
+    pub attempt: usize,
+    pub suggestions: Vec<FixSuggestion>,
+    pub applied: bool,
+    pub message: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct HealingReport {
+    pub status: HealingStatus,
+    pub attempts: Vec<HealingAttempt>,
+    pub changed_files: Vec<String>,
+    pub last_output: String,
+}
+
+impl HealingReport {
+    pub fn summary(&self) -> String {
+        let mut summary = match self.status {
+            HealingStatus::Passed if self.attempts.is_empty() => "Build and tests passed".to_string(),
+            HealingStatus::Passed => format!("Build and tests passed after {} fix attempts", self.attempts.len()),
+            HealingStatus::Exhausted => format!("Still failing after {} fix attempts", self.attempts.len()),
+            HealingStatus::Escalated => "Stopped automatic fixing; needs your attention".to_string(),
+            HealingStatus::Disabled => "Automatic fixing is disabled (auto_fix_enabled: false)".to_string(),
+        };
+        for attempt in &self.attempts {
+            summary.push_str(&format!("\n  attempt {}: {}", attempt.attempt, attempt.message));
+        }
+        summary
+    }
+}
+
+pub struct SelfHealingLoop {
+    project_root: PathBuf,
+    enabled: bool,
+    max_attempts: usize,
+    interactive: bool,
+}
+
+impl SelfHealingLoop {
+    pub fn new(project_root: &Path, config: &YamlConfig) -> Self {
+        SelfHealingLoop {
+            project_root: project_root.to_path_buf(),
+            enabled: config.auto_fix_enabled,
+            max_attempts: config.max_retry_attempts as usize,
+            interactive: config.interactive_mode,
+        }
+    }
+
+    pub async fn run(&self, client: &dyn LLMClient, changed_files: &[String]) -> Result<HealingReport> {
+        let mut report = HealingReport {
+            status: HealingStatus::Disabled,
+            attempts: Vec::new(),
+            changed_files: changed_files.to_vec(),
+            last_output: String::new(),
+        };
+        if !self.enabled {
+            return Ok(report);
+        }
+
+        for attempt in 0..=self.max_attempts {
+            let outcome = Verifier::run(&self.project_root)?;
+            report.last_output = outcome.output.clone();
+            if outcome.passed {
+                report.status = HealingStatus::Passed;
+                return Ok(report);
+            }
+            if attempt == self.max_attempts {
+                break;
+            }
+
+            let suggestions = ErrorAnalyzer::correlate_errors(&outcome.failures(), &outcome.lint_issues);
+            let strategy = ErrorAnalyzer::generate_retry_strategy_with_limit(&suggestions, attempt, self.max_attempts);
+            if strategy.escalate_to_user && !self.confirm_escalation(&suggestions) {
+                report.status = HealingStatus::Escalated;
+                return Ok(report);
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(strategy.suggested_delay_ms)).await;
+
+            let prompt = self.fix_prompt(&outcome, &suggestions, &report, attempt + 1);
+            tracing::info!("Requesting fix {}/{} from {}", attempt + 1, self.max_attempts, client.provider_name());
+            let response = client.generate_with_context(FIX_SYSTEM_PROMPT, &prompt).await?;
+
+            let applier = PatchApplier::new(&self.project_root).with_write_options(WriteOptions::with_backups(&self.project_root));
+            let (applied, message) = match applier.apply_text(&response.content, false) {
+                Ok(patch) if patch.applied => {
+                    for file in patch.files.iter().filter(|f| f.status != FileStatus::Conflict) {
+                        if !report.changed_files.contains(&file.path) {
+                            report.changed_files.push(file.path.clone());
+                        }
+                    }
+                    let files: Vec<&str> = patch.files.iter().map(|f| f.path.as_str()).collect();
+                    (true, format!("patched {}", files.join(", ")))
+                }
+                Ok(patch) => (false, format!("patch did not apply:\n{}", patch.conflict_summary().trim_end())),
+                Err(e) => (false, format!("no usable patch in response: {}", e)),
+            };
+
+            report.attempts.push(HealingAttempt {
+                attempt: attempt + 1,
+                suggestions,
+                applied,
+                message,
+            });
+        }
+
+        report.status = HealingStatus::Exhausted;
+        Ok(report)
+    }
+
+    fn confirm_escalation(&self, suggestions: &[FixSuggestion]) -> bool {
+        // Under the TUI (or without a terminal) there is nobody to answer, so stop and report.
+        if !self.interactive || !InteractiveMode::can_prompt() {
+            tracing::info!("Escalating after {} low-confidence fix suggestions", suggestions.len());
+            return false;
+        }
+        println!("Automatic fixes look unlikely to succeed ({} suggestions, none high-confidence).", suggestions.len());
+        matches!(
+            InteractiveMode::prompt_for_approval("Ask the model for another fix?"),
+            UserChoice::Approve | UserChoice::Continue
+        )
+    }
+
+    fn fix_prompt(&self, outcome: &VerificationOutcome, suggestions: &[FixSuggestion], report: &HealingReport, attempt: usize) -> String {
+        let mut prompt = format!("Fix attempt {} of {}. The project no longer builds or its tests fail.\n\n", attempt, self.max_attempts);
+
+        if !suggestions.is_empty() {
+            prompt.push_str("## Diagnosed problems\n");
+            for s in suggestions {
+                let location = match (&s.file, s.line) {
+                    (Some(file), Some(line)) => format!("{}:{}", file, line),
+                    (Some(file), None) => file.clone(),
+                    _ => "unknown location".to_string(),
+                };
+                prompt.push_str(&format!("- {} [{}]: {}\n", location, s.error_pattern, s.suggested_fix));
+            }
+            prompt.push('\n');
+        }
+
+        if let Some(previous) = report.attempts.last().filter(|a| !a.applied) {
+            prompt.push_str(&format!("## Your previous reply could not be applied\n{}\n\n", previous.message));
+        }
+
+        let output = &outcome.output;
+        let tail = output
+            .char_indices()
+            .nth(output.chars().count().saturating_sub(MAX_OUTPUT_CHARS))
+            .map(|(i, _)| &output[i..])
+            .unwrap_or(output);
+        prompt.push_str(&format!("## Failing output\n```\n{}\n```\n\n", tail.trim()));
+
+        let mut files: Vec<String> = suggestions.iter().filter_map(|s| s.file.clone()).collect();
+        files.extend(report.changed_files.iter().cloned());
+        let policy = PolicyEnforcer::for_project(&self.project_root);
+        let mut seen = Vec::new();
+        for file in files {
+            let file = file.trim_start_matches("./").to_string();
+            if seen.contains(&file) || seen.len() >= MAX_CONTEXT_FILES || !policy.can_read(Path::new(&file)) {
+                continue;
+            }
+            let Ok(content) = FileUtils::read_file(&self.project_root.join(&file)) else {
+                continue;
+            };
+            let content: String = content.chars().take(MAX_FILE_CHARS).collect();
+            prompt.push_str(&format!("## {}\n```\n{}\n```\n\n", file, content));
+            seen.push(file);
+        }
+
+        prompt.push_str("Reply with the edits that make the build and tests pass.");
+        prompt
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::client::ScriptedClient;
+
+    #[test]
+    fn test_cargo_failure_details_feed_the_analyzer() {
+        let output = "running 2 tests\ntest math::tests::adds ... FAILED\ntest math::tests::subs ... ok\n\nfailures:\n\n---- math::tests::adds stdout ----\n\nthread 'math::tests::adds' panicked at src/math.rs:12:9:\nassertion `left == right` failed\n  left: 3\n right: 4\nnote: run with `RUST_BACKTRACE=1` environment variable to display a backtrace\n\n\nfailures:\n    math::tests::adds\n";
+        let results = TestFramework::Cargo.parse_output(output);
+
+        let failed = results.iter().find(|r| r.status == TestStatus::Failed).unwrap();
+        assert_eq!(failed.file, "src/math.rs");
+        assert_eq!(failed.line, Some(12));
+        assert!(failed.error_message.as_deref().unwrap().starts_with("assertion `left == right` failed"));
+
+        let compile_errors = LintParser::parse_clippy_output("src/math.rs:3:5: error[E0425]: cannot find value `x` in this scope\n");
+        let suggestions = ErrorAnalyzer::correlate_errors(&results, &compile_errors);
+        assert_eq!(suggestions[0].error_pattern, "undefined_reference");
+        assert_eq!(suggestions[1].error_pattern, "assertion_error");
+        assert!(!ErrorAnalyzer::generate_retry_strategy_with_limit(&suggestions, 0, 3).escalate_to_user);
+        assert!(!ErrorAnalyzer::generate_retry_strategy_with_limit(&suggestions[1..], 0, 3).escalate_to_user);
+        assert!(ErrorAnalyzer::generate_retry_strategy_with_limit(&suggestions[1..], 1, 3).escalate_to_user);
+    }
+
+    #[tokio::test]
+    async fn test_failing_assertion_is_fixed_by_the_model() {
+        let root = std::env::temp_dir().join(format!("codeagent-heal-loop-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(
+            root.join("Cargo.toml"),
+            "[package]\nname = \"heal_loop\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[workspace]\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("src/lib.rs"),
+            "pub fn double(x: i32) -> i32 {\n    x * 3\n}\n\n#[test]\nfn doubles() {\n    assert_eq!(double(2), 4);\n}\n",
+        )
+        .unwrap();
+        let config = YamlConfig {
+            auto_fix_enabled: true,
+            max_retry_attempts: 2,
+            ..YamlConfig::default()
+        };
+
+        let client = ScriptedClient::new(&["src/lib.rs\n<<<<<<< SEARCH\n    x * 3\n=======\n    x * 2\n>>>>>>> REPLACE\n"]);
+        let report = SelfHealingLoop::new(&root, &config).run(&client, &["src/lib.rs".to_string()]).await.unwrap();
+
+        assert_eq!(report.status, HealingStatus::Passed, "{}", report.last_output);
+        assert_eq!(report.attempts.len(), 1);
+        assert!(report.attempts[0].applied);
+        assert_eq!(report.attempts[0].suggestions[0].error_pattern, "assertion_error");
+        assert!(client.prompts.lock().unwrap()[0].contains("x * 3"));
+        assert!(std::fs::read_to_string(root.join("src/lib.rs")).unwrap().contains("x * 2"));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
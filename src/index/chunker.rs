@@ -0,0 +1,143 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+pub const MAX_CHUNK_LINES: usize = 80;
+const WINDOW_OVERLAP: usize = 10;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CodeChunk {
+    pub start_line: usize,
+    pub end_line: usize,
+    pub name: Option<String>,
+    pub text: String,
+}
+
+pub struct Chunker;
+
+impl Chunker {
+    pub fn chunk(content: &str) -> Vec<CodeChunk> {
+        let lines: Vec<&str> = content.lines().collect();
+        if lines.is_empty() {
+            return Vec::new();
+        }
+
+        let mut starts: Vec<(usize, Option<String>)> = Vec::new();
+        for (idx, line) in lines.iter().enumerate() {
+            if let Some(name) = Self::definition_name(line) {
+                starts.push((Self::leading_comment_start(&lines, idx), Some(name)));
+            }
+        }
+
+        if starts.first().map(|(start, _)| *start) != Some(0) {
+            starts.insert(0, (0, None));
+        }
+
+        let mut chunks = Vec::new();
+        for (i, (start, name)) in starts.iter().enumerate() {
+            let end = starts
+                .get(i + 1)
+                .map(|(next, _)| *next)
+                .unwrap_or(lines.len());
+            if end <= *start {
+                continue;
+            }
+            Self::push_windows(&mut chunks, &lines, *start, end, name.clone());
+        }
+
+        chunks
+    }
+
+    fn push_windows(
+        chunks: &mut Vec<CodeChunk>,
+        lines: &[&str],
+        start: usize,
+        end: usize,
+        name: Option<String>,
+    ) {
+        let mut window_start = start;
+        loop {
+            let window_end = (window_start + MAX_CHUNK_LINES).min(end);
+            let text = lines[window_start..window_end].join("\n");
+
+            if !text.trim().is_empty() {
+                chunks.push(CodeChunk {
+                    start_line: window_start + 1,
+                    end_line: window_end,
+                    name: name.clone(),
+                    text,
+                });
+            }
+
+            if window_end >= end {
+                break;
+            }
+            window_start = window_end - WINDOW_OVERLAP;
+        }
+    }
+
+    fn leading_comment_start(lines: &[&str], idx: usize) -> usize {
+        let mut start = idx;
+        while start > 0 {
+            let prev = lines[start - 1].trim_start();
+            let is_preamble = prev.starts_with("//")
+                || prev.starts_with("#[")
+                || prev.starts_with('@')
+                || prev.starts_with("/**")
+                || prev.starts_with("* ")
+                || prev.starts_with("*/");
+            if !is_preamble {
+                break;
+            }
+            start -= 1;
+        }
+        start
+    }
+
+    pub fn definition_name(line: &str) -> Option<String> {
+        static DEFINITION_RE: OnceLock<Regex> = OnceLock::new();
+        let re = DEFINITION_RE.get_or_init(|| {
+            Regex::new(
+                r"^\s{0,4}(?:pub(?:\([^)]*\))?\s+|export\s+(?:default\s+)?)?(?:async\s+|unsafe\s+|const\s+)*(?:fn|struct|enum|trait|impl(?:<[^>]*>)?|mod|def|class|function\*?|interface|func|type)\s+(?:\([^)]*\)\s*)?([A-Za-z_][\w:<>, ]*)",
+            )
+            .unwrap()
+        });
+
+        re.captures(line).and_then(|cap| {
+            cap.get(1).map(|m| {
+                m.as_str()
+                    .split(['<', ' ', '('])
+                    .next()
+                    .unwrap_or_default()
+                    .to_string()
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunks_split_on_definitions() {
+        let source = "use std::fmt;\n\n/// Checks a token.\npub fn validate_token(t: &str) -> bool {\n    !t.is_empty()\n}\n\nstruct Session {\n    id: u64,\n}\n";
+        let chunks = Chunker::chunk(source);
+
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].name, None);
+        assert_eq!(chunks[1].name.as_deref(), Some("validate_token"));
+        assert_eq!(chunks[1].start_line, 3);
+        assert_eq!(chunks[2].name.as_deref(), Some("Session"));
+    }
+
+    #[test]
+    fn test_long_definitions_are_windowed() {
+        let body: Vec<String> = (0..200).map(|i| format!("    let x{} = {};", i, i)).collect();
+        let source = format!("fn big() {{\n{}\n}}\n", body.join("\n"));
+        let chunks = Chunker::chunk(&source);
+
+        assert!(chunks.len() >= 3);
+        assert!(chunks.iter().all(|c| c.end_line - c.start_line < MAX_CHUNK_LINES));
+    }
+}
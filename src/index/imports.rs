@@ -0,0 +1,443 @@
+use crate::utils::walker::ProjectWalker;
+use anyhow::Result;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+use std::path::Path;
+use std::sync::OnceLock;
+
+const JS_EXTENSIONS: &[&str] = &["ts", "tsx", "js", "jsx", "mjs", "cjs"];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportGraph {
+    pub edges: BTreeMap<String, BTreeSet<String>>,
+    pub declarations: BTreeMap<String, BTreeSet<String>>,
+    #[serde(skip)]
+    reverse: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl ImportGraph {
+    pub fn build(root: &Path) -> Result<Self> {
+        let files: Vec<String> = ProjectWalker::for_project(root)
+            .walk()?
+            .iter()
+            .map(|p| p.strip_prefix(root).unwrap_or(p).to_string_lossy().replace('\\', "/"))
+            .collect();
+
+        let go_module = std::fs::read_to_string(root.join("go.mod"))
+            .ok()
+            .and_then(|content| {
+                content
+                    .lines()
+                    .find_map(|l| l.trim().strip_prefix("module ").map(|m| m.trim().to_string()))
+            });
+
+        let sources: Vec<(String, String)> = files
+            .iter()
+            .filter_map(|file| {
+                let content = std::fs::read_to_string(root.join(file)).ok()?;
+                Some((file.clone(), content))
+            })
+            .collect();
+
+        Ok(Self::from_sources(&sources, go_module.as_deref()))
+    }
+
+    pub fn from_sources(sources: &[(String, String)], go_module: Option<&str>) -> Self {
+        let known: HashSet<&str> = sources.iter().map(|(f, _)| f.as_str()).collect();
+        let mut graph = ImportGraph::default();
+
+        for (file, content) in sources {
+            let extension = file.rsplit('.').next().unwrap_or_default();
+            let imports = match extension {
+                "rs" => {
+                    let declared = Self::rust_modules(file, content, &known);
+                    graph.declarations.insert(file.clone(), declared.into_iter().collect());
+                    Self::rust_imports(file, content, &known)
+                }
+                "py" => Self::python_imports(file, content, &known),
+                "go" => Self::go_imports(file, content, &known, go_module),
+                ext if JS_EXTENSIONS.contains(&ext) => Self::js_imports(file, content, &known),
+                _ => continue,
+            };
+
+            let imports: BTreeSet<String> = imports.into_iter().filter(|i| i != file).collect();
+            graph.edges.insert(file.clone(), imports);
+        }
+
+        graph.rebuild_reverse();
+        graph
+    }
+
+    fn rebuild_reverse(&mut self) {
+        self.reverse.clear();
+        for (file, imports) in &self.edges {
+            for import in imports {
+                self.reverse.entry(import.clone()).or_default().insert(file.clone());
+            }
+        }
+    }
+
+    pub fn dependencies(&self, file: &str) -> Vec<String> {
+        self.edges.get(file).map(|d| d.iter().cloned().collect()).unwrap_or_default()
+    }
+
+    pub fn declared_modules(&self, file: &str) -> Vec<String> {
+        self.declarations.get(file).map(|d| d.iter().cloned().collect()).unwrap_or_default()
+    }
+
+    pub fn dependents(&self, file: &str) -> Vec<String> {
+        self.reverse.get(file).map(|d| d.iter().cloned().collect()).unwrap_or_default()
+    }
+
+    pub fn transitive_dependents(&self, files: &[String]) -> Vec<String> {
+        let start: HashSet<&str> = files.iter().map(|f| f.as_str()).collect();
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut queue: VecDeque<&String> = files.iter().collect();
+        let mut order = Vec::new();
+
+        while let Some(file) = queue.pop_front() {
+            for dependent in self.reverse.get(file).into_iter().flatten() {
+                if start.contains(dependent.as_str()) || !seen.insert(dependent.clone()) {
+                    continue;
+                }
+                order.push(dependent.clone());
+                queue.push_back(dependent);
+            }
+        }
+
+        order
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph imports {\n    rankdir=LR;\n    node [shape=box];\n");
+        for (file, imports) in &self.edges {
+            if imports.is_empty() && !self.reverse.contains_key(file) {
+                dot.push_str(&format!("    \"{}\";\n", file));
+            }
+            for import in imports {
+                dot.push_str(&format!("    \"{}\" -> \"{}\";\n", file, import));
+            }
+        }
+        for (file, modules) in &self.declarations {
+            for module in modules {
+                dot.push_str(&format!("    \"{}\" -> \"{}\" [style=dashed];\n", file, module));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    fn rust_modules(file: &str, content: &str, known: &HashSet<&str>) -> Vec<String> {
+        static MOD_RE: OnceLock<Regex> = OnceLock::new();
+        let mod_re = MOD_RE.get_or_init(|| {
+            Regex::new(r"(?m)^\s*(?:pub(?:\([^)]*\))?\s+)?mod\s+([A-Za-z_]\w*)\s*;").unwrap()
+        });
+
+        let module_dir = Self::rust_module_dir(file);
+        mod_re
+            .captures_iter(content)
+            .filter_map(|cap| {
+                let name = &cap[1];
+                [format!("{}{}.rs", module_dir, name), format!("{}{}/mod.rs", module_dir, name)]
+                    .into_iter()
+                    .find(|c| known.contains(c.as_str()))
+            })
+            .collect()
+    }
+
+    fn rust_imports(file: &str, content: &str, known: &HashSet<&str>) -> Vec<String> {
+        static USE_RE: OnceLock<Regex> = OnceLock::new();
+        let use_re = USE_RE.get_or_init(|| {
+            Regex::new(r"(?ms)^\s*(?:pub(?:\([^)]*\))?\s+)?use\s+(?:::)?([A-Za-z_]\w*::[^;]+);").unwrap()
+        });
+
+        let module_dir = Self::rust_module_dir(file);
+        let crate_root = Self::rust_crate_root(file, known);
+        let mut imports = Vec::new();
+
+        for cap in use_re.captures_iter(content) {
+            for path in Self::expand_use_tree(&cap[1]) {
+                let mut segments: Vec<&str> = path.split("::").map(|s| s.trim()).collect();
+                let base = match segments.first().copied() {
+                    Some("crate") => crate_root.clone(),
+                    Some("self") => module_dir.clone(),
+                    Some("super") => {
+                        let mut dir = module_dir.clone();
+                        while segments.first() == Some(&"super") {
+                            dir = Self::parent_dir(&dir);
+                            segments.remove(0);
+                        }
+                        segments.insert(0, "super");
+                        dir
+                    }
+                    _ => {
+                        segments.insert(0, "self");
+                        module_dir.clone()
+                    }
+                };
+                segments.remove(0);
+
+                for len in (1..=segments.len()).rev() {
+                    let module = segments[..len].join("/");
+                    let candidates = [format!("{}{}.rs", base, module), format!("{}{}/mod.rs", base, module)];
+                    if let Some(found) = candidates.into_iter().find(|c| known.contains(c.as_str())) {
+                        imports.push(found);
+                        break;
+                    }
+                }
+            }
+        }
+
+        imports
+    }
+
+    fn expand_use_tree(tree: &str) -> Vec<String> {
+        let tree: String = tree.chars().filter(|c| !c.is_whitespace()).collect();
+        let Some(open) = tree.find('{') else {
+            return vec![tree];
+        };
+        let prefix = &tree[..open];
+        let inner = tree[open + 1..].strip_suffix('}').unwrap_or(&tree[open + 1..]);
+
+        let mut items = Vec::new();
+        let mut depth = 0;
+        let mut current = String::new();
+        for c in inner.chars() {
+            match c {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                ',' if depth == 0 => {
+                    items.push(std::mem::take(&mut current));
+                    continue;
+                }
+                _ => {}
+            }
+            current.push(c);
+        }
+        items.push(current);
+
+        items
+            .into_iter()
+            .filter(|i| !i.is_empty())
+            .flat_map(|item| {
+                if item == "self" {
+                    vec![prefix.trim_end_matches("::").to_string()]
+                } else {
+                    Self::expand_use_tree(&format!("{}{}", prefix, item))
+                }
+            })
+            .collect()
+    }
+
+    fn rust_module_dir(file: &str) -> String {
+        let (dir, name) = file.rsplit_once('/').unwrap_or(("", file));
+        let dir = if dir.is_empty() { String::new() } else { format!("{}/", dir) };
+        match name {
+            "mod.rs" | "lib.rs" | "main.rs" => dir,
+            _ => format!("{}{}/", dir, name.trim_end_matches(".rs")),
+        }
+    }
+
+    fn rust_crate_root(file: &str, known: &HashSet<&str>) -> String {
+        let mut dir = file.rsplit_once('/').map(|(d, _)| format!("{}/", d)).unwrap_or_default();
+        loop {
+            if known.contains(format!("{}lib.rs", dir).as_str()) || known.contains(format!("{}main.rs", dir).as_str()) {
+                return dir;
+            }
+            if dir.is_empty() {
+                return String::new();
+            }
+            dir = Self::parent_dir(&dir);
+        }
+    }
+
+    fn parent_dir(dir: &str) -> String {
+        let trimmed = dir.trim_end_matches('/');
+        match trimmed.rsplit_once('/') {
+            Some((parent, _)) => format!("{}/", parent),
+            None => String::new(),
+        }
+    }
+
+    fn python_imports(file: &str, content: &str, known: &HashSet<&str>) -> Vec<String> {
+        static IMPORT_RE: OnceLock<Regex> = OnceLock::new();
+        static FROM_RE: OnceLock<Regex> = OnceLock::new();
+        let import_re = IMPORT_RE.get_or_init(|| Regex::new(r"(?m)^\s*import\s+([\w.]+(?:\s*,\s*[\w.]+)*)").unwrap());
+        let from_re = FROM_RE.get_or_init(|| {
+            Regex::new(r"(?m)^\s*from\s+(\.*)([\w.]*)\s+import\s+\(?\s*([\w\s,*]+)").unwrap()
+        });
+
+        let package_dir = file.rsplit_once('/').map(|(d, _)| format!("{}/", d)).unwrap_or_default();
+        let mut imports = Vec::new();
+
+        let resolve = |base: &str, module: &str| -> Option<String> {
+            let path = module.replace('.', "/");
+            let candidates = [
+                format!("{}{}.py", base, path),
+                format!("{}{}/__init__.py", base, path),
+                format!("{}src/{}.py", base, path),
+                format!("{}src/{}/__init__.py", base, path),
+            ];
+            candidates.into_iter().find(|c| known.contains(c.as_str()))
+        };
+
+        for cap in import_re.captures_iter(content) {
+            for module in cap[1].split(',').map(|m| m.trim()) {
+                imports.extend(resolve("", module));
+            }
+        }
+
+        for cap in from_re.captures_iter(content) {
+            let dots = cap[1].len();
+            let module = &cap[2];
+            let base = if dots == 0 {
+                String::new()
+            } else {
+                (1..dots).fold(package_dir.clone(), |dir, _| Self::parent_dir(&dir))
+            };
+
+            let mut found_submodule = false;
+            for name in cap[3].split(',').map(|n| n.trim()).filter(|n| !n.is_empty() && *n != "*") {
+                let name = name.split_whitespace().next().unwrap_or(name);
+                let submodule = if module.is_empty() { name.to_string() } else { format!("{}.{}", module, name) };
+                if let Some(found) = resolve(&base, &submodule) {
+                    imports.push(found);
+                    found_submodule = true;
+                }
+            }
+            if !found_submodule && !module.is_empty() {
+                imports.extend(resolve(&base, module));
+            }
+        }
+
+        imports
+    }
+
+    fn js_imports(file: &str, content: &str, known: &HashSet<&str>) -> Vec<String> {
+        static JS_RE: OnceLock<Regex> = OnceLock::new();
+        let js_re = JS_RE.get_or_init(|| {
+            Regex::new(r#"(?:\bfrom\s*|\bimport\s*\(?\s*|\brequire\s*\(\s*)['"](\.{1,2}/[^'"]*)['"]"#).unwrap()
+        });
+
+        let dir = file.rsplit_once('/').map(|(d, _)| d).unwrap_or("");
+        let mut imports = Vec::new();
+
+        for cap in js_re.captures_iter(content) {
+            let joined = Self::normalize(&format!("{}/{}", dir, &cap[1]));
+            let mut candidates = vec![joined.clone()];
+            for ext in JS_EXTENSIONS {
+                candidates.push(format!("{}.{}", joined, ext));
+            }
+            for ext in JS_EXTENSIONS {
+                candidates.push(format!("{}/index.{}", joined, ext));
+            }
+            if let Some(found) = candidates.into_iter().find(|c| known.contains(c.as_str())) {
+                imports.push(found);
+            }
+        }
+
+        imports
+    }
+
+    fn go_imports(file: &str, content: &str, known: &HashSet<&str>, go_module: Option<&str>) -> Vec<String> {
+        static BLOCK_RE: OnceLock<Regex> = OnceLock::new();
+        static PATH_RE: OnceLock<Regex> = OnceLock::new();
+        let block_re = BLOCK_RE.get_or_init(|| Regex::new(r#"(?ms)^import\s*(\(.*?\)|[^\n]*)"#).unwrap());
+        let path_re = PATH_RE.get_or_init(|| Regex::new(r#""([^"]+)""#).unwrap());
+
+        let Some(module) = go_module else {
+            return Vec::new();
+        };
+        let mut imports = Vec::new();
+
+        for block in block_re.captures_iter(content) {
+            for cap in path_re.captures_iter(&block[1]) {
+                let Some(package) = cap[1].strip_prefix(module) else {
+                    continue;
+                };
+                let package_dir = package.trim_start_matches('/');
+                let prefix = if package_dir.is_empty() { String::new() } else { format!("{}/", package_dir) };
+
+                imports.extend(
+                    known
+                        .iter()
+                        .filter(|k| {
+                            k.strip_prefix(prefix.as_str()).is_some_and(|rest| {
+                                !rest.contains('/') && rest.ends_with(".go") && !rest.ends_with("_test.go")
+                            })
+                        })
+                        .filter(|k| **k != file)
+                        .map(|k| k.to_string()),
+                );
+            }
+        }
+
+        imports
+    }
+
+    fn normalize(path: &str) -> String {
+        let mut parts: Vec<&str> = Vec::new();
+        for part in path.split('/') {
+            match part {
+                "" | "." => {}
+                ".." => {
+                    parts.pop();
+                }
+                _ => parts.push(part),
+            }
+        }
+        parts.join("/")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(path: &str, content: &str) -> (String, String) {
+        (path.to_string(), content.to_string())
+    }
+
+    #[test]
+    fn test_rust_mod_and_use_edges() {
+        let sources = vec![
+            source("src/main.rs", "mod index;\nmod utils;\nuse crate::utils::{search::SearchResult, FileUtils};\n"),
+            source("src/index/mod.rs", "pub mod ranking;\npub use ranking::Ranker;\nuse std::fmt;\n"),
+            source("src/index/ranking.rs", "use super::super::utils::search::SearchResult;\n"),
+            source("src/utils/mod.rs", "pub mod search;\n"),
+            source("src/utils/search.rs", "use crate::index::ranking;\n"),
+        ];
+        let graph = ImportGraph::from_sources(&sources, None);
+
+        assert_eq!(graph.dependencies("src/main.rs"), vec!["src/utils/mod.rs", "src/utils/search.rs"]);
+        assert_eq!(graph.declared_modules("src/main.rs"), vec!["src/index/mod.rs", "src/utils/mod.rs"]);
+        assert_eq!(graph.dependencies("src/index/ranking.rs"), vec!["src/utils/search.rs"]);
+        assert_eq!(graph.dependents("src/index/ranking.rs"), vec!["src/index/mod.rs", "src/utils/search.rs"]);
+    }
+
+    #[test]
+    fn test_python_js_and_transitive_dependents() {
+        let sources = vec![
+            source("app/auth.py", "from .models import User\nimport app.db\n"),
+            source("app/models.py", "from app import db\n"),
+            source("app/db.py", ""),
+            source("web/api.ts", "import { login } from './client';\nconst x = require('../web/util');\n"),
+            source("web/client.ts", ""),
+            source("web/util.js", "import './client'\n"),
+        ];
+        let graph = ImportGraph::from_sources(&sources, None);
+
+        assert_eq!(graph.dependencies("app/auth.py"), vec!["app/db.py", "app/models.py"]);
+        assert_eq!(
+            graph.transitive_dependents(&["app/db.py".to_string()]),
+            vec!["app/auth.py", "app/models.py"]
+        );
+        assert_eq!(graph.dependencies("web/api.ts"), vec!["web/client.ts", "web/util.js"]);
+        assert!(graph.to_dot().contains("\"web/util.js\" -> \"web/client.ts\";"));
+    }
+}
@@ -0,0 +1,14 @@
+#![allow(dead_code)]
+
+pub mod chunker;
+pub mod imports;
+pub mod ranking;
+pub mod repo_map;
+pub mod semantic;
+pub mod symbols;
+
+pub use imports::ImportGraph;
+pub use ranking::RelevanceRanker;
+pub use repo_map::RepoMap;
+pub use semantic::SemanticIndex;
+pub use symbols::SymbolIndex;
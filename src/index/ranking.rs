@@ -0,0 +1,446 @@
+use super::chunker::{Chunker, CodeChunk};
+use super::symbols::SymbolIndex;
+use crate::integrations::git::GitManager;
+use crate::llm::embeddings::HashingEmbedder;
+use crate::utils::walker::ProjectWalker;
+use crate::utils::FileUtils;
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+const BM25_K1: f32 = 1.2;
+const BM25_B: f32 = 0.75;
+const PATH_TERM_WEIGHT: u32 = 3;
+const FILENAME_MENTION_BOOST: f32 = 6.0;
+const SYMBOL_MATCH_BOOST: f32 = 3.0;
+const MAX_SYMBOL_BOOST: f32 = 9.0;
+const RECENT_CHANGE_BOOST: f32 = 2.0;
+const RECENT_COMMITS: usize = 20;
+const SNIPPETS_PER_FILE: usize = 2;
+
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "bug", "by", "can", "code", "do", "file", "fix",
+    "for", "from", "how", "i", "in", "is", "it", "make", "of", "on", "or", "please", "should",
+    "that", "the", "this", "to", "we", "when", "where", "why", "with",
+];
+
+#[derive(Debug, Clone)]
+pub struct RankedFile {
+    pub path: String,
+    pub score: f32,
+    pub reasons: Vec<String>,
+    pub snippets: Vec<CodeChunk>,
+}
+
+struct Document {
+    path: String,
+    hash: String,
+    /// Modification time and size when last read, to skip unchanged files on refresh.
+    stamp: Option<(SystemTime, u64)>,
+    terms: HashMap<String, u32>,
+    length: u32,
+}
+
+pub struct RelevanceRanker {
+    root: PathBuf,
+    documents: HashMap<String, Document>,
+    doc_freq: HashMap<String, usize>,
+    recent: HashMap<String, f32>,
+    symbols: Option<SymbolIndex>,
+}
+
+impl RelevanceRanker {
+    pub fn new(root: &Path) -> Self {
+        RelevanceRanker {
+            root: root.to_path_buf(),
+            documents: HashMap::new(),
+            doc_freq: HashMap::new(),
+            recent: HashMap::new(),
+            symbols: None,
+        }
+    }
+
+    pub fn build(root: &Path) -> Result<Self> {
+        let mut ranker = Self::new(root);
+        ranker.refresh()?;
+        Ok(ranker)
+    }
+
+    /// Re-reads files whose content changed since the last refresh, drops deleted
+    /// ones and picks up new git activity, so a long-lived ranker stays current.
+    pub fn refresh(&mut self) -> Result<usize> {
+        let mut seen = HashSet::new();
+        let mut reindexed = 0;
+
+        for path in ProjectWalker::for_project(&self.root).walk()? {
+            let relative = path
+                .strip_prefix(&self.root)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            let stamp = std::fs::metadata(&path)
+                .ok()
+                .and_then(|m| Some((m.modified().ok()?, m.len())));
+            let unchanged = stamp.is_some() && self.documents.get(&relative).is_some_and(|d| d.stamp == stamp);
+            if !unchanged {
+                let Ok(content) = std::fs::read_to_string(&path) else {
+                    continue;
+                };
+                let hash = FileUtils::hash_bytes(content.as_bytes());
+                if self.documents.get(&relative).map(|d| d.hash.as_str()) != Some(hash.as_str()) {
+                    self.add_document(&relative, &content);
+                    reindexed += 1;
+                }
+                if let Some(document) = self.documents.get_mut(&relative) {
+                    document.stamp = stamp;
+                }
+            }
+            seen.insert(relative);
+        }
+
+        let removed: Vec<String> = self
+            .documents
+            .keys()
+            .filter(|path| !seen.contains(*path))
+            .cloned()
+            .collect();
+        for path in &removed {
+            self.remove_document(path);
+        }
+
+        if let Ok(git) = GitManager::new(&self.root) {
+            let dirty = git.get_status().unwrap_or_default();
+            let committed = git.recently_changed_files(RECENT_COMMITS).unwrap_or_default();
+            self.set_recent_changes(&dirty, &committed);
+        }
+
+        let mut symbols = self.symbols.take().unwrap_or_else(|| SymbolIndex::load(&self.root));
+        match symbols.refresh() {
+            Ok(_) => self.symbols = Some(symbols),
+            Err(e) => tracing::warn!("Symbol index unavailable for ranking: {}", e),
+        }
+
+        Ok(reindexed + removed.len())
+    }
+
+    pub fn add_document(&mut self, path: &str, content: &str) {
+        let mut terms: HashMap<String, u32> = HashMap::new();
+        for token in Self::terms(content) {
+            *terms.entry(token).or_insert(0) += 1;
+        }
+        for token in Self::terms(path) {
+            *terms.entry(token).or_insert(0) += PATH_TERM_WEIGHT;
+        }
+
+        self.remove_document(path);
+        for term in terms.keys() {
+            *self.doc_freq.entry(term.clone()).or_insert(0) += 1;
+        }
+        let length = terms.values().sum();
+
+        self.documents.insert(path.to_string(), Document {
+            path: path.to_string(),
+            hash: FileUtils::hash_bytes(content.as_bytes()),
+            stamp: None,
+            terms,
+            length,
+        });
+    }
+
+    pub fn remove_document(&mut self, path: &str) -> bool {
+        let Some(old) = self.documents.remove(path) else {
+            return false;
+        };
+        for term in old.terms.keys() {
+            if let Some(freq) = self.doc_freq.get_mut(term) {
+                *freq = freq.saturating_sub(1);
+                if *freq == 0 {
+                    self.doc_freq.remove(term);
+                }
+            }
+        }
+        true
+    }
+
+    pub fn set_recent_changes(&mut self, dirty: &[String], committed: &[String]) {
+        self.recent.clear();
+        let total = committed.len().max(1) as f32;
+        for (idx, path) in committed.iter().enumerate() {
+            self.recent
+                .insert(path.clone(), RECENT_CHANGE_BOOST * (1.0 - idx as f32 / total).max(0.25));
+        }
+        for path in dirty {
+            self.recent.insert(path.clone(), RECENT_CHANGE_BOOST);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    pub fn rank(&self, prompt: &str, limit: usize) -> Vec<RankedFile> {
+        let query: Vec<String> = Self::terms(prompt)
+            .into_iter()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        let prompt_lower = prompt.to_lowercase();
+        let symbol_boosts = self.symbol_boosts(prompt);
+        let avg_len = self.documents.values().map(|d| d.length as f32).sum::<f32>()
+            / self.documents.len().max(1) as f32;
+
+        let mut ranked: Vec<RankedFile> = self
+            .documents
+            .values()
+            .filter_map(|doc| {
+                let mut reasons = Vec::new();
+                let mut score = self.bm25(doc, &query, avg_len);
+                if score > 0.0 {
+                    reasons.push(format!("bm25 {:.2}", score));
+                }
+
+                if Self::is_mentioned(&doc.path, &prompt_lower) {
+                    score += FILENAME_MENTION_BOOST;
+                    reasons.push("mentioned by name".to_string());
+                }
+
+                if let Some(boost) = symbol_boosts.get(&doc.path) {
+                    score += boost;
+                    reasons.push("defines a mentioned symbol".to_string());
+                }
+
+                if score <= 0.0 {
+                    return None;
+                }
+
+                if let Some(boost) = self.recent.get(&doc.path) {
+                    score += boost;
+                    reasons.push("recently changed".to_string());
+                }
+
+                Some(RankedFile {
+                    path: doc.path.clone(),
+                    score,
+                    reasons,
+                    snippets: Vec::new(),
+                })
+            })
+            .collect();
+
+        ranked.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        ranked.truncate(limit);
+        ranked
+    }
+
+    pub fn select_context(&self, prompt: &str, budget_chars: usize) -> Vec<RankedFile> {
+        let query: HashSet<String> = Self::terms(prompt).into_iter().collect();
+        let mut selected = Vec::new();
+        let mut used = 0;
+
+        for mut file in self.rank(prompt, 20) {
+            let Ok(content) = std::fs::read_to_string(self.root.join(&file.path)) else {
+                continue;
+            };
+
+            let mut chunks: Vec<(f32, CodeChunk)> = Chunker::chunk(&content)
+                .into_iter()
+                .map(|chunk| (self.chunk_score(&chunk, &query), chunk))
+                .filter(|(score, _)| *score > 0.0)
+                .collect();
+            if chunks.is_empty() {
+                chunks = Chunker::chunk(&content).into_iter().take(1).map(|c| (0.0, c)).collect();
+            }
+            chunks.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+
+            for (_, chunk) in chunks.into_iter().take(SNIPPETS_PER_FILE) {
+                let cost = chunk.text.len() + file.path.len() + 16;
+                if used + cost > budget_chars {
+                    continue;
+                }
+                used += cost;
+                file.snippets.push(chunk);
+            }
+
+            if !file.snippets.is_empty() {
+                file.snippets.sort_by_key(|c| c.start_line);
+                selected.push(file);
+            }
+            if used >= budget_chars {
+                break;
+            }
+        }
+
+        selected
+    }
+
+    pub fn format_context(files: &[RankedFile]) -> String {
+        let mut context = String::new();
+        for file in files {
+            for snippet in &file.snippets {
+                context.push_str(&format!(
+                    "// {}:{}-{}\n{}\n\n",
+                    file.path, snippet.start_line, snippet.end_line, snippet.text
+                ));
+            }
+        }
+        context
+    }
+
+    fn bm25(&self, doc: &Document, query: &[String], avg_len: f32) -> f32 {
+        let n = self.documents.len() as f32;
+        query
+            .iter()
+            .filter_map(|term| {
+                let tf = *doc.terms.get(term)? as f32;
+                let df = *self.doc_freq.get(term).unwrap_or(&0) as f32;
+                let idf = ((n - df + 0.5) / (df + 0.5) + 1.0).ln();
+                let norm = BM25_K1 * (1.0 - BM25_B + BM25_B * doc.length as f32 / avg_len.max(1.0));
+                Some(idf * tf * (BM25_K1 + 1.0) / (tf + norm))
+            })
+            .sum()
+    }
+
+    fn chunk_score(&self, chunk: &CodeChunk, query: &HashSet<String>) -> f32 {
+        let n = self.documents.len().max(1) as f32;
+        let mut seen = HashSet::new();
+        let mut text = chunk.text.clone();
+        if let Some(name) = &chunk.name {
+            text.push(' ');
+            text.push_str(name);
+        }
+
+        Self::terms(&text)
+            .into_iter()
+            .filter(|t| query.contains(t) && seen.insert(t.clone()))
+            .map(|t| {
+                let df = *self.doc_freq.get(&t).unwrap_or(&1) as f32;
+                (n / df).ln() + 1.0
+            })
+            .sum()
+    }
+
+    fn symbol_boosts(&self, prompt: &str) -> HashMap<String, f32> {
+        let mut boosts: HashMap<String, f32> = HashMap::new();
+        let Some(symbols) = &self.symbols else {
+            return boosts;
+        };
+
+        let words: HashSet<&str> = prompt
+            .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .filter(|w| w.len() > 2)
+            .collect();
+
+        for word in words {
+            for symbol in symbols.find_definitions(word) {
+                let boost = boosts.entry(symbol.file.clone()).or_insert(0.0);
+                *boost = (*boost + SYMBOL_MATCH_BOOST).min(MAX_SYMBOL_BOOST);
+            }
+        }
+
+        boosts
+    }
+
+    fn is_mentioned(path: &str, prompt_lower: &str) -> bool {
+        let path_lower = path.to_lowercase();
+        if prompt_lower.contains(&path_lower) {
+            return true;
+        }
+
+        let file_name = path_lower.rsplit('/').next().unwrap_or(&path_lower);
+        let stem = file_name.split('.').next().unwrap_or(file_name);
+        if prompt_lower.contains(file_name) {
+            return true;
+        }
+        stem.len() > 3
+            && !matches!(stem, "mod" | "main" | "lib" | "index")
+            && prompt_lower
+                .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .any(|w| w == stem)
+    }
+
+    fn terms(text: &str) -> Vec<String> {
+        HashingEmbedder::tokenize(text)
+            .into_iter()
+            .filter(|t| t.len() > 1 && !STOP_WORDS.contains(&t.as_str()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_ranker() -> RelevanceRanker {
+        let mut ranker = RelevanceRanker::new(Path::new("."));
+        ranker.add_document(
+            "src/auth/login.rs",
+            "pub fn login(user: &User, password: &str) -> Result<Session> { verify_password(password) }",
+        );
+        ranker.add_document("src/auth/session.rs", "pub struct Session { token: String }");
+        ranker.add_document("src/ui/theme.rs", "pub fn colors() -> Palette { Palette::dark() }");
+        ranker
+    }
+
+    #[test]
+    fn test_bm25_prefers_matching_identifiers() {
+        let ranker = sample_ranker();
+        let ranked = ranker.rank("fix the bug in login when the password is wrong", 3);
+
+        assert_eq!(ranked[0].path, "src/auth/login.rs");
+        assert!(ranked.iter().all(|r| r.path != "src/ui/theme.rs"));
+    }
+
+    #[test]
+    fn test_filename_mention_and_recent_changes_boost() {
+        let mut ranker = sample_ranker();
+        ranker.set_recent_changes(&["src/auth/session.rs".to_string()], &[]);
+
+        let ranked = ranker.rank("update session.rs to store the password login token", 3);
+        assert_eq!(ranked[0].path, "src/auth/session.rs");
+        assert!(ranked[0].reasons.iter().any(|r| r == "mentioned by name"));
+        assert!(ranked[0].reasons.iter().any(|r| r == "recently changed"));
+    }
+
+    #[test]
+    fn test_replacing_a_document_keeps_document_frequencies() {
+        let mut ranker = sample_ranker();
+        ranker.add_document("src/auth/login.rs", "pub fn logout(session: Session) {}");
+
+        assert_eq!(ranker.len(), 3);
+        assert!(!ranker.doc_freq.contains_key("password"));
+        assert_eq!(ranker.doc_freq.get("session"), Some(&2));
+
+        assert!(ranker.remove_document("src/auth/session.rs"));
+        assert_eq!(ranker.doc_freq.get("session"), Some(&1));
+    }
+
+    #[test]
+    fn test_refresh_reindexes_only_changed_files() {
+        let root = std::env::temp_dir().join(format!("codeagent-ranking-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/login.rs"), "pub fn login() {}\n").unwrap();
+        std::fs::write(root.join("src/theme.rs"), "pub fn colors() {}\n").unwrap();
+
+        let mut ranker = RelevanceRanker::build(&root).unwrap();
+        assert_eq!(ranker.len(), 2);
+        assert_eq!(ranker.refresh().unwrap(), 0);
+
+        std::fs::write(root.join("src/login.rs"), "pub fn login(password: &str) {}\n").unwrap();
+        std::fs::remove_file(root.join("src/theme.rs")).unwrap();
+        assert_eq!(ranker.refresh().unwrap(), 2);
+        assert_eq!(ranker.rank("password", 1)[0].path, "src/login.rs");
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,279 @@
+use super::symbols::{Symbol, SymbolIndex, SymbolKind};
+use crate::cache::storage::{Cache, CacheKey};
+use crate::integrations::git::GitManager;
+use crate::utils::walker::ProjectWalker;
+use crate::utils::FileUtils;
+use anyhow::Result;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_REPO_MAP_TOKENS: usize = 1024;
+const CHARS_PER_TOKEN: usize = 4;
+const CACHE_TTL_SECONDS: u64 = 7 * 24 * 3600;
+const CACHE_MAX_ENTRIES: usize = 16;
+
+pub struct RepoMap;
+
+struct MapEntry<'a> {
+    symbol: &'a Symbol,
+    score: usize,
+}
+
+impl RepoMap {
+    pub fn cache_path(root: &Path) -> PathBuf {
+        root.join(".codeagent").join("cache").join("repo_map.json")
+    }
+
+    pub fn cached(root: &Path, token_budget: usize) -> Result<String> {
+        let cache_path = Self::cache_path(root);
+        let mut cache: Cache<String> = Cache::load(&cache_path, CACHE_MAX_ENTRIES);
+
+        let mut symbols = None;
+        let identifier = match Self::git_state(root) {
+            Some(state) => format!("{}:{}", state, token_budget),
+            None => {
+                let mut index = SymbolIndex::load(root);
+                index.refresh()?;
+                let mut hashes: Vec<&str> = index.files.values().map(|f| f.hash.as_str()).collect();
+                hashes.sort_unstable();
+                let state = FileUtils::hash_bytes(hashes.concat().as_bytes());
+                symbols = Some(index);
+                format!("nogit-{}:{}", state, token_budget)
+            }
+        };
+        let key = CacheKey {
+            category: "repo_map".to_string(),
+            identifier,
+        };
+
+        if let Some(map) = cache.get(&key) {
+            return Ok(map);
+        }
+
+        let symbols = match symbols {
+            Some(index) => index,
+            None => {
+                let mut index = SymbolIndex::load(root);
+                index.refresh()?;
+                index
+            }
+        };
+        let files: Vec<String> = ProjectWalker::for_project(root)
+            .walk()?
+            .iter()
+            .map(|p| p.strip_prefix(root).unwrap_or(p).to_string_lossy().replace('\\', "/"))
+            .collect();
+
+        let map = Self::build(&files, &symbols, token_budget);
+        cache.set(key, map.clone(), CACHE_TTL_SECONDS);
+        if let Err(e) = cache.save(&cache_path) {
+            tracing::warn!("Could not persist repo map cache: {}", e);
+        }
+        Ok(map)
+    }
+
+    /// The cached map as a section to append to a system prompt; empty when there is none.
+    pub fn system_prompt_section(root: &Path) -> String {
+        match Self::cached(root, DEFAULT_REPO_MAP_TOKENS) {
+            Ok(map) if !map.trim().is_empty() => format!("\n\n## Repository map\n{}", map.trim_end()),
+            Ok(_) => String::new(),
+            Err(e) => {
+                tracing::warn!("Could not build repo map: {}", e);
+                String::new()
+            }
+        }
+    }
+
+    pub fn build(files: &[String], symbols: &SymbolIndex, token_budget: usize) -> String {
+        let budget = token_budget * CHARS_PER_TOKEN;
+
+        let mut reference_counts: HashMap<&str, usize> = HashMap::new();
+        for file in symbols.files.values() {
+            for (name, refs) in &file.references {
+                *reference_counts.entry(name.as_str()).or_insert(0) += refs.len();
+            }
+        }
+
+        let mut definition_counts: HashMap<&str, usize> = HashMap::new();
+        for symbol in symbols.files.values().flat_map(|f| f.definitions.iter()) {
+            *definition_counts.entry(symbol.name.as_str()).or_insert(0) += 1;
+        }
+
+        let mut entries: Vec<MapEntry> = symbols
+            .files
+            .values()
+            .flat_map(|f| f.definitions.iter())
+            .filter(|s| s.public && s.kind != SymbolKind::Module)
+            .map(|symbol| MapEntry {
+                symbol,
+                score: reference_counts.get(symbol.name.as_str()).copied().unwrap_or(0)
+                    / definition_counts.get(symbol.name.as_str()).copied().unwrap_or(1),
+            })
+            .collect();
+        entries.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| (&a.symbol.file, a.symbol.line).cmp(&(&b.symbol.file, b.symbol.line)))
+        });
+
+        let known_files: HashSet<&str> = files.iter().map(|f| f.as_str()).collect();
+        let mut used = 0;
+        let mut shown_files: HashSet<&str> = HashSet::new();
+        let mut shown: BTreeMap<&str, Vec<&Symbol>> = BTreeMap::new();
+
+        for entry in &entries {
+            let file = entry.symbol.file.as_str();
+            if !known_files.contains(file) {
+                continue;
+            }
+            let mut cost = Self::signature_line(entry.symbol).len() + 1;
+            if !shown_files.contains(file) {
+                cost += Self::path_cost(file, &shown_files);
+            }
+            if used + cost > budget {
+                continue;
+            }
+            used += cost;
+            shown_files.insert(file);
+            shown.entry(file).or_default().push(entry.symbol);
+        }
+
+        for file in files {
+            if shown_files.contains(file.as_str()) {
+                continue;
+            }
+            let cost = Self::path_cost(file, &shown_files);
+            if used + cost > budget {
+                break;
+            }
+            used += cost;
+            shown_files.insert(file.as_str());
+        }
+
+        for symbols in shown.values_mut() {
+            symbols.sort_by_key(|s| s.line);
+        }
+        let mut sorted_files: Vec<&str> = shown_files.into_iter().collect();
+        sorted_files.sort_unstable();
+        Self::render(&sorted_files, &shown)
+    }
+
+    fn render(files: &[&str], shown: &BTreeMap<&str, Vec<&Symbol>>) -> String {
+        let mut output = String::new();
+        let mut open_dirs: Vec<&str> = Vec::new();
+
+        for file in files {
+            let parts: Vec<&str> = file.split('/').collect();
+            let dirs = &parts[..parts.len() - 1];
+
+            let common = open_dirs
+                .iter()
+                .zip(dirs.iter())
+                .take_while(|(a, b)| a == b)
+                .count();
+            open_dirs.truncate(common);
+            for dir in &dirs[common..] {
+                output.push_str(&format!("{}{}/\n", "  ".repeat(open_dirs.len()), dir));
+                open_dirs.push(dir);
+            }
+
+            let indent = "  ".repeat(open_dirs.len());
+            output.push_str(&format!("{}{}\n", indent, parts[parts.len() - 1]));
+
+            if let Some(symbols) = shown.get(file) {
+                for symbol in symbols {
+                    output.push_str(&format!("{}  {}\n", indent, Self::signature_line(symbol)));
+                }
+            }
+        }
+
+        output
+    }
+
+    fn signature_line(symbol: &Symbol) -> String {
+        let signature = symbol
+            .signature
+            .trim()
+            .trim_end_matches('{')
+            .trim_end();
+        match &symbol.container {
+            Some(container) => format!("[{}] {}", container, signature),
+            None => signature.to_string(),
+        }
+    }
+
+    fn path_cost(file: &str, shown: &HashSet<&str>) -> usize {
+        let depth = file.matches('/').count();
+        let new_dir = !shown.iter().any(|f| Path::new(f).parent() == Path::new(file).parent());
+        file.len() + depth * 2 + 1 + if new_dir { depth * 4 } else { 0 }
+    }
+
+    fn git_state(root: &Path) -> Option<String> {
+        let git = GitManager::new(root).ok()?;
+        let head = git.head_commit()?;
+
+        let mut dirty = git.get_status().ok()?;
+        dirty.sort();
+        let mut state = String::new();
+        for path in dirty {
+            let hash = FileUtils::hash_file(&root.join(&path)).unwrap_or_else(|_| "deleted".to_string());
+            state.push_str(&format!("{}={};", path, hash));
+        }
+
+        Some(format!("{}-{}", head, FileUtils::hash_bytes(state.as_bytes())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::symbols::FileSymbols;
+
+    fn symbol(name: &str, file: &str, line: usize, public: bool) -> Symbol {
+        Symbol {
+            name: name.to_string(),
+            kind: SymbolKind::Function,
+            file: file.to_string(),
+            line,
+            column: 1,
+            end_line: line,
+            container: None,
+            signature: format!("pub fn {}() {{", name),
+            public,
+        }
+    }
+
+    fn sample_index() -> SymbolIndex {
+        let mut index = SymbolIndex::default();
+        let mut auth = FileSymbols::default();
+        auth.definitions.push(symbol("login", "src/auth/login.rs", 3, true));
+        auth.definitions.push(symbol("helper", "src/auth/login.rs", 9, false));
+        auth.references.insert("rarely_used".to_string(), vec![(1, 1)]);
+        index.files.insert("src/auth/login.rs".to_string(), auth);
+
+        let mut util = FileSymbols::default();
+        util.definitions.push(symbol("rarely_used", "src/util.rs", 1, true));
+        util.references.insert("login".to_string(), vec![(2, 1), (5, 1), (8, 1)]);
+        index.files.insert("src/util.rs".to_string(), util);
+        index
+    }
+
+    #[test]
+    fn test_map_renders_tree_with_public_signatures() {
+        let files = vec!["src/auth/login.rs".to_string(), "src/util.rs".to_string()];
+        let map = RepoMap::build(&files, &sample_index(), 500);
+
+        assert!(map.starts_with("src/\n  auth/\n    login.rs\n      pub fn login()\n"));
+        assert!(map.contains("  util.rs\n    pub fn rarely_used()\n"));
+        assert!(!map.contains("helper"));
+    }
+
+    #[test]
+    fn test_most_referenced_symbols_survive_truncation() {
+        let files = vec!["src/auth/login.rs".to_string(), "src/util.rs".to_string()];
+        let map = RepoMap::build(&files, &sample_index(), 20);
+
+        assert!(map.contains("pub fn login()"));
+        assert!(!map.contains("rarely_used()"));
+    }
+}
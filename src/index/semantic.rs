@@ -0,0 +1,225 @@
+use super::chunker::{Chunker, CodeChunk};
+use crate::config::yaml_parser::YamlConfig;
+use crate::llm::embeddings::{cosine_similarity, detect_embedder, Embedder};
+use crate::utils::search::SearchResult;
+use crate::utils::{FileUtils, WriteOptions};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+const EMBED_BATCH_SIZE: usize = 32;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexedChunk {
+    pub chunk: CodeChunk,
+    pub vector: Vec<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexedFile {
+    pub hash: String,
+    pub chunks: Vec<IndexedChunk>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VectorIndex {
+    pub provider: String,
+    pub model: String,
+    pub files: HashMap<String, IndexedFile>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RefreshStats {
+    pub files_indexed: usize,
+    pub files_unchanged: usize,
+    pub files_removed: usize,
+    pub chunks_embedded: usize,
+}
+
+pub struct SemanticIndex {
+    root: PathBuf,
+    index: VectorIndex,
+    embedder: Box<dyn Embedder>,
+}
+
+impl SemanticIndex {
+    pub fn index_path(root: &Path) -> PathBuf {
+        root.join(".codeagent").join("index").join("vectors.json")
+    }
+
+    pub fn open(root: &Path, embedder: Box<dyn Embedder>) -> Self {
+        let index = std::fs::read_to_string(Self::index_path(root))
+            .ok()
+            .and_then(|content| serde_json::from_str::<VectorIndex>(&content).ok())
+            .filter(|index| {
+                index.provider == embedder.provider_name() && index.model == embedder.model_name()
+            })
+            .unwrap_or_else(|| VectorIndex {
+                provider: embedder.provider_name().to_string(),
+                model: embedder.model_name().to_string(),
+                files: HashMap::new(),
+            });
+
+        SemanticIndex {
+            root: root.to_path_buf(),
+            index,
+            embedder,
+        }
+    }
+
+    /// Opens the index with the embedder the project's `.codeagent.yml` selects.
+    pub async fn for_project(root: &Path) -> Self {
+        let config = YamlConfig::load(&root.join(".codeagent.yml")).unwrap_or_default();
+        Self::open(root, detect_embedder(&config).await)
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.files.values().map(|f| f.chunks.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub async fn refresh(&mut self) -> Result<RefreshStats> {
+        let files = FileUtils::find_source_files(&self.root)?;
+        self.refresh_files(&files).await
+    }
+
+    pub async fn refresh_files(&mut self, files: &[PathBuf]) -> Result<RefreshStats> {
+        let mut stats = RefreshStats::default();
+        let mut seen = HashSet::with_capacity(files.len());
+
+        for path in files {
+            let relative = self.relative_key(path);
+            seen.insert(relative.clone());
+
+            let Ok(bytes) = std::fs::read(path) else {
+                continue;
+            };
+            let hash = FileUtils::hash_bytes(&bytes);
+
+            if self.index.files.get(&relative).is_some_and(|f| f.hash == hash) {
+                stats.files_unchanged += 1;
+                continue;
+            }
+
+            let content = String::from_utf8_lossy(&bytes);
+            let chunks = Chunker::chunk(&content);
+            let mut indexed = Vec::with_capacity(chunks.len());
+
+            for batch in chunks.chunks(EMBED_BATCH_SIZE) {
+                let inputs: Vec<String> = batch
+                    .iter()
+                    .map(|c| Self::embedding_input(&relative, c))
+                    .collect();
+                let vectors = self.embedder.embed(&inputs).await?;
+                for (chunk, vector) in batch.iter().zip(vectors) {
+                    indexed.push(IndexedChunk {
+                        chunk: chunk.clone(),
+                        vector,
+                    });
+                }
+            }
+
+            stats.files_indexed += 1;
+            stats.chunks_embedded += indexed.len();
+            self.index.files.insert(relative, IndexedFile { hash, chunks: indexed });
+        }
+
+        let before = self.index.files.len();
+        self.index.files.retain(|key, _| seen.contains(key));
+        stats.files_removed = before - self.index.files.len();
+
+        self.save()?;
+        tracing::info!(
+            "Semantic index refreshed: {} indexed, {} unchanged, {} removed",
+            stats.files_indexed,
+            stats.files_unchanged,
+            stats.files_removed
+        );
+
+        Ok(stats)
+    }
+
+    pub async fn search_semantic(&self, query: &str, k: usize) -> Result<Vec<SearchResult>> {
+        let query_vector = self.embedder.embed_one(query).await?;
+        let query_vector = query_vector.as_slice();
+
+        let mut scored: Vec<(f32, &String, &CodeChunk)> = self
+            .index
+            .files
+            .iter()
+            .flat_map(|(file, indexed)| {
+                indexed
+                    .chunks
+                    .iter()
+                    .map(move |c| (cosine_similarity(query_vector, &c.vector), file, &c.chunk))
+            })
+            .collect();
+
+        scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+
+        Ok(scored
+            .into_iter()
+            .take(k)
+            .map(|(score, file, chunk)| SearchResult {
+                file: self.root.join(file),
+                line_number: chunk.start_line,
+                end_line: chunk.end_line,
+                content: chunk.text.clone(),
+                score: Some(score),
+                ..SearchResult::default()
+            })
+            .collect())
+    }
+
+    /// Refreshes the index first, so prompts never see stale chunks.
+    pub async fn refreshed_context(&mut self, query: &str, k: usize, max_chars: usize) -> Result<String> {
+        self.refresh().await?;
+        self.retrieve_context(query, k, max_chars).await
+    }
+
+    pub async fn retrieve_context(&self, query: &str, k: usize, max_chars: usize) -> Result<String> {
+        let mut context = String::new();
+
+        for result in self.search_semantic(query, k).await? {
+            let relative = result
+                .file
+                .strip_prefix(&self.root)
+                .unwrap_or(&result.file)
+                .display()
+                .to_string();
+            let snippet = format!(
+                "// {}:{}\n{}\n\n",
+                relative, result.line_number, result.content
+            );
+            if context.len() + snippet.len() > max_chars {
+                break;
+            }
+            context.push_str(&snippet);
+        }
+
+        Ok(context)
+    }
+
+    fn save(&self) -> Result<()> {
+        let json = serde_json::to_string(&self.index)?;
+        FileUtils::write_file_with(&Self::index_path(&self.root), &json, &WriteOptions::internal())
+    }
+
+    fn relative_key(&self, path: &Path) -> String {
+        path.strip_prefix(&self.root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace('\\', "/")
+    }
+
+    fn embedding_input(file: &str, chunk: &CodeChunk) -> String {
+        match &chunk.name {
+            Some(name) => format!("{} {}\n{}", file, name, chunk.text),
+            None => format!("{}\n{}", file, chunk.text),
+        }
+    }
+}
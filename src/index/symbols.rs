@@ -0,0 +1,519 @@
+use crate::utils::{FileUtils, WriteOptions};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use tree_sitter::{Node, Parser};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SymbolLanguage {
+    Rust,
+    Python,
+    JavaScript,
+    TypeScript,
+    Tsx,
+    Go,
+}
+
+impl SymbolLanguage {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "rs" => Some(SymbolLanguage::Rust),
+            "py" => Some(SymbolLanguage::Python),
+            "js" | "jsx" | "mjs" | "cjs" => Some(SymbolLanguage::JavaScript),
+            "ts" => Some(SymbolLanguage::TypeScript),
+            "tsx" => Some(SymbolLanguage::Tsx),
+            "go" => Some(SymbolLanguage::Go),
+            _ => None,
+        }
+    }
+
+    fn grammar(&self) -> tree_sitter::Language {
+        match self {
+            SymbolLanguage::Rust => tree_sitter_rust::language(),
+            SymbolLanguage::Python => tree_sitter_python::language(),
+            SymbolLanguage::JavaScript => tree_sitter_javascript::language(),
+            SymbolLanguage::TypeScript => tree_sitter_typescript::language_typescript(),
+            SymbolLanguage::Tsx => tree_sitter_typescript::language_tsx(),
+            SymbolLanguage::Go => tree_sitter_go::language(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SymbolKind {
+    Function,
+    Method,
+    Struct,
+    Enum,
+    Trait,
+    Class,
+    Interface,
+    Type,
+    Module,
+    Constant,
+}
+
+impl fmt::Display for SymbolKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            SymbolKind::Function => "fn",
+            SymbolKind::Method => "method",
+            SymbolKind::Struct => "struct",
+            SymbolKind::Enum => "enum",
+            SymbolKind::Trait => "trait",
+            SymbolKind::Class => "class",
+            SymbolKind::Interface => "interface",
+            SymbolKind::Type => "type",
+            SymbolKind::Module => "mod",
+            SymbolKind::Constant => "const",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+    pub container: Option<String>,
+    pub signature: String,
+    pub public: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SymbolReference {
+    pub name: String,
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FileSymbols {
+    pub hash: String,
+    pub definitions: Vec<Symbol>,
+    pub references: HashMap<String, Vec<(usize, usize)>>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SymbolIndex {
+    #[serde(skip)]
+    root: PathBuf,
+    pub files: HashMap<String, FileSymbols>,
+}
+
+const REFERENCE_KINDS: &[&str] = &[
+    "identifier",
+    "type_identifier",
+    "field_identifier",
+    "property_identifier",
+    "shorthand_property_identifier",
+];
+
+impl SymbolIndex {
+    pub fn index_path(root: &Path) -> PathBuf {
+        root.join(".codeagent").join("index").join("symbols.json")
+    }
+
+    pub fn load(root: &Path) -> Self {
+        let mut index = std::fs::read_to_string(Self::index_path(root))
+            .ok()
+            .and_then(|content| serde_json::from_str::<SymbolIndex>(&content).ok())
+            .unwrap_or_default();
+        index.root = root.to_path_buf();
+        index
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn refresh(&mut self) -> Result<usize> {
+        let files: Vec<PathBuf> = FileUtils::find_source_files(&self.root)?
+            .into_iter()
+            .filter(|p| SymbolLanguage::from_path(p).is_some())
+            .collect();
+        self.refresh_files(&files)
+    }
+
+    pub fn refresh_files(&mut self, files: &[PathBuf]) -> Result<usize> {
+        let mut parser = Parser::new();
+        let mut seen = HashSet::with_capacity(files.len());
+        let mut reindexed = 0;
+
+        for path in files {
+            let Some(language) = SymbolLanguage::from_path(path) else {
+                continue;
+            };
+            let relative = self.relative_key(path);
+            seen.insert(relative.clone());
+
+            let Ok(bytes) = std::fs::read(path) else {
+                continue;
+            };
+            let hash = FileUtils::hash_bytes(&bytes);
+            if self.files.get(&relative).is_some_and(|f| f.hash == hash) {
+                continue;
+            }
+
+            let mut symbols = Self::extract(&mut parser, language, &relative, &bytes)?;
+            symbols.hash = hash;
+            self.files.insert(relative, symbols);
+            reindexed += 1;
+        }
+
+        let before = self.files.len();
+        self.files.retain(|key, _| seen.contains(key));
+        if reindexed > 0 || self.files.len() < before {
+            self.save()?;
+        }
+
+        tracing::info!("Symbol index refreshed: {} files re-parsed", reindexed);
+        Ok(reindexed)
+    }
+
+    pub fn find_definitions(&self, name: &str) -> Vec<&Symbol> {
+        let mut found: Vec<&Symbol> = self
+            .files
+            .values()
+            .flat_map(|f| f.definitions.iter())
+            .filter(|s| s.name == name)
+            .collect();
+        found.sort_by(|a, b| (&a.file, a.line).cmp(&(&b.file, b.line)));
+        found
+    }
+
+    pub fn find_references(&self, name: &str) -> Vec<SymbolReference> {
+        let mut found: Vec<SymbolReference> = self
+            .files
+            .iter()
+            .flat_map(|(file, symbols)| {
+                symbols
+                    .references
+                    .get(name)
+                    .into_iter()
+                    .flatten()
+                    .map(move |(line, column)| SymbolReference {
+                        name: name.to_string(),
+                        file: file.clone(),
+                        line: *line,
+                        column: *column,
+                    })
+            })
+            .collect();
+        found.sort_by(|a, b| (&a.file, a.line, a.column).cmp(&(&b.file, b.line, b.column)));
+        found
+    }
+
+    pub fn search(&self, query: &str) -> Vec<&Symbol> {
+        let query_lower = query.to_lowercase();
+        let mut found: Vec<&Symbol> = self
+            .files
+            .values()
+            .flat_map(|f| f.definitions.iter())
+            .filter(|s| s.name.to_lowercase().contains(&query_lower))
+            .collect();
+        found.sort_by_key(|s| (s.name.len(), s.file.clone(), s.line));
+        found
+    }
+
+    pub fn definitions_in(&self, file: &str) -> &[Symbol] {
+        self.files
+            .get(file)
+            .map(|f| f.definitions.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn reference_count(&self, name: &str) -> usize {
+        self.files
+            .values()
+            .filter_map(|f| f.references.get(name))
+            .map(|refs| refs.len())
+            .sum()
+    }
+
+    fn save(&self) -> Result<()> {
+        let json = serde_json::to_string(self)?;
+        FileUtils::write_file_with(&Self::index_path(&self.root), &json, &WriteOptions::internal())
+    }
+
+    fn relative_key(&self, path: &Path) -> String {
+        path.strip_prefix(&self.root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace('\\', "/")
+    }
+
+    pub fn extract(
+        parser: &mut Parser,
+        language: SymbolLanguage,
+        file: &str,
+        source: &[u8],
+    ) -> Result<FileSymbols> {
+        parser.set_language(language.grammar())?;
+        let tree = parser
+            .parse(source, None)
+            .ok_or_else(|| anyhow::anyhow!("Failed to parse {}", file))?;
+
+        let mut extractor = Extractor {
+            language,
+            file,
+            source,
+            definitions: Vec::new(),
+            definition_names: HashSet::new(),
+            references: HashMap::new(),
+        };
+        extractor.visit(tree.root_node(), None);
+
+        Ok(FileSymbols {
+            hash: String::new(),
+            definitions: extractor.definitions,
+            references: extractor.references,
+        })
+    }
+}
+
+struct Extractor<'a> {
+    language: SymbolLanguage,
+    file: &'a str,
+    source: &'a [u8],
+    definitions: Vec<Symbol>,
+    definition_names: HashSet<usize>,
+    references: HashMap<String, Vec<(usize, usize)>>,
+}
+
+impl<'a> Extractor<'a> {
+    fn visit(&mut self, node: Node, container: Option<String>) {
+        let mut child_container = container.clone();
+
+        if let Some((kind, name_node)) = self.classify(node, container.is_some()) {
+            let name = self.text(name_node);
+            self.definition_names.insert(name_node.start_byte());
+            self.definitions.push(Symbol {
+                name: name.clone(),
+                kind,
+                file: self.file.to_string(),
+                line: node.start_position().row + 1,
+                column: name_node.start_position().column + 1,
+                end_line: node.end_position().row + 1,
+                container: container.clone(),
+                signature: self.signature(node),
+                public: self.is_public(node, &name),
+            });
+
+            if matches!(
+                kind,
+                SymbolKind::Class | SymbolKind::Trait | SymbolKind::Interface | SymbolKind::Module
+            ) {
+                child_container = Some(name);
+            }
+        } else if let Some(owner) = self.impl_owner(node) {
+            child_container = Some(owner);
+        }
+
+        if node.child_count() == 0 {
+            if REFERENCE_KINDS.contains(&node.kind())
+                && !self.definition_names.contains(&node.start_byte())
+            {
+                let name = self.text(node);
+                if name.len() > 1 {
+                    let position = node.start_position();
+                    self.references
+                        .entry(name)
+                        .or_default()
+                        .push((position.row + 1, position.column + 1));
+                }
+            }
+            return;
+        }
+
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            self.visit(child, child_container.clone());
+        }
+    }
+
+    fn classify<'t>(&self, node: Node<'t>, in_container: bool) -> Option<(SymbolKind, Node<'t>)> {
+        let callable = if in_container {
+            SymbolKind::Method
+        } else {
+            SymbolKind::Function
+        };
+
+        let kind = match (self.language, node.kind()) {
+            (SymbolLanguage::Rust, "function_item" | "function_signature_item") => callable,
+            (SymbolLanguage::Rust, "struct_item" | "union_item") => SymbolKind::Struct,
+            (SymbolLanguage::Rust, "enum_item") => SymbolKind::Enum,
+            (SymbolLanguage::Rust, "trait_item") => SymbolKind::Trait,
+            (SymbolLanguage::Rust, "type_item") => SymbolKind::Type,
+            (SymbolLanguage::Rust, "mod_item") => SymbolKind::Module,
+            (SymbolLanguage::Rust, "const_item" | "static_item") => SymbolKind::Constant,
+            (SymbolLanguage::Python, "function_definition") => callable,
+            (SymbolLanguage::Python, "class_definition") => SymbolKind::Class,
+            (SymbolLanguage::Go, "function_declaration") => SymbolKind::Function,
+            (SymbolLanguage::Go, "method_declaration") => SymbolKind::Method,
+            (SymbolLanguage::Go, "type_spec") => match node.child_by_field_name("type")?.kind() {
+                "struct_type" => SymbolKind::Struct,
+                "interface_type" => SymbolKind::Interface,
+                _ => SymbolKind::Type,
+            },
+            (_, "function_declaration" | "generator_function_declaration") => SymbolKind::Function,
+            (_, "class_declaration" | "abstract_class_declaration") => SymbolKind::Class,
+            (_, "method_definition" | "method_signature") => SymbolKind::Method,
+            (_, "interface_declaration") => SymbolKind::Interface,
+            (_, "type_alias_declaration") => SymbolKind::Type,
+            (_, "enum_declaration") => SymbolKind::Enum,
+            (_, "variable_declarator") => {
+                let value = node.child_by_field_name("value")?;
+                match value.kind() {
+                    "arrow_function" | "function" | "function_expression" => SymbolKind::Function,
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        };
+
+        let name_node = node.child_by_field_name("name")?;
+        Some((kind, name_node))
+    }
+
+    fn impl_owner(&self, node: Node) -> Option<String> {
+        match (self.language, node.kind()) {
+            (SymbolLanguage::Rust, "impl_item") => {
+                let ty = node.child_by_field_name("type")?;
+                let text = self.text(ty);
+                Some(text.split('<').next().unwrap_or(&text).to_string())
+            }
+            _ => None,
+        }
+    }
+
+    fn is_public(&self, node: Node, name: &str) -> bool {
+        match self.language {
+            SymbolLanguage::Rust => {
+                let mut cursor = node.walk();
+                let has_pub = node
+                    .children(&mut cursor)
+                    .any(|c| c.kind() == "visibility_modifier");
+                has_pub
+            }
+            SymbolLanguage::Python => !name.starts_with('_'),
+            SymbolLanguage::Go => name.chars().next().is_some_and(|c| c.is_uppercase()),
+            _ => {
+                let mut current = node.parent();
+                while let Some(parent) = current {
+                    if parent.kind() == "export_statement" {
+                        return true;
+                    }
+                    if parent.kind() == "program" {
+                        break;
+                    }
+                    current = parent.parent();
+                }
+                false
+            }
+        }
+    }
+
+    fn signature(&self, node: Node) -> String {
+        let text = self.text(node);
+        let header = text.lines().next().unwrap_or_default().trim();
+        let header = header
+            .trim_end_matches('{')
+            .trim_end_matches(':')
+            .trim_end();
+        if header.chars().count() > 120 {
+            format!("{}…", header.chars().take(119).collect::<String>())
+        } else {
+            header.to_string()
+        }
+    }
+
+    fn text(&self, node: Node) -> String {
+        node.utf8_text(self.source).unwrap_or_default().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extract(language: SymbolLanguage, source: &str) -> FileSymbols {
+        let mut parser = Parser::new();
+        SymbolIndex::extract(&mut parser, language, "sample", source.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_rust_definitions_and_references() {
+        let symbols = extract(
+            SymbolLanguage::Rust,
+            "pub struct Token;\nimpl Token {\n    pub fn validate(&self) -> bool { check(self) }\n}\nfn check(t: &Token) -> bool { true }\n",
+        );
+
+        let names: Vec<(&str, SymbolKind)> = symbols
+            .definitions
+            .iter()
+            .map(|s| (s.name.as_str(), s.kind))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("Token", SymbolKind::Struct),
+                ("validate", SymbolKind::Method),
+                ("check", SymbolKind::Function),
+            ]
+        );
+        assert_eq!(symbols.definitions[1].container.as_deref(), Some("Token"));
+        assert!(symbols.definitions[0].public);
+        assert!(!symbols.definitions[2].public);
+        assert_eq!(symbols.references.get("check"), Some(&vec![(3, 38)]));
+    }
+
+    #[test]
+    fn test_python_and_go_definitions() {
+        let python = extract(
+            SymbolLanguage::Python,
+            "class Auth:\n    def login(self):\n        pass\n\ndef _helper():\n    pass\n",
+        );
+        assert_eq!(python.definitions[0].kind, SymbolKind::Class);
+        assert_eq!(python.definitions[1].kind, SymbolKind::Method);
+        assert!(!python.definitions[2].public);
+
+        let go = extract(
+            SymbolLanguage::Go,
+            "package main\ntype Server struct{}\nfunc (s *Server) Start() {}\nfunc main() {}\n",
+        );
+        let kinds: Vec<SymbolKind> = go.definitions.iter().map(|s| s.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![SymbolKind::Struct, SymbolKind::Method, SymbolKind::Function]
+        );
+    }
+
+    #[test]
+    fn test_refresh_saves_only_when_something_changed() {
+        let root = std::env::temp_dir().join(format!("codeagent-symbols-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/lib.rs"), "pub fn login() {}\n").unwrap();
+        std::fs::write(root.join("src/theme.rs"), "pub fn colors() {}\n").unwrap();
+
+        let mut index = SymbolIndex::load(&root);
+        assert_eq!(index.refresh().unwrap(), 2);
+        std::fs::remove_file(SymbolIndex::index_path(&root)).unwrap();
+        assert_eq!(index.refresh().unwrap(), 0);
+        assert!(!SymbolIndex::index_path(&root).exists());
+
+        std::fs::remove_file(root.join("src/theme.rs")).unwrap();
+        index.refresh().unwrap();
+        assert_eq!(SymbolIndex::load(&root).files.len(), 1);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,281 @@
+use crate::config::yaml_parser::CustomTool;
+use crate::parsers::lint_parser::LintParser;
+use crate::parsers::test_parser::TestParser;
+use crate::parsers::{LintResult, TestResult, TestStatus};
+use anyhow::Result;
+use regex::Regex;
+use std::collections::HashMap;
+use crate::integrations::sandbox::{CommandRunner, CommandSpec};
+use std::path::{Component, Path};
+use std::time::Duration;
+
+pub const DEFAULT_TOOL_TIMEOUT_SECS: u64 = 120;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputParser {
+    Clippy,
+    Eslint,
+    Pylint,
+    CargoTest,
+    Jest,
+    Pytest,
+    GoTest,
+}
+
+impl OutputParser {
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name.to_lowercase().as_str() {
+            "clippy" | "rustc" | "cargo" => Ok(OutputParser::Clippy),
+            "eslint" => Ok(OutputParser::Eslint),
+            "pylint" => Ok(OutputParser::Pylint),
+            "cargo-test" | "libtest" => Ok(OutputParser::CargoTest),
+            "jest" => Ok(OutputParser::Jest),
+            "pytest" => Ok(OutputParser::Pytest),
+            "go-test" | "gotest" => Ok(OutputParser::GoTest),
+            other => Err(anyhow::anyhow!(
+                "Unknown output parser '{}' (expected clippy, eslint, pylint, cargo-test, jest, pytest or go-test)",
+                other
+            )),
+        }
+    }
+
+    pub fn parse_lints(&self, output: &str) -> Vec<LintResult> {
+        match self {
+            OutputParser::Clippy => LintParser::parse_clippy_output(output),
+            OutputParser::Eslint => LintParser::parse_eslint_output(output),
+            OutputParser::Pylint => LintParser::parse_pylint_output(output),
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn parse_tests(&self, output: &str) -> Vec<TestResult> {
+        match self {
+            OutputParser::CargoTest => TestParser::parse_cargo_test_output(output),
+            OutputParser::Jest => TestParser::parse_jest_output(output),
+            OutputParser::Pytest => TestParser::parse_pytest_output(output),
+            OutputParser::GoTest => TestParser::parse_go_test_output(output),
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CustomToolOutput {
+    pub tool: String,
+    pub command: Vec<String>,
+    pub stdout: String,
+    pub stderr: String,
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub duration_ms: u64,
+    pub lint_issues: Vec<LintResult>,
+    pub test_results: Vec<TestResult>,
+}
+
+impl CustomToolOutput {
+    pub fn success(&self) -> bool {
+        self.exit_code == Some(0)
+            && !self.timed_out
+            && !self.test_results.iter().any(|t| t.status == TestStatus::Failed)
+    }
+
+    pub fn to_text(&self) -> String {
+        let status = if self.timed_out {
+            "timed out".to_string()
+        } else {
+            match self.exit_code {
+                Some(code) => format!("exit code {}", code),
+                None => "killed by signal".to_string(),
+            }
+        };
+        let mut text = format!("$ {}\n{} after {}ms\n", self.command.join(" "), status, self.duration_ms);
+        if !self.stdout.trim().is_empty() {
+            text.push_str(&format!("--- stdout ---\n{}\n", self.stdout.trim_end()));
+        }
+        if !self.stderr.trim().is_empty() {
+            text.push_str(&format!("--- stderr ---\n{}\n", self.stderr.trim_end()));
+        }
+        if !self.lint_issues.is_empty() {
+            text.push_str(&format!("--- {} issues ---\n", self.lint_issues.len()));
+            for issue in &self.lint_issues {
+                text.push_str(&format!(
+                    "{}:{}:{} {:?} [{}] {}\n",
+                    issue.file, issue.line, issue.column, issue.severity, issue.rule, issue.message
+                ));
+            }
+        }
+        if !self.test_results.is_empty() {
+            let summary = TestParser::summarize_results(&self.test_results);
+            text.push_str(&format!(
+                "--- tests: {} passed, {} failed, {} skipped ---\n",
+                summary.passed, summary.failed, summary.skipped
+            ));
+        }
+        text
+    }
+}
+
+pub struct CustomToolRunner;
+
+impl CustomToolRunner {
+    pub fn placeholders(tool: &CustomTool) -> Vec<String> {
+        let re = Regex::new(r"\{(\w+)\}").unwrap();
+        let mut names = Vec::new();
+        for text in std::iter::once(&tool.command).chain(tool.args.iter()) {
+            for cap in re.captures_iter(text) {
+                let name = cap[1].to_string();
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+        }
+        names
+    }
+
+    pub fn render(template: &str, vars: &HashMap<String, String>) -> Result<String> {
+        let re = Regex::new(r"\{(\w+)\}").unwrap();
+        let mut missing = None;
+        let rendered = re.replace_all(template, |cap: &regex::Captures| match vars.get(&cap[1]) {
+            Some(value) => value.clone(),
+            None => {
+                missing = Some(cap[1].to_string());
+                String::new()
+            }
+        });
+        match missing {
+            Some(name) => Err(anyhow::anyhow!("No value for {{{}}} in '{}'", name, template)),
+            None => Ok(rendered.into_owned()),
+        }
+    }
+
+    /// Values come from the model and are often spliced into `sh -c` scripts, so they may
+    /// only be plain words, and path-like arguments must name a location inside the project.
+    pub fn validate_arg(name: &str, value: &str, project_root: &Path) -> Result<()> {
+        let plain = !value.is_empty()
+            && !value.starts_with('-')
+            && value
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "._/@:+=,-".contains(c));
+        if !plain {
+            return Err(anyhow::anyhow!(
+                "Argument {{{}}} must be a plain word or project-relative path, got '{}'",
+                name,
+                value
+            ));
+        }
+
+        let is_path = ["file", "path", "dir"].iter().any(|suffix| name.ends_with(suffix)) || value.contains('/');
+        if is_path {
+            let path = Path::new(value);
+            let mut escapes = path.is_absolute()
+                || path.components().any(|c| !matches!(c, Component::Normal(_) | Component::CurDir));
+            // A symlink inside the project can still point outside it.
+            if let (Ok(target), Ok(root)) = (project_root.join(path).canonicalize(), project_root.canonicalize()) {
+                escapes |= !target.starts_with(root);
+            }
+            if escapes {
+                return Err(anyhow::anyhow!(
+                    "Argument {{{}}} must be a path inside the project, got '{}'",
+                    name,
+                    value
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn run(tool: &CustomTool, project_root: &Path, args: &HashMap<String, String>) -> Result<CustomToolOutput> {
+        if !tool.enabled {
+            return Err(anyhow::anyhow!("Custom tool '{}' is disabled", tool.name));
+        }
+        let parser = tool.parser.as_deref().map(OutputParser::from_name).transpose()?;
+
+        for (name, value) in args {
+            Self::validate_arg(name, value, project_root)?;
+        }
+        let mut vars = args.clone();
+        vars.insert("project_root".to_string(), project_root.display().to_string());
+        let program = Self::render(&tool.command, &vars)?;
+        let rendered_args = tool
+            .args
+            .iter()
+            .map(|arg| Self::render(arg, &vars))
+            .collect::<Result<Vec<String>>>()?;
+
+        tracing::info!("Running custom tool {}: {} {}", tool.name, program, rendered_args.join(" "));
+        let mut spec = CommandSpec::for_project(&program, &[], project_root)
+            .with_timeout(Duration::from_secs(tool.timeout_secs.unwrap_or(DEFAULT_TOOL_TIMEOUT_SECS)));
+        spec.args = rendered_args.clone();
+        let result = CommandRunner::run_blocking(&spec)
+            .map_err(|e| anyhow::anyhow!("Failed to start custom tool '{}' ({}): {}", tool.name, program, e))?;
+
+        let mut output = CustomToolOutput {
+            tool: tool.name.clone(),
+            command: std::iter::once(program).chain(rendered_args).collect(),
+            stdout: result.stdout,
+            stderr: result.stderr,
+            exit_code: result.exit_code,
+            timed_out: result.timed_out,
+            duration_ms: result.duration_ms,
+            ..CustomToolOutput::default()
+        };
+
+        let combined = format!("{}\n{}", output.stdout, output.stderr);
+        if let Some(parser) = parser {
+            output.lint_issues = parser.parse_lints(&combined);
+            output.test_results = parser.parse_tests(&combined);
+        }
+
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shell_tool(script: &str, parser: Option<&str>, timeout_secs: Option<u64>) -> CustomTool {
+        CustomTool {
+            name: "check".to_string(),
+            command: "sh".to_string(),
+            args: vec!["-c".to_string(), script.to_string()],
+            enabled: true,
+            description: None,
+            timeout_secs,
+            parser: parser.map(|p| p.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_templated_run_with_clippy_parser() {
+        let tool = shell_tool(
+            "echo \"{file}:3:1: warning[dead_code]: unused in $(basename {project_root})\" >&2; exit 1",
+            Some("clippy"),
+            None,
+        );
+        let root = std::env::temp_dir();
+        let args = HashMap::from([("file".to_string(), "src/lib.rs".to_string())]);
+        let output = CustomToolRunner::run(&tool, &root, &args).unwrap();
+
+        assert_eq!(output.exit_code, Some(1));
+        assert!(!output.success());
+        assert_eq!(output.lint_issues.len(), 1);
+        assert_eq!(output.lint_issues[0].file, "src/lib.rs");
+        assert_eq!(output.lint_issues[0].rule, "dead_code");
+        assert_eq!(CustomToolRunner::placeholders(&tool), vec!["file", "project_root"]);
+        assert!(CustomToolRunner::run(&tool, &root, &HashMap::new()).is_err());
+
+        for bad in ["src/lib.rs; rm -rf ~", "$(whoami)", "../secret.rs", "/etc/passwd", "--config=x", "a b"] {
+            let args = HashMap::from([("file".to_string(), bad.to_string())]);
+            assert!(CustomToolRunner::run(&tool, &root, &args).is_err(), "accepted {}", bad);
+        }
+    }
+
+    #[test]
+    fn test_timeout_kills_the_process() {
+        let tool = shell_tool("sleep 5", None, Some(1));
+        let output = CustomToolRunner::run(&tool, &std::env::temp_dir(), &HashMap::new()).unwrap();
+        assert!(output.timed_out);
+        assert!(output.duration_ms < 4000);
+    }
+}
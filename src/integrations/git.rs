@@ -1,15 +1,22 @@
+use crate::core::audit::{AuditEvent, AuditLog};
+use crate::core::policy::PolicyEnforcer;
 use anyhow::Result;
-use std::path::Path;
-use git2::{Repository, IndexAddOption};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use git2::{IndexAddOption, Repository, StatusOptions};
 
 pub struct GitManager {
     repo: Option<Repository>,
+    policy: Arc<PolicyEnforcer>,
 }
 
 impl GitManager {
     pub fn new(repo_path: &Path) -> Result<Self> {
         let repo = Repository::open(repo_path).ok();
-        Ok(GitManager { repo })
+        Ok(GitManager {
+            repo,
+            policy: PolicyEnforcer::for_project(repo_path),
+        })
     }
 
     pub fn is_git_repo(&self) -> bool {
@@ -18,9 +25,30 @@ impl GitManager {
 
     pub fn stage_changes(&self, patterns: &[&str]) -> Result<()> {
         if let Some(repo) = &self.repo {
-            let mut index = repo.index()?;
-            index.add_all(patterns.iter().map(|s| s.to_string()), IndexAddOption::DEFAULT, None)?;
-            index.write()?;
+            let result = (|| -> Result<()> {
+                let mut index = repo.index()?;
+                let pathspecs: Vec<String> = patterns.iter().map(|s| s.to_string()).collect();
+                // Collect what the pathspecs would stage without adding anything, so the
+                // policy sees the concrete files before the index changes.
+                let mut matched = Vec::new();
+                index.add_all(
+                    pathspecs.iter(),
+                    IndexAddOption::DEFAULT,
+                    Some(&mut |path: &Path, _: &[u8]| {
+                        matched.push(path.to_path_buf());
+                        1
+                    }),
+                )?;
+                let workdir = repo.workdir().ok_or_else(|| anyhow::anyhow!("Cannot stage in a bare repository"))?;
+                let files: Vec<PathBuf> = matched.iter().map(|p| workdir.join(p)).collect();
+                self.policy.check_stage(&files)?;
+
+                index.add_all(pathspecs.iter(), IndexAddOption::DEFAULT, None)?;
+                index.write()?;
+                Ok(())
+            })();
+            Self::audit("stage", patterns.join(" "), result.as_ref().err());
+            result?;
             tracing::info!("Staged changes: {:?}", patterns);
             Ok(())
         } else {
@@ -30,21 +58,31 @@ impl GitManager {
 
     pub fn commit(&self, message: &str) -> Result<()> {
         if let Some(repo) = &self.repo {
-            let signature = repo.signature()?;
-            let tree_id = {
-                let mut index = repo.index()?;
-                index.write_tree()?
+            self.policy.check_git_commit(message)?;
+            let result = (|| -> Result<git2::Oid> {
+                let signature = repo.signature()?;
+                let tree_id = {
+                    let mut index = repo.index()?;
+                    index.write_tree()?
+                };
+                let tree = repo.find_tree(tree_id)?;
+                let parent_commit = repo.head()?.peel_to_commit()?;
+                Ok(repo.commit(
+                    Some("HEAD"),
+                    &signature,
+                    &signature,
+                    message,
+                    &tree,
+                    &[&parent_commit],
+                )?)
+            })();
+            let subject = message.lines().next().unwrap_or_default();
+            let detail = match &result {
+                Ok(oid) => format!("{} \"{}\"", oid, subject),
+                Err(_) => format!("\"{}\"", subject),
             };
-            let tree = repo.find_tree(tree_id)?;
-            let parent_commit = repo.head()?.peel_to_commit()?;
-            repo.commit(
-                Some("HEAD"),
-                &signature,
-                &signature,
-                message,
-                &tree,
-                &[&parent_commit],
-            )?;
+            Self::audit("commit", detail, result.as_ref().err());
+            result?;
             tracing::info!("Committed with message: {}", message);
             Ok(())
         } else {
@@ -52,9 +90,22 @@ impl GitManager {
         }
     }
 
+    fn audit(operation: &str, detail: String, error: Option<&anyhow::Error>) {
+        AuditLog::record(AuditEvent::Git {
+            operation: operation.to_string(),
+            detail,
+            error: error.map(|e| e.to_string()),
+        });
+    }
+
     pub fn get_status(&self) -> Result<Vec<String>> {
         if let Some(repo) = &self.repo {
-            let statuses = repo.statuses(None)?;
+            let mut options = StatusOptions::new();
+            options
+                .include_untracked(true)
+                .recurse_untracked_dirs(true)
+                .include_ignored(false);
+            let statuses = repo.statuses(Some(&mut options))?;
             let mut status_list = Vec::new();
 
             for item in statuses.iter() {
@@ -68,4 +119,39 @@ impl GitManager {
             Err(anyhow::anyhow!("Not a Git repository"))
         }
     }
+
+    pub fn head_commit(&self) -> Option<String> {
+        let repo = self.repo.as_ref()?;
+        let commit = repo.head().ok()?.peel_to_commit().ok()?;
+        Some(commit.id().to_string())
+    }
+
+    pub fn recently_changed_files(&self, max_commits: usize) -> Result<Vec<String>> {
+        let Some(repo) = &self.repo else {
+            return Err(anyhow::anyhow!("Not a Git repository"));
+        };
+
+        let mut files: Vec<String> = Vec::new();
+        let mut revwalk = repo.revwalk()?;
+        if revwalk.push_head().is_err() {
+            return Ok(files);
+        }
+
+        for oid in revwalk.take(max_commits) {
+            let commit = repo.find_commit(oid?)?;
+            let tree = commit.tree()?;
+            let parent_tree = commit.parent(0).ok().map(|p| p.tree()).transpose()?;
+            let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?;
+
+            for delta in diff.deltas() {
+                if let Some(path) = delta.new_file().path().and_then(|p| p.to_str()) {
+                    if !files.iter().any(|f| f == path) {
+                        files.push(path.to_string());
+                    }
+                }
+            }
+        }
+
+        Ok(files)
+    }
 }
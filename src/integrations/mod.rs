@@ -1,5 +1,8 @@
 #![allow(dead_code)]
 
+pub mod custom_tools;
 pub mod git;
 pub mod ollama;
+pub mod sandbox;
+pub mod test_frameworks;
 pub mod test_runner;
@@ -1,4 +1,7 @@
+use crate::core::audit::AuditLog;
+use crate::llm::client::{LLMClient, LLMResponse};
 use anyhow::Result;
+use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize)]
@@ -17,6 +20,10 @@ struct OllamaRequest {
 #[derive(Deserialize)]
 struct OllamaResponse {
     response: String,
+    #[serde(default)]
+    prompt_eval_count: Option<u32>,
+    #[serde(default)]
+    eval_count: Option<u32>,
 }
 
 #[derive(Deserialize)]
@@ -31,6 +38,89 @@ pub struct OllamaTagsResponse {
     pub models: Vec<OllamaModel>,
 }
 
+#[derive(Serialize)]
+struct OllamaModelRequest {
+    model: String,
+}
+
+#[derive(Serialize)]
+struct OllamaPullRequest {
+    model: String,
+    stream: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OllamaPullProgress {
+    #[serde(default)]
+    pub status: String,
+    #[serde(default)]
+    pub digest: Option<String>,
+    #[serde(default)]
+    pub total: Option<u64>,
+    #[serde(default)]
+    pub completed: Option<u64>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl OllamaPullProgress {
+    pub fn percent(&self) -> Option<u16> {
+        match (self.completed, self.total) {
+            (Some(completed), Some(total)) if total > 0 => {
+                Some(((completed.min(total) * 100) / total) as u16)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct OllamaModelDetails {
+    #[serde(default)]
+    pub family: String,
+    #[serde(default)]
+    pub parameter_size: String,
+    #[serde(default)]
+    pub quantization_level: String,
+}
+
+#[derive(Deserialize)]
+struct OllamaShowResponse {
+    #[serde(default)]
+    parameters: String,
+    #[serde(default)]
+    template: String,
+    #[serde(default)]
+    details: OllamaModelDetails,
+    #[serde(default)]
+    model_info: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone)]
+pub struct OllamaModelInfo {
+    pub name: String,
+    pub context_length: Option<u64>,
+    pub parameters: String,
+    pub template: String,
+    pub details: OllamaModelDetails,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OllamaRunningModel {
+    pub name: String,
+    #[serde(default)]
+    pub size: i64,
+    #[serde(default)]
+    pub size_vram: i64,
+    #[serde(default)]
+    pub expires_at: String,
+}
+
+#[derive(Deserialize)]
+struct OllamaPsResponse {
+    models: Vec<OllamaRunningModel>,
+}
+
 pub struct OllamaClient {
     pub base_url: String,
     pub model: String,
@@ -282,14 +372,34 @@ impl OllamaClient {
     }
 
     pub async fn generate(&self, prompt: &str) -> Result<String> {
+        self.generate_with_context(prompt, "").await
+    }
+
+    pub async fn generate_with_context(&self, prompt: &str, context: &str) -> Result<String> {
         tracing::info!("Generating response from Ollama model: {}", self.model);
+
+        let full_prompt = if context.is_empty() {
+            format!("{}\n\nUser: {}\n\nAssistant:", SYSTEM_PROMPT, prompt)
+        } else {
+            format!("{}\n\n{}\nUser: {}\n\nAssistant:", SYSTEM_PROMPT, context, prompt)
+        };
         
-        let full_prompt = format!("{}\n\nUser: {}\n\nAssistant:", SYSTEM_PROMPT, prompt);
-        
+        let prompt_hash = AuditLog::hash_prompt([full_prompt.as_str()]);
+        let started = std::time::Instant::now();
+        let result = self.send_generate(full_prompt).await;
+        let tokens = result
+            .as_ref()
+            .map(|r| (r.prompt_eval_count, r.eval_count))
+            .unwrap_or_default();
+        AuditLog::llm_call("Ollama", &self.model, prompt_hash, tokens, started, result.as_ref().err());
+        Ok(result?.response.trim().to_string())
+    }
+
+    async fn send_generate(&self, prompt: String) -> Result<OllamaResponse> {
         let url = format!("{}/api/generate", self.base_url);
         let request = OllamaRequest {
             model: self.model.clone(),
-            prompt: full_prompt,
+            prompt,
             stream: false,
         };
 
@@ -300,8 +410,7 @@ impl OllamaClient {
             .await?;
 
         if response.status().is_success() {
-            let data: OllamaResponse = response.json().await?;
-            Ok(data.response.trim().to_string())
+            Ok(response.json().await?)
         } else {
             Err(anyhow::anyhow!(
                 "Ollama API error: {}",
@@ -317,4 +426,183 @@ impl OllamaClient {
             Err(_) => Ok(false),
         }
     }
+
+    pub async fn pull<F>(&mut self, model: &str, mut on_progress: F) -> Result<()>
+    where
+        F: FnMut(&OllamaPullProgress),
+    {
+        tracing::info!("Pulling Ollama model: {}", model);
+
+        let url = format!("{}/api/pull", self.base_url);
+        let request = OllamaPullRequest {
+            model: model.to_string(),
+            stream: true,
+        };
+
+        let mut response = self.client.post(&url).json(&request).send().await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!("Ollama pull error: {}", error_text));
+        }
+
+        // Chunks can end inside a multibyte character, so only decode complete lines.
+        let mut buffer: Vec<u8> = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            buffer.extend_from_slice(&chunk);
+
+            while let Some(newline) = buffer.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=newline).collect();
+                Self::handle_pull_line(String::from_utf8_lossy(&line).trim(), &mut on_progress)?;
+            }
+        }
+        Self::handle_pull_line(String::from_utf8_lossy(&buffer).trim(), &mut on_progress)?;
+
+        if !self.available_models.iter().any(|m| m == model) {
+            self.available_models.push(model.to_string());
+        }
+
+        Ok(())
+    }
+
+    fn handle_pull_line<F>(line: &str, on_progress: &mut F) -> Result<()>
+    where
+        F: FnMut(&OllamaPullProgress),
+    {
+        if line.is_empty() {
+            return Ok(());
+        }
+
+        let progress: OllamaPullProgress = serde_json::from_str(line)?;
+        if let Some(error) = &progress.error {
+            return Err(anyhow::anyhow!("Ollama pull error: {}", error));
+        }
+
+        on_progress(&progress);
+        Ok(())
+    }
+
+    pub async fn show(&self, model: &str) -> Result<OllamaModelInfo> {
+        let url = format!("{}/api/show", self.base_url);
+        let request = OllamaModelRequest {
+            model: model.to_string(),
+        };
+
+        let response = self.client.post(&url).json(&request).send().await?;
+
+        if response.status().is_success() {
+            let data: OllamaShowResponse = response.json().await?;
+            let context_length = data
+                .model_info
+                .iter()
+                .find(|(key, _)| key.ends_with(".context_length"))
+                .and_then(|(_, value)| value.as_u64());
+
+            Ok(OllamaModelInfo {
+                name: model.to_string(),
+                context_length,
+                parameters: data.parameters,
+                template: data.template,
+                details: data.details,
+            })
+        } else {
+            Err(anyhow::anyhow!(
+                "Failed to show model {}: {}",
+                model,
+                response.status()
+            ))
+        }
+    }
+
+    pub async fn delete(&mut self, model: &str) -> Result<()> {
+        tracing::info!("Deleting Ollama model: {}", model);
+
+        let url = format!("{}/api/delete", self.base_url);
+        let request = OllamaModelRequest {
+            model: model.to_string(),
+        };
+
+        let response = self.client.delete(&url).json(&request).send().await?;
+
+        if response.status().is_success() {
+            self.available_models.retain(|m| m != model);
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "Failed to delete model {}: {}",
+                model,
+                response.status()
+            ))
+        }
+    }
+
+    pub async fn ps(&self) -> Result<Vec<OllamaRunningModel>> {
+        let url = format!("{}/api/ps", self.base_url);
+        let response = self.client.get(&url).send().await?;
+
+        if response.status().is_success() {
+            let data: OllamaPsResponse = response.json().await?;
+            Ok(data.models)
+        } else {
+            Err(anyhow::anyhow!("Failed to list running models"))
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl LLMClient for OllamaClient {
+    async fn generate(&self, prompt: &str) -> Result<LLMResponse> {
+        LLMClient::generate_with_context(self, "", prompt).await
+    }
+
+    async fn generate_with_context(&self, system_prompt: &str, user_prompt: &str) -> Result<LLMResponse> {
+        let content = OllamaClient::generate_with_context(self, user_prompt, system_prompt).await?;
+        Ok(LLMResponse {
+            content,
+            tokens_used: None,
+            model: self.model.clone(),
+            provider: "Ollama".to_string(),
+        })
+    }
+
+    fn provider_name(&self) -> &str {
+        "Ollama"
+    }
+
+    fn model_name(&self) -> &str {
+        &self.model
+    }
+
+    async fn health_check(&self) -> Result<bool> {
+        OllamaClient::health_check(self).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pull_progress_percent() {
+        let progress: OllamaPullProgress = serde_json::from_str(
+            r#"{"status":"pulling 6a0746a1ec1a","digest":"sha256:6a07","total":200,"completed":50}"#,
+        )
+        .unwrap();
+        assert_eq!(progress.percent(), Some(25));
+
+        let status_only: OllamaPullProgress =
+            serde_json::from_str(r#"{"status":"verifying sha256 digest"}"#).unwrap();
+        assert_eq!(status_only.percent(), None);
+    }
+
+    #[test]
+    fn test_pull_line_reports_error() {
+        let mut seen = Vec::new();
+        let result = OllamaClient::handle_pull_line(
+            r#"{"error":"pull model manifest: file does not exist"}"#,
+            &mut |p: &OllamaPullProgress| seen.push(p.status.clone()),
+        );
+        assert!(result.is_err());
+        assert!(seen.is_empty());
+    }
 }
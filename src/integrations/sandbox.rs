@@ -0,0 +1,416 @@
+use crate::config::yaml_parser::SandboxConfig;
+use crate::core::audit::{AuditEvent, AuditLog};
+use crate::core::policy::PolicyEnforcer;
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::process::Command;
+
+pub const DEFAULT_COMMAND_TIMEOUT: Duration = Duration::from_secs(600);
+pub const DEFAULT_MAX_OUTPUT_BYTES: usize = 4 * 1024 * 1024;
+
+const DEFAULT_ENV_ALLOWLIST: &[&str] = &[
+    "PATH",
+    "HOME",
+    "USER",
+    "LOGNAME",
+    "SHELL",
+    "LANG",
+    "LC_ALL",
+    "LC_CTYPE",
+    "TERM",
+    "TMPDIR",
+    "TZ",
+    "CARGO_HOME",
+    "RUSTUP_HOME",
+    "RUSTUP_TOOLCHAIN",
+    "RUSTFLAGS",
+    "GOPATH",
+    "GOROOT",
+    "GOCACHE",
+    "GOMODCACHE",
+    "GOFLAGS",
+    "NODE_PATH",
+    "NVM_DIR",
+    "npm_config_cache",
+    "PYTHONPATH",
+    "VIRTUAL_ENV",
+    "CONDA_PREFIX",
+    "JAVA_HOME",
+    "CC",
+    "CXX",
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ResourceLimits {
+    pub cpu_seconds: Option<u64>,
+    pub memory_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CommandSpec {
+    pub program: String,
+    pub args: Vec<String>,
+    pub cwd: PathBuf,
+    pub timeout: Duration,
+    pub max_output_bytes: usize,
+    pub env_allowlist: Vec<String>,
+    pub env: Vec<(String, String)>,
+    pub isolate_network: bool,
+    pub limits: ResourceLimits,
+    pub policy: Option<Arc<PolicyEnforcer>>,
+}
+
+impl CommandSpec {
+    pub fn new(program: &str, args: &[&str], cwd: &Path) -> Self {
+        CommandSpec {
+            program: program.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+            cwd: cwd.to_path_buf(),
+            timeout: DEFAULT_COMMAND_TIMEOUT,
+            max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+            env_allowlist: DEFAULT_ENV_ALLOWLIST.iter().map(|v| v.to_string()).collect(),
+            env: Vec::new(),
+            isolate_network: false,
+            limits: ResourceLimits::default(),
+            policy: None,
+        }
+    }
+
+    /// Applies the `sandbox` and `policy` sections of `.codeagent.yml` for the project at `cwd`.
+    pub fn for_project(program: &str, args: &[&str], cwd: &Path) -> Self {
+        let config = crate::config::yaml_parser::YamlConfig::load(&cwd.join(".codeagent.yml")).unwrap_or_default();
+        let mut spec = Self::new(program, args, cwd).with_sandbox(&config.sandbox);
+        spec.policy = Some(PolicyEnforcer::for_project(cwd));
+        spec
+    }
+
+    pub fn with_sandbox(mut self, sandbox: &SandboxConfig) -> Self {
+        self.isolate_network = sandbox.network_isolation;
+        self.limits = ResourceLimits {
+            cpu_seconds: sandbox.cpu_seconds,
+            memory_bytes: sandbox.memory_mb.map(|mb| mb * 1024 * 1024),
+        };
+        if let Some(max) = sandbox.max_output_bytes {
+            self.max_output_bytes = max;
+        }
+        for name in &sandbox.env_allowlist {
+            if !self.env_allowlist.contains(name) {
+                self.env_allowlist.push(name.clone());
+            }
+        }
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_env(mut self, name: &str, value: &str) -> Self {
+        self.env.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn display(&self) -> String {
+        std::iter::once(self.program.as_str())
+            .chain(self.args.iter().map(|a| a.as_str()))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CommandOutput {
+    pub stdout: String,
+    pub stderr: String,
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub truncated: bool,
+    pub duration_ms: u64,
+}
+
+impl CommandOutput {
+    pub fn success(&self) -> bool {
+        self.exit_code == Some(0) && !self.timed_out
+    }
+
+    pub fn combined(&self) -> String {
+        let mut combined = self.stdout.clone();
+        if !self.stderr.is_empty() {
+            if !combined.is_empty() && !combined.ends_with('\n') {
+                combined.push('\n');
+            }
+            combined.push_str(&self.stderr);
+        }
+        combined
+    }
+}
+
+/// Output read from one pipe so far, shared so a capture cut short still yields it.
+#[derive(Default)]
+struct Captured {
+    bytes: Vec<u8>,
+    truncated: bool,
+}
+
+pub struct CommandRunner;
+
+impl CommandRunner {
+    pub async fn run(spec: &CommandSpec) -> Result<CommandOutput> {
+        if let Some(policy) = &spec.policy {
+            let argv: Vec<String> = std::iter::once(spec.program.clone()).chain(spec.args.clone()).collect();
+            policy.check_command(&argv)?;
+        }
+
+        let start = Instant::now();
+        let result = Self::execute(spec).await;
+        AuditLog::record(AuditEvent::Command {
+            argv: std::iter::once(spec.program.clone()).chain(spec.args.clone()).collect(),
+            cwd: spec.cwd.display().to_string(),
+            exit_code: result.as_ref().ok().and_then(|o| o.exit_code),
+            timed_out: result.as_ref().is_ok_and(|o| o.timed_out),
+            duration_ms: start.elapsed().as_millis() as u64,
+            error: result.as_ref().err().map(|e| e.to_string()),
+        });
+        result
+    }
+
+    async fn execute(spec: &CommandSpec) -> Result<CommandOutput> {
+        let mut command = Command::new(&spec.program);
+        command
+            .args(&spec.args)
+            .current_dir(&spec.cwd)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .env_clear();
+        for name in &spec.env_allowlist {
+            if let Some(value) = std::env::var_os(name) {
+                command.env(name, value);
+            }
+        }
+        for (name, value) in &spec.env {
+            command.env(name, value);
+        }
+        Self::confine(&mut command, spec);
+
+        tracing::debug!("Running {} in {}", spec.display(), spec.cwd.display());
+        let start = Instant::now();
+        let mut child = command
+            .spawn()
+            .map_err(|e| anyhow::anyhow!("Failed to run {}: {}", spec.program, e))?;
+        let pid = child.id();
+
+        let stdout = child.stdout.take().map(|r| Self::spawn_capture(r, spec.max_output_bytes));
+        let stderr = child.stderr.take().map(|r| Self::spawn_capture(r, spec.max_output_bytes));
+
+        let (status, timed_out) = match tokio::time::timeout(spec.timeout, child.wait()).await {
+            Ok(status) => {
+                // Background children left in the group would otherwise outlive the command.
+                Self::kill_group(pid);
+                (Some(status?), false)
+            }
+            Err(_) => {
+                tracing::warn!("{} timed out after {:?}", spec.display(), spec.timeout);
+                Self::kill_group(pid);
+                let _ = child.kill().await;
+                (None, true)
+            }
+        };
+
+        let mut output = CommandOutput {
+            exit_code: status.and_then(|s| s.code()),
+            timed_out,
+            ..CommandOutput::default()
+        };
+        for (capture, target) in [(stdout, &mut output.stdout), (stderr, &mut output.stderr)] {
+            let Some((handle, captured)) = capture else {
+                continue;
+            };
+            // Descendants that escaped the process group may keep the pipe open; keep what
+            // was read so far.
+            let abort = handle.abort_handle();
+            if tokio::time::timeout(Duration::from_secs(2), handle).await.is_err() {
+                abort.abort();
+            }
+            let captured = captured.lock().unwrap_or_else(|e| e.into_inner());
+            *target = Self::render(&captured, spec.max_output_bytes);
+            output.truncated |= captured.truncated;
+        }
+        output.duration_ms = start.elapsed().as_millis() as u64;
+        Ok(output)
+    }
+
+    /// Runs a command from synchronous code, whether or not a tokio runtime is active.
+    pub fn run_blocking(spec: &CommandSpec) -> Result<CommandOutput> {
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) if handle.runtime_flavor() == tokio::runtime::RuntimeFlavor::MultiThread => {
+                tokio::task::block_in_place(|| handle.block_on(Self::run(spec)))
+            }
+            _ => std::thread::scope(|scope| {
+                scope
+                    .spawn(|| {
+                        tokio::runtime::Builder::new_current_thread()
+                            .enable_all()
+                            .build()?
+                            .block_on(Self::run(spec))
+                    })
+                    .join()
+                    .map_err(|_| anyhow::anyhow!("Command runner thread panicked"))?
+            }),
+        }
+    }
+
+    fn spawn_capture<R: AsyncRead + Unpin + Send + 'static>(
+        reader: R,
+        max_bytes: usize,
+    ) -> (tokio::task::JoinHandle<()>, Arc<Mutex<Captured>>) {
+        let captured = Arc::new(Mutex::new(Captured::default()));
+        (tokio::spawn(Self::capture(reader, max_bytes, captured.clone())), captured)
+    }
+
+    async fn capture<R: AsyncRead + Unpin>(mut reader: R, max_bytes: usize, captured: Arc<Mutex<Captured>>) {
+        let mut chunk = [0u8; 8192];
+        while let Ok(n) = reader.read(&mut chunk).await {
+            if n == 0 {
+                break;
+            }
+            let mut captured = captured.lock().unwrap_or_else(|e| e.into_inner());
+            let room = max_bytes.saturating_sub(captured.bytes.len());
+            if n > room {
+                captured.truncated = true;
+            }
+            captured.bytes.extend_from_slice(&chunk[..n.min(room)]);
+        }
+    }
+
+    fn render(captured: &Captured, max_bytes: usize) -> String {
+        let mut text = String::from_utf8_lossy(&captured.bytes).into_owned();
+        if captured.truncated {
+            text.push_str(&format!("\n[output truncated at {} bytes]\n", max_bytes));
+        }
+        text
+    }
+
+    #[cfg(unix)]
+    fn confine(command: &mut Command, spec: &CommandSpec) {
+        let limits = spec.limits;
+        let isolate_network = spec.isolate_network;
+        command.process_group(0);
+        // SAFETY: the closure runs in the forked child before exec and only calls
+        // async-signal-safe libc functions without allocating.
+        unsafe {
+            command.pre_exec(move || {
+                if let Some(seconds) = limits.cpu_seconds {
+                    Self::set_limit(libc::RLIMIT_CPU as libc::c_int, seconds)?;
+                }
+                if let Some(bytes) = limits.memory_bytes {
+                    Self::set_limit(libc::RLIMIT_AS as libc::c_int, bytes)?;
+                }
+                if isolate_network {
+                    Self::unshare_network()?;
+                }
+                Ok(())
+            });
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn confine(_command: &mut Command, spec: &CommandSpec) {
+        if spec.isolate_network || spec.limits != ResourceLimits::default() {
+            tracing::warn!("Sandbox limits are only supported on Unix; running {} unconfined", spec.program);
+        }
+    }
+
+    #[cfg(unix)]
+    fn set_limit(resource: libc::c_int, value: u64) -> std::io::Result<()> {
+        let limit = libc::rlimit {
+            rlim_cur: value as libc::rlim_t,
+            rlim_max: value as libc::rlim_t,
+        };
+        // glibc types the resource as an unsigned enum; everywhere else it is a plain int.
+        if unsafe { libc::setrlimit(resource as _, &limit) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    fn unshare_network() -> std::io::Result<()> {
+        // Unprivileged users need a user namespace to own the new network namespace.
+        let flags = if unsafe { libc::geteuid() } == 0 {
+            libc::CLONE_NEWNET
+        } else {
+            libc::CLONE_NEWUSER | libc::CLONE_NEWNET
+        };
+        if unsafe { libc::unshare(flags) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    #[cfg(all(unix, not(target_os = "linux")))]
+    fn unshare_network() -> std::io::Result<()> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "network isolation requires Linux namespaces",
+        ))
+    }
+
+    fn kill_group(pid: Option<u32>) {
+        #[cfg(unix)]
+        if let Some(pid) = pid {
+            unsafe {
+                libc::kill(-(pid as libc::pid_t), libc::SIGKILL);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shell(script: &str) -> CommandSpec {
+        CommandSpec::new("sh", &["-c", script], &std::env::temp_dir())
+    }
+
+    #[tokio::test]
+    async fn test_timeout_kills_process_group_and_caps_output() {
+        let spec = shell("sleep 30 & echo started; wait").with_timeout(Duration::from_millis(300));
+        let output = CommandRunner::run(&spec).await.unwrap();
+        assert!(output.timed_out);
+        assert!(output.stdout.contains("started"));
+        assert!(output.duration_ms < 5000);
+
+        let output = CommandRunner::run(&shell("echo left; sleep 30 &")).await.unwrap();
+        assert!(output.success());
+        assert!(output.duration_ms < 2000);
+
+        // Escapes the process group, so only the capture timeout ends the read.
+        let output = CommandRunner::run(&shell("echo partial; setsid sleep 5 &")).await.unwrap();
+        assert_eq!(output.stdout, "partial\n");
+
+        let mut spec = shell("yes | head -c 100000");
+        spec.max_output_bytes = 1000;
+        let output = CommandRunner::run(&spec).await.unwrap();
+        assert!(output.success());
+        assert!(output.truncated);
+        assert!(output.stdout.starts_with("y\ny\n"));
+    }
+
+    #[test]
+    fn test_environment_is_allowlisted_and_limits_apply() {
+        std::env::set_var("CODEAGENT_SANDBOX_SECRET", "hunter2");
+        let spec = shell("echo \"[$CODEAGENT_SANDBOX_SECRET][$EXTRA]\"; ulimit -t").with_env("EXTRA", "ok");
+        let mut spec = spec;
+        spec.limits.cpu_seconds = Some(7);
+        let output = CommandRunner::run_blocking(&spec).unwrap();
+        assert_eq!(output.stdout, "[][ok]\n7\n");
+    }
+}
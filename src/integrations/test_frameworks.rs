@@ -0,0 +1,356 @@
+use crate::config::yaml_parser::{TestSuiteConfig, YamlConfig};
+use crate::integrations::custom_tools::OutputParser;
+use crate::parsers::TestResult;
+use crate::utils::walker::ProjectWalker;
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+const MAX_MARKER_DEPTH: usize = 4;
+
+const MARKER_FILES: &[&str] = &[
+    "Cargo.toml",
+    "package.json",
+    "go.mod",
+    "pytest.ini",
+    "conftest.py",
+    "pyproject.toml",
+    "setup.cfg",
+    "tox.ini",
+    "Makefile",
+];
+
+// What `npm init` puts in package.json when there are no tests.
+const NPM_PLACEHOLDER_TEST: &str = "no test specified";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestFramework {
+    Cargo,
+    Jest,
+    Vitest,
+    Mocha,
+    NpmScript,
+    Pytest,
+    GoTest,
+    Make,
+}
+
+impl TestFramework {
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name.to_lowercase().as_str() {
+            "cargo" | "rust" => Ok(TestFramework::Cargo),
+            "jest" => Ok(TestFramework::Jest),
+            "vitest" => Ok(TestFramework::Vitest),
+            "mocha" => Ok(TestFramework::Mocha),
+            "npm" | "node" => Ok(TestFramework::NpmScript),
+            "pytest" | "python" => Ok(TestFramework::Pytest),
+            "go" | "go-test" | "gotest" => Ok(TestFramework::GoTest),
+            "make" => Ok(TestFramework::Make),
+            other => Err(anyhow::anyhow!(
+                "Unknown test framework '{}' (expected cargo, jest, vitest, mocha, npm, pytest, go or make)",
+                other
+            )),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TestFramework::Cargo => "cargo",
+            TestFramework::Jest => "jest",
+            TestFramework::Vitest => "vitest",
+            TestFramework::Mocha => "mocha",
+            TestFramework::NpmScript => "npm",
+            TestFramework::Pytest => "pytest",
+            TestFramework::GoTest => "go",
+            TestFramework::Make => "make",
+        }
+    }
+
+    pub fn is_node(&self) -> bool {
+        matches!(
+            self,
+            TestFramework::Jest | TestFramework::Vitest | TestFramework::Mocha | TestFramework::NpmScript
+        )
+    }
+
+    /// The command used when neither config nor package.json says otherwise; verbose
+    /// where the parser needs per-test lines.
+    pub fn default_command(&self) -> Vec<String> {
+        let command: &[&str] = match self {
+            TestFramework::Cargo => &["cargo", "test"],
+            TestFramework::Jest | TestFramework::Vitest | TestFramework::Mocha | TestFramework::NpmScript => {
+                &["npm", "test"]
+            }
+            TestFramework::Pytest => &["pytest", "-v"],
+            TestFramework::GoTest => &["go", "test", "-v", "./..."],
+            TestFramework::Make => &["make", "test"],
+        };
+        command.iter().map(|s| s.to_string()).collect()
+    }
+
+    /// Flags that make the runner print a JSON report the parser prefers over its text output.
+    pub fn report_args(&self) -> &'static [&'static str] {
+        match self {
+            TestFramework::Jest => &["--json"],
+            TestFramework::Vitest => &["--reporter=json"],
+            _ => &[],
+        }
+    }
+
+    pub fn output_parser(&self) -> Option<OutputParser> {
+        match self {
+            TestFramework::Cargo => Some(OutputParser::CargoTest),
+            TestFramework::Jest | TestFramework::Vitest | TestFramework::NpmScript => Some(OutputParser::Jest),
+            TestFramework::Pytest => Some(OutputParser::Pytest),
+            TestFramework::GoTest => Some(OutputParser::GoTest),
+            TestFramework::Mocha | TestFramework::Make => None,
+        }
+    }
+
+    pub fn parse_output(&self, output: &str) -> Vec<TestResult> {
+        self.output_parser().map(|p| p.parse_tests(output)).unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestSuite {
+    pub framework: TestFramework,
+    /// Project-relative directory the command runs in; empty for the root.
+    pub dir: PathBuf,
+    pub command: Vec<String>,
+    /// Suite-relative JUnit XML report to read results from instead of the output.
+    pub junit: Option<PathBuf>,
+}
+
+impl TestSuite {
+    pub fn new(framework: TestFramework, dir: &Path) -> Self {
+        TestSuite {
+            framework,
+            dir: dir.to_path_buf(),
+            command: framework.default_command(),
+            junit: None,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        if self.dir.as_os_str().is_empty() {
+            self.framework.name().to_string()
+        } else {
+            format!("{} in {}", self.framework.name(), self.dir.display())
+        }
+    }
+
+    /// The command with the framework's report flags, unless it already picks a reporter.
+    pub fn report_command(&self) -> Vec<String> {
+        let chooses_reporter = self.command.iter().any(|a| a.starts_with("--json") || a.starts_with("--reporter"));
+        if chooses_reporter {
+            return self.command.clone();
+        }
+        let args = self.framework.report_args().iter().map(|s| s.to_string()).collect();
+        Self::append_args(self.framework, self.command.clone(), args)
+    }
+
+    /// Adds arguments for the test runner itself. npm only forwards them to the script
+    /// after `--`; yarn and pnpm pass them through.
+    pub fn append_args(framework: TestFramework, mut command: Vec<String>, args: Vec<String>) -> Vec<String> {
+        let npm = command.first().is_some_and(|p| p == "npm");
+        if !args.is_empty() && framework.is_node() && npm && !command.iter().any(|a| a == "--") {
+            command.push("--".to_string());
+        }
+        command.extend(args);
+        command
+    }
+
+    fn covers(&self, dir: &Path) -> bool {
+        dir.starts_with(&self.dir)
+    }
+}
+
+pub struct TestFrameworkDetector;
+
+impl TestFrameworkDetector {
+    /// Suites from the `test_suites` config section if there is one, otherwise detected
+    /// from project markers, nearest the root first.
+    pub fn suites(project_root: &Path) -> Result<Vec<TestSuite>> {
+        let config = YamlConfig::load(&project_root.join(".codeagent.yml")).unwrap_or_default();
+        if !config.test_suites.is_empty() {
+            return config.test_suites.iter().map(Self::from_config).collect();
+        }
+        Self::detect(project_root)
+    }
+
+    pub fn from_config(config: &TestSuiteConfig) -> Result<TestSuite> {
+        let framework = TestFramework::from_name(&config.framework)?;
+        let dir = config.path.as_deref().unwrap_or("").trim_start_matches("./").trim_matches('/');
+        let mut suite = TestSuite::new(framework, Path::new(if dir == "." { "" } else { dir }));
+        if let Some(command) = &config.command {
+            suite.command = command.split_whitespace().map(|s| s.to_string()).collect();
+            if suite.command.is_empty() {
+                return Err(anyhow::anyhow!("Empty command for {} test suite", framework.name()));
+            }
+        }
+        suite.junit = config.junit.as_ref().map(PathBuf::from);
+        Ok(suite)
+    }
+
+    pub fn detect(project_root: &Path) -> Result<Vec<TestSuite>> {
+        let mut markers: BTreeMap<PathBuf, Vec<String>> = BTreeMap::new();
+        for path in ProjectWalker::for_project(project_root).walk()? {
+            let Ok(relative) = path.strip_prefix(project_root) else {
+                continue;
+            };
+            let Some(name) = relative.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if relative.components().count() > MAX_MARKER_DEPTH
+                || !MARKER_FILES.contains(&name)
+                || relative.components().any(|c| c.as_os_str() == "node_modules")
+            {
+                continue;
+            }
+            let dir = relative.parent().unwrap_or(Path::new("")).to_path_buf();
+            markers.entry(dir).or_default().push(name.to_string());
+        }
+
+        let mut suites: Vec<TestSuite> = Vec::new();
+        let mut node_workspaces: Vec<PathBuf> = Vec::new();
+        for (dir, names) in &markers {
+            let read = |name: &str| std::fs::read_to_string(project_root.join(dir).join(name)).unwrap_or_default();
+            let has = |name: &str| names.iter().any(|n| n == name);
+            let covered = |suites: &[TestSuite], framework: TestFramework| {
+                suites.iter().any(|s| s.framework == framework && s.covers(dir))
+            };
+            let before = suites.len();
+
+            // Workspace members are built and tested from the enclosing crate.
+            if has("Cargo.toml") && !covered(&suites, TestFramework::Cargo) {
+                suites.push(TestSuite::new(TestFramework::Cargo, dir));
+            }
+            if has("go.mod") {
+                suites.push(TestSuite::new(TestFramework::GoTest, dir));
+            }
+            if has("package.json") && !node_workspaces.iter().any(|w| dir.starts_with(w)) {
+                if let Some((suite, workspaces)) = Self::node_suite(project_root, dir, &read("package.json")) {
+                    if workspaces {
+                        node_workspaces.push(dir.clone());
+                    }
+                    suites.push(suite);
+                }
+            }
+            let pytest_configured = has("pytest.ini")
+                || has("conftest.py")
+                || read("pyproject.toml").contains("[tool.pytest")
+                || read("setup.cfg").contains("[tool:pytest]")
+                || read("tox.ini").contains("[pytest]");
+            if pytest_configured && !covered(&suites, TestFramework::Pytest) {
+                suites.push(TestSuite::new(TestFramework::Pytest, dir));
+            }
+            // A Makefile usually wraps one of the above, so only use it on its own.
+            if has("Makefile") && suites.len() == before && Self::has_make_target(&read("Makefile"), "test") {
+                suites.push(TestSuite::new(TestFramework::Make, dir));
+            }
+        }
+
+        Ok(suites)
+    }
+
+    fn node_suite(project_root: &Path, dir: &Path, package_json: &str) -> Option<(TestSuite, bool)> {
+        let package: serde_json::Value = serde_json::from_str(package_json).ok()?;
+        let script = package.pointer("/scripts/test")?.as_str()?;
+        if script.contains(NPM_PLACEHOLDER_TEST) {
+            return None;
+        }
+
+        let depends_on = |name: &str| {
+            ["dependencies", "devDependencies"]
+                .iter()
+                .any(|key| package.get(key).and_then(|deps| deps.get(name)).is_some())
+        };
+        let framework = if script.contains("vitest") || depends_on("vitest") {
+            TestFramework::Vitest
+        } else if script.contains("jest") || depends_on("jest") {
+            TestFramework::Jest
+        } else if script.contains("mocha") || depends_on("mocha") {
+            TestFramework::Mocha
+        } else {
+            TestFramework::NpmScript
+        };
+
+        let mut suite = TestSuite::new(framework, dir);
+        let lockfile = |name: &str| {
+            dir.ancestors()
+                .any(|d| project_root.join(d).join(name).exists())
+        };
+        if lockfile("pnpm-lock.yaml") {
+            suite.command = vec!["pnpm".to_string(), "test".to_string()];
+        } else if lockfile("yarn.lock") {
+            suite.command = vec!["yarn".to_string(), "test".to_string()];
+        }
+        Some((suite, package.get("workspaces").is_some()))
+    }
+
+    fn has_make_target(makefile: &str, target: &str) -> bool {
+        makefile.lines().any(|line| {
+            line.split_once(':').is_some_and(|(targets, rest)| {
+                !rest.starts_with('=') && targets.split_whitespace().any(|t| t == target)
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_frameworks_in_a_monorepo() {
+        let root = std::env::temp_dir().join(format!("codeagent-frameworks-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let files = [
+            ("Cargo.toml", "[workspace]\nmembers = [\"crates/core\"]\n"),
+            ("crates/core/Cargo.toml", "[package]\nname = \"core\"\n"),
+            ("web/package.json", r#"{"scripts": {"test": "vitest run"}, "workspaces": ["packages/*"]}"#),
+            ("web/packages/ui/package.json", r#"{"scripts": {"test": "jest"}}"#),
+            ("web/yarn.lock", ""),
+            ("tools/package.json", r#"{"scripts": {"test": "echo \"Error: no test specified\" && exit 1"}}"#),
+            ("backend/pyproject.toml", "[tool.pytest.ini_options]\ntestpaths = [\"tests\"]\n"),
+            ("backend/tests/conftest.py", ""),
+            ("svc/go.mod", "module example.com/svc\n"),
+            ("svc/Makefile", "test:\n\tgo test ./...\n"),
+            ("legacy/Makefile", "CC := gcc\ntest: build\n\t./run-tests\n"),
+        ];
+        for (path, content) in files {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+
+        let suites = TestFrameworkDetector::suites(&root).unwrap();
+        let found: Vec<(TestFramework, &str, String)> = suites
+            .iter()
+            .map(|s| (s.framework, s.dir.to_str().unwrap(), s.command.join(" ")))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (TestFramework::Cargo, "", "cargo test".to_string()),
+                (TestFramework::Pytest, "backend", "pytest -v".to_string()),
+                (TestFramework::Make, "legacy", "make test".to_string()),
+                (TestFramework::GoTest, "svc", "go test -v ./...".to_string()),
+                (TestFramework::Vitest, "web", "yarn test".to_string()),
+            ]
+        );
+
+        std::fs::write(
+            root.join(".codeagent.yml"),
+            "model_provider: ollama\ntest_suites:\n  - framework: pytest\n    path: ./backend\n    command: python -m pytest -q\n",
+        )
+        .unwrap();
+        let suites = TestFrameworkDetector::suites(&root).unwrap();
+        assert_eq!(suites.len(), 1);
+        assert_eq!(suites[0].dir, PathBuf::from("backend"));
+        assert_eq!(suites[0].command, vec!["python", "-m", "pytest", "-q"]);
+        assert_eq!(suites[0].framework.output_parser(), Some(OutputParser::Pytest));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
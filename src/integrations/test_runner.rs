@@ -1,66 +1,488 @@
+use crate::index::ImportGraph;
+use crate::integrations::sandbox::{CommandRunner, CommandSpec};
+use crate::integrations::test_frameworks::{TestFramework, TestFrameworkDetector, TestSuite};
+use crate::parsers::junit::{JunitParser, JunitWriter};
+use crate::parsers::test_parser::{TestParser, TestSummary};
+use crate::parsers::{TestResult, TestStatus};
 use anyhow::Result;
-use std::path::Path;
-use std::process::Command;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime};
+
+const JS_EXTENSIONS: &[&str] = &["js", "jsx", "ts", "tsx", "mjs", "cjs", "mts", "cts"];
 
 pub struct TestRunner;
 
 #[derive(Debug, Clone)]
-pub struct TestResult {
-    pub passed: bool,
-    pub output: String,
-    pub exit_code: i32,
+pub struct SuiteReport {
+    pub suite: TestSuite,
+    pub stdout: String,
+    pub stderr: String,
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub duration_ms: u64,
+    pub tests: Vec<TestResult>,
 }
 
-impl TestRunner {
-    pub async fn run_all_tests(project_root: &Path) -> Result<TestResult> {
-        tracing::info!("Running all tests");
-
-        let test_frameworks = vec!["cargo test", "npm test", "pytest", "go test"];
-
-        for framework_cmd in test_frameworks {
-            let result = Self::run_command(framework_cmd, project_root);
-            
-            match result {
-                Ok(output) => {
-                    return Ok(TestResult {
-                        passed: output.status.success(),
-                        output: String::from_utf8_lossy(&output.stdout).to_string(),
-                        exit_code: output.status.code().unwrap_or(-1),
-                    });
+impl SuiteReport {
+    pub fn passed(&self) -> bool {
+        self.exit_code == Some(0) && !self.timed_out && !self.tests.iter().any(|t| t.status == TestStatus::Failed)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TestReport {
+    pub suites: Vec<SuiteReport>,
+    pub duration_ms: u64,
+}
+
+impl TestReport {
+    pub fn passed(&self) -> bool {
+        self.suites.iter().all(|s| s.passed())
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match self.suites.iter().find(|s| !s.passed()) {
+            Some(suite) => suite.exit_code.filter(|c| *c != 0).unwrap_or(-1),
+            None => 0,
+        }
+    }
+
+    pub fn tests(&self) -> impl Iterator<Item = &TestResult> {
+        self.suites.iter().flat_map(|s| s.tests.iter())
+    }
+
+    pub fn failures(&self) -> Vec<&TestResult> {
+        self.tests().filter(|t| t.status == TestStatus::Failed).collect()
+    }
+
+    pub fn junit(&self) -> String {
+        JunitWriter::write("codeagent", &self.tests().cloned().collect::<Vec<_>>())
+    }
+
+    pub fn summary(&self) -> TestSummary {
+        TestParser::summarize_results(&self.tests().cloned().collect::<Vec<_>>())
+    }
+
+    /// Everything the suites printed, stdout then stderr, under a header per suite.
+    pub fn output(&self) -> String {
+        if self.suites.is_empty() {
+            return "No test suites ran\n".to_string();
+        }
+        let mut output = String::new();
+        for suite in &self.suites {
+            let summary = TestParser::summarize_results(&suite.tests);
+            let status = if suite.timed_out {
+                "timed out".to_string()
+            } else {
+                match suite.exit_code {
+                    Some(code) => format!("exit {}", code),
+                    None => "killed".to_string(),
+                }
+            };
+            output.push_str(&format!(
+                "=== {} ({}): {} passed, {} failed, {} skipped; {} in {}ms ===\n",
+                suite.suite.label(),
+                suite.suite.command.join(" "),
+                summary.passed,
+                summary.failed,
+                summary.skipped,
+                status,
+                suite.duration_ms
+            ));
+            for stream in [&suite.stdout, &suite.stderr] {
+                if !stream.trim().is_empty() {
+                    output.push_str(stream.trim_end());
+                    output.push('\n');
                 }
-                Err(_) => continue,
             }
         }
+        output
+    }
+}
 
-        Ok(TestResult {
-            passed: true,
-            output: "No test framework detected".to_string(),
-            exit_code: 0,
-        })
+enum RustTestFilter {
+    Target(String),
+    Module(String),
+    All,
+}
+
+impl TestRunner {
+    pub async fn run_all_tests(project_root: &Path) -> Result<TestReport> {
+        let suites = TestFrameworkDetector::suites(project_root)?;
+        tracing::info!("Running {} test suites", suites.len());
+        Self::run_suites(project_root, &suites).await
     }
 
-    pub async fn run_specific_test(project_root: &Path, test_name: &str) -> Result<TestResult> {
+    /// Runs one test, given by name or by file (`tests/api.rs`, `tests/test_x.py::test_y`),
+    /// with the filter syntax of each framework that could own it.
+    pub async fn run_specific_test(project_root: &Path, test_name: &str) -> Result<TestReport> {
         tracing::info!("Running specific test: {}", test_name);
+        let suites = TestFrameworkDetector::suites(project_root)?;
+        if suites.is_empty() {
+            return Err(anyhow::anyhow!("No test framework detected to run {}", test_name));
+        }
 
-        let output = Command::new("cargo")
-            .args(&["test", test_name])
-            .current_dir(project_root)
-            .output()?;
+        let file = test_name.split("::").next().unwrap_or(test_name);
+        let extension = Path::new(file).extension().and_then(|e| e.to_str());
+        let targeted: Vec<TestSuite> = match extension.filter(|_| file.contains('/') || file.contains('.')) {
+            Some(_) => {
+                let owner = Self::owning_suite(&suites, file)
+                    .map(|idx| &suites[idx])
+                    .ok_or_else(|| anyhow::anyhow!("No test suite covers {}", file))?;
+                let relative = Path::new(test_name).strip_prefix(&owner.dir).unwrap_or(Path::new(test_name));
+                vec![Self::filtered_suite(owner, &relative.to_string_lossy(), true)]
+            }
+            None => suites.iter().map(|s| Self::filtered_suite(s, test_name, false)).collect(),
+        };
+
+        let mut report = Self::run_suites(project_root, &targeted).await?;
+        // A name filter runs in every suite; keep the ones that actually ran the test.
+        if report.suites.len() > 1 && report.suites.iter().any(|s| !s.tests.is_empty()) {
+            report.suites.retain(|s| !s.tests.is_empty());
+        }
+        Ok(report)
+    }
+
+    pub fn affected_tests(project_root: &Path, changed_files: &[String]) -> Result<Vec<String>> {
+        let graph = ImportGraph::build(project_root)?;
+        let mut candidates: Vec<String> = changed_files.to_vec();
+        candidates.extend(graph.transitive_dependents(changed_files));
 
-        Ok(TestResult {
-            passed: output.status.success(),
-            output: String::from_utf8_lossy(&output.stdout).to_string(),
-            exit_code: output.status.code().unwrap_or(-1),
+        Ok(candidates
+            .into_iter()
+            .filter(|file| Self::is_test_file(project_root, file))
+            .collect())
+    }
+
+    /// Runs the tests that exercise `changed_files`, each through the detected suite
+    /// that owns it, so config overrides and monorepo suite directories apply.
+    pub async fn run_affected_tests(project_root: &Path, changed_files: &[String]) -> Result<TestReport> {
+        let tests = Self::affected_tests(project_root, changed_files)?;
+        tracing::info!("Running {} affected test files", tests.len());
+        if tests.is_empty() {
+            return Ok(TestReport::default());
+        }
+
+        let suites = TestFrameworkDetector::suites(project_root)?;
+        let mut owned: Vec<Vec<String>> = vec![Vec::new(); suites.len()];
+        for test in &tests {
+            match Self::owning_suite(&suites, test) {
+                Some(idx) => {
+                    let relative = Path::new(test).strip_prefix(&suites[idx].dir).unwrap_or(Path::new(test));
+                    owned[idx].push(relative.to_string_lossy().into_owned());
+                }
+                None => tracing::warn!("No test suite covers {}; skipping it", test),
+            }
+        }
+
+        let targeted: Vec<TestSuite> = suites
+            .iter()
+            .zip(&owned)
+            .filter(|(_, files)| !files.is_empty())
+            .flat_map(|(suite, files)| Self::file_filtered_suites(suite, files))
+            .collect();
+        Self::run_suites(project_root, &targeted).await
+    }
+
+    pub async fn run_suites(project_root: &Path, suites: &[TestSuite]) -> Result<TestReport> {
+        let start = Instant::now();
+        let mut report = TestReport::default();
+        for suite in suites {
+            report.suites.push(Self::run_suite(project_root, suite).await?);
+        }
+        report.duration_ms = start.elapsed().as_millis() as u64;
+        Ok(report)
+    }
+
+    pub async fn run_suite(project_root: &Path, suite: &TestSuite) -> Result<SuiteReport> {
+        let reported = Self::with_report_file(project_root, suite);
+        let command = reported.report_command();
+        let (program, args) = command
+            .split_first()
+            .ok_or_else(|| anyhow::anyhow!("Empty command for {} tests", suite.label()))?;
+        let mut spec = CommandSpec::for_project(program, &[], project_root);
+        spec.args = args.to_vec();
+        spec.cwd = project_root.join(&suite.dir);
+        let started = SystemTime::now();
+        let output = CommandRunner::run(&spec).await?;
+
+        let junit = reported.junit.as_ref().and_then(|report| Self::read_junit(&spec.cwd.join(report), suite, started));
+        let mut tests = junit.unwrap_or_else(|| suite.framework.parse_output(&output.combined()));
+        // Jest and Vitest report absolute paths; other parsers give suite-relative ones.
+        let root = project_root.canonicalize().unwrap_or_else(|_| project_root.to_path_buf());
+        for test in tests.iter_mut().filter(|t| !t.file.is_empty()) {
+            let file = Path::new(&test.file);
+            let relative = match file.strip_prefix(&root) {
+                Ok(relative) => relative.to_path_buf(),
+                Err(_) if file.is_absolute() => continue,
+                Err(_) => suite.dir.join(file),
+            };
+            test.file = relative.to_string_lossy().into_owned();
+        }
+        Ok(SuiteReport {
+            suite: suite.clone(),
+            stdout: output.stdout,
+            stderr: output.stderr,
+            exit_code: output.exit_code,
+            timed_out: output.timed_out,
+            duration_ms: output.duration_ms,
+            tests,
         })
     }
 
-    fn run_command(cmd: &str, project_root: &Path) -> Result<std::process::Output> {
-        let parts: Vec<&str> = cmd.split_whitespace().collect();
-        let output = Command::new(parts[0])
-            .args(&parts[1..])
-            .current_dir(project_root)
-            .output()?;
+    /// pytest's JUnit report has per-test durations and tracebacks without any plugin, so
+    /// ask for one under `.codeagent/reports` unless the suite already writes its own.
+    fn with_report_file(project_root: &Path, suite: &TestSuite) -> TestSuite {
+        let writes_junit = suite.junit.is_some() || suite.command.iter().any(|a| a.starts_with("--junitxml"));
+        if suite.framework != TestFramework::Pytest || writes_junit {
+            return suite.clone();
+        }
+        let name = suite.label().replace([' ', '/', '\\'], "-");
+        let root = project_root.canonicalize().unwrap_or_else(|_| project_root.to_path_buf());
+        let report = root.join(".codeagent").join("reports").join(format!("{}.xml", name));
+        let mut command = suite.command.clone();
+        command.push(format!("--junitxml={}", report.display()));
+        TestSuite {
+            command,
+            junit: Some(report),
+            ..suite.clone()
+        }
+    }
+
+    /// Results from the suite's JUnit report, if this run wrote one.
+    fn read_junit(path: &Path, suite: &TestSuite, started: SystemTime) -> Option<Vec<TestResult>> {
+        let fresh = std::fs::metadata(path).and_then(|m| m.modified()).is_ok_and(|modified| modified >= started);
+        if !fresh {
+            tracing::warn!("{} did not write {}; parsing its output instead", suite.label(), path.display());
+            return None;
+        }
+        let parsed = std::fs::read_to_string(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|xml| JunitParser::parse(&xml, suite.framework.name()));
+        match parsed {
+            Ok(tests) => Some(tests),
+            Err(e) => {
+                tracing::warn!("Could not read {}: {}; parsing the output instead", path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// The innermost suite whose directory contains `file` and whose runner handles it.
+    fn owning_suite(suites: &[TestSuite], file: &str) -> Option<usize> {
+        let extension = Path::new(file).extension().and_then(|e| e.to_str()).unwrap_or_default();
+        suites
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| Path::new(file).starts_with(&s.dir) && Self::handles_extension(s.framework, extension))
+            .max_by_key(|(_, s)| s.dir.components().count())
+            .map(|(idx, _)| idx)
+    }
+
+    fn handles_extension(framework: TestFramework, extension: &str) -> bool {
+        match framework {
+            TestFramework::Cargo => extension == "rs",
+            TestFramework::Pytest => extension == "py",
+            TestFramework::GoTest => extension == "go",
+            TestFramework::Make => true,
+            _ => JS_EXTENSIONS.contains(&extension),
+        }
+    }
+
+    /// `test` is a suite-relative file when `is_file`, otherwise a test name.
+    fn filtered_suite(suite: &TestSuite, test: &str, is_file: bool) -> TestSuite {
+        let mut command = suite.command.clone();
+        let args: Vec<String> = match suite.framework {
+            TestFramework::Cargo if is_file => match Self::rust_test_filter(test) {
+                RustTestFilter::Target(name) => vec!["--test".to_string(), name],
+                RustTestFilter::Module(module) => vec![module],
+                RustTestFilter::All => Vec::new(),
+            },
+            TestFramework::Cargo => vec![test.to_string()],
+            TestFramework::Jest | TestFramework::Vitest if !is_file => vec!["-t".to_string(), test.to_string()],
+            TestFramework::Mocha if !is_file => vec!["--grep".to_string(), test.to_string()],
+            TestFramework::Pytest if !is_file => vec!["-k".to_string(), test.to_string()],
+            TestFramework::GoTest if is_file => {
+                let dir = Path::new(test).parent().unwrap_or(Path::new(""));
+                let package = PathBuf::from(".").join(dir).to_string_lossy().into_owned();
+                command.retain(|arg| arg != "./...");
+                vec![package]
+            }
+            TestFramework::GoTest => vec!["-run".to_string(), format!("^{}$", test)],
+            TestFramework::Make => {
+                tracing::warn!("make test cannot run a single test; running the whole suite for {}", test);
+                Vec::new()
+            }
+            _ => vec![test.to_string()],
+        };
+        TestSuite {
+            command: TestSuite::append_args(suite.framework, command, args),
+            ..suite.clone()
+        }
+    }
+
+    /// Runs for several suite-relative test files: one command where the runner takes
+    /// files as arguments, one per Rust test target.
+    fn file_filtered_suites(suite: &TestSuite, files: &[String]) -> Vec<TestSuite> {
+        match suite.framework {
+            TestFramework::Cargo => {
+                let mut targets = Vec::new();
+                let mut modules = Vec::new();
+                for file in files {
+                    match Self::rust_test_filter(file) {
+                        RustTestFilter::Target(name) => targets.push(name),
+                        RustTestFilter::Module(module) => modules.push(module),
+                        RustTestFilter::All => return vec![suite.clone()],
+                    }
+                }
+                let mut runs: Vec<TestSuite> = targets
+                    .into_iter()
+                    .map(|name| TestSuite {
+                        command: TestSuite::append_args(suite.framework, suite.command.clone(), vec!["--test".to_string(), name]),
+                        ..suite.clone()
+                    })
+                    .collect();
+                if !modules.is_empty() {
+                    // libtest takes any number of filters, but cargo only one before `--`.
+                    let mut args = Vec::new();
+                    if modules.len() > 1 && !suite.command.iter().any(|a| a == "--") {
+                        args.push("--".to_string());
+                    }
+                    args.extend(modules);
+                    runs.push(TestSuite {
+                        command: TestSuite::append_args(suite.framework, suite.command.clone(), args),
+                        ..suite.clone()
+                    });
+                }
+                runs
+            }
+            TestFramework::GoTest => {
+                let mut command = suite.command.clone();
+                command.retain(|arg| arg != "./...");
+                let mut packages: Vec<String> = Vec::new();
+                for file in files {
+                    let dir = Path::new(file).parent().unwrap_or(Path::new(""));
+                    let package = PathBuf::from(".").join(dir).to_string_lossy().into_owned();
+                    if !packages.contains(&package) {
+                        packages.push(package);
+                    }
+                }
+                vec![TestSuite {
+                    command: TestSuite::append_args(suite.framework, command, packages),
+                    ..suite.clone()
+                }]
+            }
+            TestFramework::Make => vec![suite.clone()],
+            _ => vec![TestSuite {
+                command: TestSuite::append_args(suite.framework, suite.command.clone(), files.to_vec()),
+                ..suite.clone()
+            }],
+        }
+    }
+
+    fn is_test_file(project_root: &Path, file: &str) -> bool {
+        let name = file.rsplit('/').next().unwrap_or(file);
+        if file.starts_with("tests/") || file.contains("/tests/") || file.contains("__tests__/") {
+            return true;
+        }
+        if name.starts_with("test_") && name.ends_with(".py")
+            || name.ends_with("_test.py")
+            || name.ends_with("_test.go")
+            || name.contains(".test.")
+            || name.contains(".spec.")
+        {
+            return true;
+        }
+        file.ends_with(".rs")
+            && std::fs::read_to_string(project_root.join(file))
+                .is_ok_and(|content| content.contains("#[test]") || content.contains("#[tokio::test]"))
+    }
+
+    fn rust_test_filter(file: &str) -> RustTestFilter {
+        if let Some(name) = file
+            .strip_prefix("tests/")
+            .or_else(|| file.rsplit_once("/tests/").map(|(_, rest)| rest))
+        {
+            return RustTestFilter::Target(name.trim_end_matches(".rs").to_string());
+        }
+
+        let module = file.rsplit_once("src/").map(|(_, rest)| rest).unwrap_or(file);
+        let module = module
+            .trim_end_matches(".rs")
+            .trim_end_matches("/mod")
+            .replace('/', "::");
+        if module == "main" || module == "lib" {
+            RustTestFilter::All
+        } else {
+            RustTestFilter::Module(module)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_reports_per_test_results_and_filters_by_framework() {
+        let root = std::env::temp_dir().join(format!("codeagent-test-runner-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(
+            root.join("run.sh"),
+            "echo \"args: $*\"\necho 'test math::adds ... ok'\necho 'test math::subs ... FAILED'\necho 'warning: slow' >&2\nexit 101\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join(".codeagent.yml"),
+            "model_provider: ollama\ntest_suites:\n  - framework: cargo\n    command: sh run.sh\n",
+        )
+        .unwrap();
+
+        let report = TestRunner::run_all_tests(&root).await.unwrap();
+        assert!(!report.passed());
+        assert_eq!(report.exit_code(), 101);
+        assert_eq!(report.summary().passed, 1);
+        assert_eq!(report.failures()[0].name, "math::subs");
+        assert!(report.suites[0].stderr.contains("warning: slow"));
+        assert!(report.output().contains("1 passed, 1 failed"));
+
+        let by_name = TestRunner::run_specific_test(&root, "math::subs").await.unwrap();
+        assert!(by_name.suites[0].stdout.starts_with("args: math::subs\n"));
+        let by_file = TestRunner::run_specific_test(&root, "tests/api.rs").await.unwrap();
+        assert!(by_file.suites[0].stdout.starts_with("args: --test api\n"));
+        assert!(TestRunner::run_specific_test(&root, "web/app.test.ts").await.is_err());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn test_affected_tests_run_through_their_owning_suites() {
+        let root = std::env::temp_dir().join(format!("codeagent-affected-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join("web/src")).unwrap();
+        let script = "echo \"args: $*\"\n";
+        std::fs::write(root.join("run.sh"), script).unwrap();
+        std::fs::write(root.join("web/run.sh"), script).unwrap();
+        std::fs::write(root.join("src/math.rs"), "#[test]\nfn adds() {}\n").unwrap();
+        std::fs::write(
+            root.join(".codeagent.yml"),
+            "model_provider: ollama\ntest_suites:\n  - framework: cargo\n    command: sh run.sh\n  - framework: mocha\n    path: web\n    command: sh run.sh\n",
+        )
+        .unwrap();
 
-        Ok(output)
+        let changed = ["src/math.rs", "tests/api.rs", "tests/db.rs", "web/src/app.test.js", "web/src/b.spec.js"].map(String::from);
+        let report = TestRunner::run_affected_tests(&root, &changed).await.unwrap();
+        let runs: Vec<(&Path, &str)> = report.suites.iter().map(|s| (s.suite.dir.as_path(), s.stdout.trim())).collect();
+        assert_eq!(
+            runs,
+            vec![
+                (Path::new(""), "args: --test api"),
+                (Path::new(""), "args: --test db"),
+                (Path::new(""), "args: math"),
+                (Path::new("web"), "args: src/app.test.js src/b.spec.js"),
+            ]
+        );
+        let _ = std::fs::remove_dir_all(&root);
     }
 }
@@ -1,4 +1,8 @@
-use std::io::{self, BufRead};
+use std::io::{self, BufRead, IsTerminal};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Set while the TUI owns the terminal in raw mode, where stdin prompts would corrupt the screen.
+static TERMINAL_UI_ACTIVE: AtomicBool = AtomicBool::new(false);
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum UserChoice {
@@ -13,6 +17,15 @@ pub enum UserChoice {
 pub struct InteractiveMode;
 
 impl InteractiveMode {
+    pub fn set_terminal_ui_active(active: bool) {
+        TERMINAL_UI_ACTIVE.store(active, Ordering::SeqCst);
+    }
+
+    /// Whether a line-based prompt on stdin can be answered right now.
+    pub fn can_prompt() -> bool {
+        !TERMINAL_UI_ACTIVE.load(Ordering::SeqCst) && io::stdin().is_terminal()
+    }
+
     pub fn prompt_for_approval(message: &str) -> UserChoice {
         println!("\n{}", message);
         println!("\nOptions:");
@@ -81,6 +94,23 @@ impl InteractiveMode {
         println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
     }
 
+    pub fn display_diff(diff: &str) {
+        println!();
+        for line in diff.lines() {
+            if line.starts_with("+++") || line.starts_with("---") {
+                println!("\x1b[1m{}\x1b[0m", line);
+            } else if line.starts_with('+') {
+                println!("\x1b[32m{}\x1b[0m", line);
+            } else if line.starts_with('-') {
+                println!("\x1b[31m{}\x1b[0m", line);
+            } else if line.starts_with("@@") {
+                println!("\x1b[36m{}\x1b[0m", line);
+            } else {
+                println!("{}", line);
+            }
+        }
+    }
+
     pub fn display_summary(title: &str, items: &[(String, String)]) {
         println!("\n📊 {}", title);
         println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
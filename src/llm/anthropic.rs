@@ -1,4 +1,5 @@
 use super::client::{LLMClient, LLMResponse};
+use crate::core::audit::AuditLog;
 use serde::{Deserialize, Serialize};
 use anyhow::Result;
 
@@ -11,28 +12,28 @@ struct AnthropicRequest {
 }
 
 #[derive(Serialize, Deserialize)]
-struct AnthropicMessage {
-    role: String,
-    content: String,
+pub struct AnthropicMessage {
+    pub role: String,
+    pub content: String,
 }
 
 #[derive(Deserialize)]
-struct AnthropicApiResponse {
-    content: Vec<AnthropicContent>,
-    usage: AnthropicUsage,
+pub struct AnthropicApiResponse {
+    pub content: Vec<AnthropicContent>,
+    pub usage: AnthropicUsage,
 }
 
 #[derive(Deserialize)]
-struct AnthropicContent {
+pub struct AnthropicContent {
     #[serde(rename = "type")]
-    content_type: String,
-    text: String,
+    pub content_type: String,
+    pub text: String,
 }
 
 #[derive(Deserialize)]
-struct AnthropicUsage {
-    input_tokens: u32,
-    output_tokens: u32,
+pub struct AnthropicUsage {
+    pub input_tokens: u32,
+    pub output_tokens: u32,
 }
 
 pub struct AnthropicClient {
@@ -56,6 +57,23 @@ impl AnthropicClient {
         &self,
         system: Option<String>,
         messages: Vec<AnthropicMessage>,
+    ) -> Result<AnthropicApiResponse> {
+        let prompt_hash =
+            AuditLog::hash_prompt(system.iter().map(|s| s.as_str()).chain(messages.iter().map(|m| m.content.as_str())));
+        let started = std::time::Instant::now();
+        let result = self.send(system, messages).await;
+        let tokens = result
+            .as_ref()
+            .map(|r| (Some(r.usage.input_tokens), Some(r.usage.output_tokens)))
+            .unwrap_or_default();
+        AuditLog::llm_call("Anthropic", &self.model, prompt_hash, tokens, started, result.as_ref().err());
+        result
+    }
+
+    async fn send(
+        &self,
+        system: Option<String>,
+        messages: Vec<AnthropicMessage>,
     ) -> Result<AnthropicApiResponse> {
         let url = format!("{}/messages", self.base_url);
         
@@ -99,7 +117,7 @@ impl LLMClient for AnthropicClient {
         
         let content = api_response
             .content
-            .get(0)
+            .first()
             .map(|c| c.text.clone())
             .ok_or_else(|| anyhow::anyhow!("No response from Anthropic"))?;
 
@@ -130,7 +148,7 @@ impl LLMClient for AnthropicClient {
         
         let content = api_response
             .content
-            .get(0)
+            .first()
             .map(|c| c.text.clone())
             .ok_or_else(|| anyhow::anyhow!("No response from Anthropic"))?;
 
@@ -1,5 +1,9 @@
+use crate::config::yaml_parser::YamlConfig;
 use std::fmt;
 
+pub const DEFAULT_OPENAI_MODEL: &str = "gpt-4o";
+pub const DEFAULT_ANTHROPIC_MODEL: &str = "claude-sonnet-4-5";
+
 #[derive(Debug, Clone)]
 pub enum LLMProvider {
     Ollama {
@@ -50,3 +54,96 @@ impl fmt::Display for LLMProvider {
         }
     }
 }
+
+/// The hosted client `model_provider` selects, or None when the project runs on Ollama.
+pub fn hosted_client(config: &YamlConfig) -> anyhow::Result<Option<Box<dyn LLMClient>>> {
+    let key = |key: &Option<String>, name: &str| {
+        key.clone()
+            .filter(|k| !k.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("{} is required for the {} provider", name, config.model_provider))
+    };
+    match config.model_provider.as_str() {
+        "openai" => Ok(Some(Box::new(super::openai::OpenAIClient::new(
+            key(&config.openai_api_key, "openai_api_key")?,
+            DEFAULT_OPENAI_MODEL.to_string(),
+        )))),
+        "anthropic" => Ok(Some(Box::new(super::anthropic::AnthropicClient::new(
+            key(&config.anthropic_api_key, "anthropic_api_key")?,
+            DEFAULT_ANTHROPIC_MODEL.to_string(),
+        )))),
+        _ => Ok(None),
+    }
+}
+
+/// Replays canned replies in order and records every prompt it was sent.
+#[cfg(test)]
+pub struct ScriptedClient {
+    pub replies: std::sync::Mutex<Vec<String>>,
+    pub prompts: std::sync::Mutex<Vec<String>>,
+    pub system_prompts: std::sync::Mutex<Vec<String>>,
+}
+
+#[cfg(test)]
+impl ScriptedClient {
+    pub fn new(replies: &[&str]) -> Self {
+        ScriptedClient {
+            replies: std::sync::Mutex::new(replies.iter().map(|r| r.to_string()).collect()),
+            prompts: std::sync::Mutex::new(Vec::new()),
+            system_prompts: std::sync::Mutex::new(Vec::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+#[async_trait::async_trait]
+impl LLMClient for ScriptedClient {
+    async fn generate(&self, prompt: &str) -> anyhow::Result<LLMResponse> {
+        self.generate_with_context("", prompt).await
+    }
+
+    async fn generate_with_context(&self, system_prompt: &str, user_prompt: &str) -> anyhow::Result<LLMResponse> {
+        self.system_prompts.lock().unwrap().push(system_prompt.to_string());
+        self.prompts.lock().unwrap().push(user_prompt.to_string());
+        let mut replies = self.replies.lock().unwrap();
+        if replies.is_empty() {
+            return Err(anyhow::anyhow!("ScriptedClient ran out of replies"));
+        }
+        Ok(LLMResponse {
+            content: replies.remove(0),
+            tokens_used: None,
+            model: "scripted".to_string(),
+            provider: "test".to_string(),
+        })
+    }
+
+    fn provider_name(&self) -> &str {
+        "test"
+    }
+
+    fn model_name(&self) -> &str {
+        "scripted"
+    }
+
+    async fn health_check(&self) -> anyhow::Result<bool> {
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hosted_client_follows_model_provider() {
+        let config = YamlConfig::from_yaml("model_provider: anthropic
+anthropic_api_key: sk-test
+").unwrap();
+        assert_eq!(hosted_client(&config).unwrap().unwrap().provider_name(), "Anthropic");
+
+        let config = YamlConfig::from_yaml("model_provider: openai
+").unwrap();
+        assert!(hosted_client(&config).is_err());
+
+        assert!(hosted_client(&YamlConfig::default()).unwrap().is_none());
+    }
+}
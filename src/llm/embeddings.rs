@@ -0,0 +1,328 @@
+use crate::config::yaml_parser::YamlConfig;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_OLLAMA_EMBEDDING_MODEL: &str = "nomic-embed-text";
+pub const DEFAULT_OPENAI_EMBEDDING_MODEL: &str = "text-embedding-3-small";
+pub const DEFAULT_HASHING_DIMENSIONS: usize = 384;
+
+#[async_trait::async_trait]
+pub trait Embedder: Send + Sync {
+    async fn embed(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>>;
+
+    async fn embed_one(&self, input: &str) -> Result<Vec<f32>> {
+        self.embed(&[input.to_string()])
+            .await?
+            .pop()
+            .ok_or_else(|| anyhow::anyhow!("Embedder returned no vectors"))
+    }
+
+    fn provider_name(&self) -> &str;
+
+    fn model_name(&self) -> &str;
+}
+
+#[derive(Serialize)]
+struct OllamaEmbedRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Deserialize)]
+struct OllamaEmbedResponse {
+    embeddings: Vec<Vec<f32>>,
+}
+
+pub struct OllamaEmbedder {
+    base_url: String,
+    model: String,
+    client: reqwest::Client,
+}
+
+impl OllamaEmbedder {
+    pub fn new(base_url: String, model: String) -> Self {
+        OllamaEmbedder {
+            base_url,
+            model,
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Embedder for OllamaEmbedder {
+    async fn embed(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>> {
+        let url = format!("{}/api/embed", self.base_url);
+        let request = OllamaEmbedRequest {
+            model: &self.model,
+            input: inputs,
+        };
+
+        let response = self.client.post(&url).json(&request).send().await?;
+
+        if response.status().is_success() {
+            let data: OllamaEmbedResponse = response.json().await?;
+            Ok(data.embeddings)
+        } else {
+            let error_text = response.text().await.unwrap_or_default();
+            Err(anyhow::anyhow!("Ollama embed error: {}", error_text))
+        }
+    }
+
+    fn provider_name(&self) -> &str {
+        "Ollama"
+    }
+
+    fn model_name(&self) -> &str {
+        &self.model
+    }
+}
+
+#[derive(Serialize)]
+struct OpenAIEmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Deserialize)]
+struct OpenAIEmbeddingResponse {
+    data: Vec<OpenAIEmbedding>,
+}
+
+#[derive(Deserialize)]
+struct OpenAIEmbedding {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+pub struct OpenAIEmbedder {
+    api_key: String,
+    model: String,
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl OpenAIEmbedder {
+    pub fn new(api_key: String, model: String) -> Self {
+        OpenAIEmbedder {
+            api_key,
+            model,
+            base_url: "https://api.openai.com/v1".to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Embedder for OpenAIEmbedder {
+    async fn embed(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>> {
+        let url = format!("{}/embeddings", self.base_url);
+        let request = OpenAIEmbeddingRequest {
+            model: &self.model,
+            input: inputs,
+        };
+
+        let response = self
+            .client
+            .post(&url)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .json(&request)
+            .send()
+            .await?;
+
+        if response.status().is_success() {
+            let mut data: OpenAIEmbeddingResponse = response.json().await?;
+            data.data.sort_by_key(|e| e.index);
+            Ok(data.data.into_iter().map(|e| e.embedding).collect())
+        } else {
+            let error_text = response.text().await.unwrap_or_default();
+            Err(anyhow::anyhow!("OpenAI embeddings error: {}", error_text))
+        }
+    }
+
+    fn provider_name(&self) -> &str {
+        "OpenAI"
+    }
+
+    fn model_name(&self) -> &str {
+        &self.model
+    }
+}
+
+// Offline fallback: feature-hashes identifiers and their character trigrams into a
+// fixed-size vector, so similar names land close together without a model server.
+pub struct HashingEmbedder {
+    dimensions: usize,
+    model: String,
+}
+
+impl HashingEmbedder {
+    pub fn new(dimensions: usize) -> Self {
+        // Every feature is reduced modulo the dimension count, so zero would divide by zero.
+        let dimensions = dimensions.max(1);
+        HashingEmbedder {
+            dimensions,
+            model: format!("hashed-ngrams-{}", dimensions),
+        }
+    }
+
+    pub fn embed_text(&self, text: &str) -> Vec<f32> {
+        let mut vector = vec![0.0f32; self.dimensions];
+
+        for word in Self::tokenize(text) {
+            self.add_feature(&mut vector, &word, 1.0);
+
+            let padded = format!("#{}#", word);
+            let chars: Vec<char> = padded.chars().collect();
+            for window in chars.windows(3) {
+                let trigram: String = window.iter().collect();
+                self.add_feature(&mut vector, &trigram, 0.5);
+            }
+        }
+
+        normalize(&mut vector);
+        vector
+    }
+
+    fn add_feature(&self, vector: &mut [f32], feature: &str, weight: f32) {
+        let hash = fnv1a(feature.as_bytes());
+        let index = (hash % self.dimensions as u64) as usize;
+        let sign = if (hash >> 63) == 0 { 1.0 } else { -1.0 };
+        vector[index] += sign * weight;
+    }
+
+    pub fn tokenize(text: &str) -> Vec<String> {
+        let mut tokens = Vec::new();
+
+        for raw in text.split(|c: char| !c.is_alphanumeric()) {
+            if raw.is_empty() {
+                continue;
+            }
+
+            let mut current = String::new();
+            let mut prev_lower = false;
+            for c in raw.chars() {
+                if c.is_uppercase() && prev_lower && !current.is_empty() {
+                    tokens.push(current.to_lowercase());
+                    current.clear();
+                }
+                prev_lower = c.is_lowercase() || c.is_ascii_digit();
+                current.push(c);
+            }
+            if !current.is_empty() {
+                tokens.push(current.to_lowercase());
+            }
+        }
+
+        tokens
+    }
+}
+
+impl Default for HashingEmbedder {
+    fn default() -> Self {
+        Self::new(DEFAULT_HASHING_DIMENSIONS)
+    }
+}
+
+#[async_trait::async_trait]
+impl Embedder for HashingEmbedder {
+    async fn embed(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>> {
+        Ok(inputs.iter().map(|input| self.embed_text(input)).collect())
+    }
+
+    fn provider_name(&self) -> &str {
+        "Offline"
+    }
+
+    fn model_name(&self) -> &str {
+        &self.model
+    }
+}
+
+pub async fn detect_embedder(config: &YamlConfig) -> Box<dyn Embedder> {
+    match config.model_provider.as_str() {
+        "openai" => {
+            if let Some(api_key) = config.openai_api_key.clone().filter(|k| !k.is_empty()) {
+                let model = config
+                    .embedding_model
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_OPENAI_EMBEDDING_MODEL.to_string());
+                return Box::new(OpenAIEmbedder::new(api_key, model));
+            }
+        }
+        "ollama" => {
+            let model = config
+                .embedding_model
+                .clone()
+                .unwrap_or_else(|| DEFAULT_OLLAMA_EMBEDDING_MODEL.to_string());
+            let embedder = OllamaEmbedder::new(config.ollama_base_url.clone(), model);
+            match embedder.embed_one("ping").await {
+                Ok(_) => return Box::new(embedder),
+                Err(e) => tracing::warn!("Ollama embeddings unavailable, using offline embedder: {}", e),
+            }
+        }
+        _ => {}
+    }
+
+    Box::new(HashingEmbedder::default())
+}
+
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+fn normalize(vector: &mut [f32]) {
+    let norm: f32 = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        for value in vector.iter_mut() {
+            *value /= norm;
+        }
+    }
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize_splits_identifiers() {
+        assert_eq!(
+            HashingEmbedder::tokenize("validateToken(auth_header)"),
+            vec!["validate", "token", "auth", "header"]
+        );
+    }
+
+    #[test]
+    fn test_hashing_embedder_is_deterministic_and_similar() {
+        let embedder = HashingEmbedder::default();
+        let a = embedder.embed_text("fn validate_token(token: &str)");
+        let b = embedder.embed_text("fn validate_token(token: &str)");
+        let related = embedder.embed_text("where do we validate tokens");
+        let unrelated = embedder.embed_text("render the settings tab border");
+
+        assert_eq!(a, b);
+        assert!(cosine_similarity(&a, &related) > cosine_similarity(&a, &unrelated));
+        assert_eq!(HashingEmbedder::new(0).embed_text("validate token").len(), 1);
+    }
+}
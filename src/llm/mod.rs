@@ -3,3 +3,4 @@
 pub mod openai;
 pub mod anthropic;
 pub mod client;
+pub mod embeddings;
@@ -1,4 +1,5 @@
 use super::client::{LLMClient, LLMResponse};
+use crate::core::audit::AuditLog;
 use serde::{Deserialize, Serialize};
 use anyhow::Result;
 
@@ -11,25 +12,29 @@ struct OpenAIRequest {
 }
 
 #[derive(Serialize, Deserialize)]
-struct OpenAIMessage {
-    role: String,
-    content: String,
+pub struct OpenAIMessage {
+    pub role: String,
+    pub content: String,
 }
 
 #[derive(Deserialize)]
-struct OpenAIApiResponse {
-    choices: Vec<OpenAIChoice>,
-    usage: OpenAIUsage,
+pub struct OpenAIApiResponse {
+    pub choices: Vec<OpenAIChoice>,
+    pub usage: OpenAIUsage,
 }
 
 #[derive(Deserialize)]
-struct OpenAIChoice {
-    message: OpenAIMessage,
+pub struct OpenAIChoice {
+    pub message: OpenAIMessage,
 }
 
 #[derive(Deserialize)]
-struct OpenAIUsage {
-    total_tokens: u32,
+pub struct OpenAIUsage {
+    #[serde(default)]
+    pub prompt_tokens: Option<u32>,
+    #[serde(default)]
+    pub completion_tokens: Option<u32>,
+    pub total_tokens: u32,
 }
 
 pub struct OpenAIClient {
@@ -50,6 +55,18 @@ impl OpenAIClient {
     }
 
     pub async fn call_api(&self, messages: Vec<OpenAIMessage>) -> Result<OpenAIApiResponse> {
+        let prompt_hash = AuditLog::hash_prompt(messages.iter().map(|m| m.content.as_str()));
+        let started = std::time::Instant::now();
+        let result = self.send(messages).await;
+        let tokens = result
+            .as_ref()
+            .map(|r| (r.usage.prompt_tokens, r.usage.completion_tokens))
+            .unwrap_or_default();
+        AuditLog::llm_call("OpenAI", &self.model, prompt_hash, tokens, started, result.as_ref().err());
+        result
+    }
+
+    async fn send(&self, messages: Vec<OpenAIMessage>) -> Result<OpenAIApiResponse> {
         let url = format!("{}/chat/completions", self.base_url);
         
         let request = OpenAIRequest {
@@ -94,7 +111,7 @@ impl LLMClient for OpenAIClient {
         
         let content = api_response
             .choices
-            .get(0)
+            .first()
             .map(|c| c.message.content.clone())
             .ok_or_else(|| anyhow::anyhow!("No response from OpenAI"))?;
 
@@ -126,7 +143,7 @@ impl LLMClient for OpenAIClient {
         
         let content = api_response
             .choices
-            .get(0)
+            .first()
             .map(|c| c.message.content.clone())
             .ok_or_else(|| anyhow::anyhow!("No response from OpenAI"))?;
 
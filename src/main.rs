@@ -7,12 +7,14 @@ use crossterm::{
 };
 use ratatui::prelude::*;
 use std::io;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
 
 mod cli;
 mod cache;
 mod config;
 mod core;
 mod error_correction;
+mod index;
 mod integrations;
 mod interactive;
 mod llm;
@@ -25,18 +27,34 @@ use cli::Cli;
 use tui::{App, EventHandler, draw, handle_input};
 use crossterm::event::KeyCode;
 
+const CHAT_CONTEXT_BUDGET: usize = 6000;
+const SEMANTIC_CONTEXT_CHUNKS: usize = 6;
+const SEMANTIC_CONTEXT_CHARS: usize = 4000;
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    let project_path = cli.path.clone().unwrap_or_else(|| ".".to_string());
+    let project_root = utils::FileUtils::project_root(std::path::Path::new(&project_path));
+
+    // The TUI owns the terminal, so it logs to a file; subcommands log to stderr.
+    let writer = match cli.command {
+        Some(_) => BoxMakeWriter::new(io::stderr),
+        None => match open_log_file(&project_root) {
+            Ok(file) => BoxMakeWriter::new(std::sync::Mutex::new(file)),
+            Err(_) => BoxMakeWriter::new(io::sink),
+        },
+    };
     tracing_subscriber::fmt()
         .with_env_filter(
             tracing_subscriber::EnvFilter::from_default_env()
                 .add_directive(tracing::Level::WARN.into()),
         )
-        .with_writer(std::io::sink)
+        .with_writer(writer)
+        .with_ansi(cli.command.is_some())
         .init();
 
-    let cli = Cli::parse();
-    let project_path = cli.path.unwrap_or_else(|| ".".to_string());
+    core::audit::AuditLog::init(&project_root);
 
     let mut ollama_client = match integrations::ollama::OllamaClient::auto_detect().await {
         Ok(client) => {
@@ -52,7 +70,12 @@ async fn main() -> anyhow::Result<()> {
         }
     };
 
+    if let Some(command) = cli.command {
+        return cli::run_command(command, &project_path, &mut ollama_client).await;
+    }
+
     enable_raw_mode()?;
+    interactive::mode::InteractiveMode::set_terminal_ui_active(true);
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen)?;
 
@@ -62,11 +85,13 @@ async fn main() -> anyhow::Result<()> {
     let mut app = App::new(project_path);
     app.ollama_model = ollama_client.model.clone();
     app.available_models = ollama_client.available_models.clone();
+    app.pending_model_action = Some(tui::app::ModelAction::Refresh);
     let events = EventHandler::new();
 
     let result = run_app(&mut terminal, &mut app, &events, &mut ollama_client).await;
 
     disable_raw_mode()?;
+    interactive::mode::InteractiveMode::set_terminal_ui_active(false);
     execute!(io::stdout(), LeaveAlternateScreen)?;
 
     if let Err(err) = result {
@@ -76,12 +101,24 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
+fn open_log_file(root: &std::path::Path) -> io::Result<std::fs::File> {
+    let dir = root.join(".codeagent");
+    std::fs::create_dir_all(&dir)?;
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join("codeagent.log"))
+}
+
 async fn run_app(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
     app: &mut App,
     events: &EventHandler,
     ollama: &mut integrations::ollama::OllamaClient,
 ) -> io::Result<()> {
+    let mut semantic_index: Option<index::SemanticIndex> = None;
+    let mut ranker: Option<index::RelevanceRanker> = None;
+
     while app.is_running {
         terminal.draw(|f| draw(f, app))?;
 
@@ -97,9 +134,14 @@ async fn run_app(
                         app.add_message("You".to_string(), prompt.clone());
                         app.is_loading = true;
                         
-                        match ollama.generate(&prompt).await {
-                            Ok(response) => {
-                                app.add_message("CodeAgent".to_string(), response);
+                        let context = chat_context(app, &mut ranker, &mut semantic_index, &prompt).await;
+                        let agent = core::agent::Agent::for_project(std::path::Path::new(&app.project_path));
+                        match agent.run(&*ollama, &prompt, &context).await {
+                            Ok(turn) => {
+                                for outcome in &turn.tool_calls {
+                                    app.add_message("Tool".to_string(), outcome.summary());
+                                }
+                                app.add_message("CodeAgent".to_string(), turn.answer);
                             }
                             Err(e) => {
                                 app.add_message(
@@ -123,6 +165,183 @@ async fn run_app(
                 }
             }
         }
+
+        if let Some(action) = app.pending_model_action.take() {
+            handle_model_action(terminal, app, ollama, action).await?;
+        }
+
+        if let Some(query) = app.pending_semantic_query.take() {
+            app.is_loading = true;
+            terminal.draw(|f| draw(f, app))?;
+            handle_semantic_query(app, &mut semantic_index, &query).await;
+            app.is_loading = false;
+        }
+    }
+
+    Ok(())
+}
+
+async fn chat_context(
+    app: &App,
+    ranker: &mut Option<index::RelevanceRanker>,
+    semantic_index: &mut Option<index::SemanticIndex>,
+    prompt: &str,
+) -> String {
+    let root = std::path::Path::new(&app.project_path);
+    let mut context = String::new();
+
+    match ranker {
+        Some(existing) => {
+            if let Err(e) = existing.refresh() {
+                tracing::warn!("Could not refresh file ranking: {}", e);
+            }
+        }
+        None => match index::RelevanceRanker::build(root) {
+            Ok(built) => *ranker = Some(built),
+            Err(e) => tracing::warn!("Could not rank project files: {}", e),
+        },
+    }
+
+    let relevant = ranker
+        .as_ref()
+        .map(|r| index::RelevanceRanker::format_context(&r.select_context(prompt, CHAT_CONTEXT_BUDGET)))
+        .unwrap_or_default();
+    if !relevant.is_empty() {
+        context.push_str(&format!("## RELEVANT PROJECT FILES\n{}", relevant));
+    }
+
+    if semantic_index.is_none() {
+        *semantic_index = Some(index::SemanticIndex::for_project(root).await);
+    }
+    if let Some(semantic) = semantic_index.as_mut() {
+        match semantic.refreshed_context(prompt, SEMANTIC_CONTEXT_CHUNKS, SEMANTIC_CONTEXT_CHARS).await {
+            Ok(related) if !related.is_empty() => {
+                context.push_str(&format!("## SEMANTICALLY RELATED CODE\n{}", related));
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Could not search the semantic index: {}", e),
+        }
+    }
+
+    context
+}
+
+async fn handle_semantic_query(
+    app: &mut App,
+    semantic_index: &mut Option<index::SemanticIndex>,
+    query: &str,
+) {
+    let root = std::path::PathBuf::from(&app.project_path);
+
+    if semantic_index.is_none() {
+        *semantic_index = Some(index::SemanticIndex::for_project(&root).await);
+    }
+
+    let Some(index) = semantic_index.as_mut() else {
+        return;
+    };
+
+    let results = match index.refresh().await {
+        Ok(_) => utils::search::SemanticSearch::search_semantic(index, query, 8).await,
+        Err(e) => Err(e),
+    };
+
+    match results {
+        Ok(results) if results.is_empty() => {
+            app.add_message("CodeAgent".to_string(), format!("No matches for \"{}\"", query));
+        }
+        Ok(results) => {
+            let mut lines = vec![format!("Top matches for \"{}\":", query)];
+            for result in results {
+                let relative = result.file.strip_prefix(&root).unwrap_or(&result.file);
+                let first_line = result
+                    .content
+                    .lines()
+                    .find(|l| !l.trim().is_empty())
+                    .unwrap_or_default()
+                    .trim();
+                lines.push(format!(
+                    "▪ {}:{} ({:.2})  {}",
+                    relative.display(),
+                    result.line_number,
+                    result.score.unwrap_or_default(),
+                    first_line
+                ));
+            }
+            app.add_message("CodeAgent".to_string(), lines.join("\n"));
+        }
+        Err(e) => {
+            app.add_message("Error".to_string(), format!("❌ Search failed: {}", e));
+        }
+    }
+}
+
+async fn handle_model_action(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    app: &mut App,
+    ollama: &mut integrations::ollama::OllamaClient,
+    action: tui::app::ModelAction,
+) -> io::Result<()> {
+    use tui::app::{ModelAction, ModelPullState, NotificationLevel};
+
+    let result = match action {
+        ModelAction::Switch(model) => {
+            let ready = if ollama.available_models.contains(&model) {
+                Ok(())
+            } else {
+                app.model_pull = Some(ModelPullState {
+                    model: model.clone(),
+                    status: "starting".to_string(),
+                    percent: None,
+                });
+                terminal.draw(|f| draw(f, app))?;
+
+                let pulled = ollama
+                    .pull(&model, |progress| {
+                        if let Some(state) = app.model_pull.as_mut() {
+                            state.status = progress.status.clone();
+                            state.percent = progress.percent();
+                        }
+                        let _ = terminal.draw(|f| draw(f, app));
+                    })
+                    .await;
+                app.model_pull = None;
+                pulled
+            };
+
+            ready.map(|_| {
+                ollama.set_model(model.clone());
+                app.ollama_model = model.clone();
+                format!("Switched to {}", model)
+            })
+        }
+        ModelAction::Delete(model) => ollama
+            .delete(&model)
+            .await
+            .map(|_| format!("Deleted {}", model)),
+        ModelAction::Refresh => ollama
+            .get_available_models()
+            .await
+            .map(|models| format!("{} models available", models.len())),
+    };
+
+    app.available_models = ollama.available_models.clone();
+    app.loaded_models = ollama
+        .ps()
+        .await
+        .map(|running| running.into_iter().map(|m| m.name).collect())
+        .unwrap_or_default();
+    app.active_model_context = ollama
+        .show(&ollama.model)
+        .await
+        .ok()
+        .and_then(|info| info.context_length);
+
+    match result {
+        Ok(message) => {
+            app.add_notification("Models".to_string(), message, NotificationLevel::Success)
+        }
+        Err(e) => app.add_notification("Models".to_string(), e.to_string(), NotificationLevel::Error),
     }
 
     Ok(())
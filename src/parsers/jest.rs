@@ -0,0 +1,343 @@
+use super::test_parser::{TestResult, TestStatus};
+use regex::Regex;
+use serde::Deserialize;
+
+const FRAMEWORK: &str = "Jest";
+
+/// The report printed by `jest --json` and `vitest --reporter=json`.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JestReport {
+    test_results: Vec<JestFileResult>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JestFileResult {
+    name: String,
+    #[serde(default)]
+    status: Option<String>,
+    #[serde(default)]
+    message: Option<String>,
+    #[serde(default)]
+    assertion_results: Vec<JestAssertion>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JestAssertion {
+    #[serde(default)]
+    ancestor_titles: Vec<String>,
+    title: String,
+    status: String,
+    #[serde(default)]
+    duration: Option<f64>,
+    #[serde(default)]
+    failure_messages: Vec<String>,
+    #[serde(default)]
+    location: Option<JestLocation>,
+}
+
+#[derive(Deserialize)]
+struct JestLocation {
+    line: usize,
+    column: usize,
+}
+
+pub struct JestParser;
+
+impl JestParser {
+    pub fn parse(output: &str) -> Vec<TestResult> {
+        match Self::parse_json(output) {
+            Some(results) => results,
+            None => Self::parse_text(output),
+        }
+    }
+
+    /// Finds the JSON report among whatever the package manager printed around it.
+    pub fn parse_json(output: &str) -> Option<Vec<TestResult>> {
+        let report = Self::line_starts(output)
+            .filter(|&start| output[start..].starts_with('{'))
+            .find_map(|start| {
+                serde_json::Deserializer::from_str(&output[start..])
+                    .into_iter::<JestReport>()
+                    .next()
+                    .and_then(|report| report.ok())
+            })?;
+
+        let mut results = Vec::new();
+        for file in report.test_results {
+            // A file that fails to load (syntax error, missing module) has no assertions.
+            if file.assertion_results.is_empty() && file.status.as_deref() == Some("failed") {
+                let mut result = TestResult::new(&file.name, TestStatus::Failed, FRAMEWORK);
+                result.file = file.name.clone();
+                let message = Self::strip_ansi(file.message.as_deref().unwrap_or_default());
+                result.error_message = Some(Self::failure_message(&message).unwrap_or_else(|| "Test file failed".to_string()));
+                result.stack_trace = Some(message).filter(|m| !m.trim().is_empty());
+                results.push(result);
+                continue;
+            }
+
+            for assertion in file.assertion_results {
+                let status = match assertion.status.as_str() {
+                    "passed" => TestStatus::Passed,
+                    "failed" => TestStatus::Failed,
+                    "todo" => TestStatus::Pending,
+                    _ => TestStatus::Skipped,
+                };
+                let name = assertion
+                    .ancestor_titles
+                    .iter()
+                    .chain(std::iter::once(&assertion.title))
+                    .map(|s| s.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" › ");
+                let mut result = TestResult::new(&name, status, FRAMEWORK);
+                result.file = file.name.clone();
+                result.duration_ms = assertion.duration.map(|d| d.round() as u64).unwrap_or(0);
+                if let Some(location) = &assertion.location {
+                    result.line = Some(location.line);
+                    result.column = Some(location.column);
+                }
+                if result.status == TestStatus::Failed {
+                    let failure = Self::strip_ansi(&assertion.failure_messages.join("\n\n"));
+                    // A frame in the test file beats `location`, which only points at the test's start.
+                    if let Some((line, column, in_test_file)) = Self::stack_location(&failure, &file.name) {
+                        if in_test_file || result.line.is_none() {
+                            result.line = Some(line);
+                            result.column = Some(column);
+                        }
+                    }
+                    result.error_message = Some(Self::failure_message(&failure).unwrap_or_else(|| "Test failed".to_string()));
+                    result.stack_trace = Some(failure).filter(|f| !f.trim().is_empty());
+                }
+                results.push(result);
+            }
+        }
+        Some(results)
+    }
+
+    /// The default reporters' `PASS`/`FAIL` file headers and ✓/✕ lines. Vitest marks
+    /// file headers with the same glyphs as tests, so paths are recognised by extension.
+    /// Jest's `● describe › test` blocks fill in the failures.
+    pub fn parse_text(output: &str) -> Vec<TestResult> {
+        let output = Self::strip_ansi(output);
+        let file_re = Regex::new(r"^\s*(?:PASS|FAIL|✓|❯|×)\s+(\S+\.[cm]?[jt]sx?)(?:\s|$)").unwrap();
+        let test_re = Regex::new(r"^\s*(✓|√|✕|×|○|↓)\s+(?:skipped\s+|todo\s+)?(.+?)(?:\s+\(?(\d+)\s*ms\)?)?\s*$").unwrap();
+        let block_re = Regex::new(r"^\s*● (.+?)\s*$").unwrap();
+        let summary_re = Regex::new(r"^(?:Summary of all failing tests|Test Suites:|Tests:|Snapshots:|Time:)").unwrap();
+
+        let mut results = Vec::new();
+        let mut file = String::new();
+        // (file, title, text) of each failure block.
+        let mut blocks: Vec<(String, String, String)> = Vec::new();
+        let mut block: Option<(String, String, String)> = None;
+        for line in output.lines() {
+            if let Some(cap) = file_re.captures(line) {
+                blocks.extend(block.take());
+                file = cap[1].to_string();
+                continue;
+            }
+            if let Some(cap) = block_re.captures(line) {
+                blocks.extend(block.take());
+                block = Some((file.clone(), cap[1].to_string(), String::new()));
+                continue;
+            }
+            if summary_re.is_match(line) {
+                blocks.extend(block.take());
+            }
+            if let Some((_, _, text)) = block.as_mut() {
+                text.push_str(line);
+                text.push('\n');
+                continue;
+            }
+            let Some(cap) = test_re.captures(line) else {
+                continue;
+            };
+            let status = match &cap[1] {
+                "✓" | "√" => TestStatus::Passed,
+                "✕" | "×" => TestStatus::Failed,
+                _ => TestStatus::Skipped,
+            };
+            let mut result = TestResult::new(&cap[2], status, FRAMEWORK);
+            result.file = file.clone();
+            result.duration_ms = cap.get(3).and_then(|d| d.as_str().parse().ok()).unwrap_or(0);
+            if result.status == TestStatus::Failed {
+                result.error_message = Some("Test failed".to_string());
+            }
+            results.push(result);
+        }
+        blocks.extend(block);
+
+        // The summary at the end repeats every block, so each one is used at most once.
+        let mut claimed = vec![false; blocks.len()];
+        for result in results.iter_mut().filter(|r| r.status == TestStatus::Failed) {
+            let suffix = format!(" › {}", result.name);
+            let Some(b) = (0..blocks.len()).find(|&b| {
+                let (file, title, _) = &blocks[b];
+                !claimed[b] && *file == result.file && (*title == result.name || title.ends_with(&suffix))
+            }) else {
+                continue;
+            };
+            claimed[b] = true;
+            let (_, title, text) = &blocks[b];
+            result.name = title.clone();
+            Self::attach_block(result, text);
+        }
+        // A file that fails to load only has a block.
+        for (b, (file, title, text)) in blocks.iter().enumerate() {
+            if claimed[b] || title != "Test suite failed to run" || results.iter().any(|r| r.file == *file) {
+                continue;
+            }
+            let mut result = TestResult::new(file, TestStatus::Failed, FRAMEWORK);
+            result.file = file.clone();
+            Self::attach_block(&mut result, text);
+            results.push(result);
+        }
+        results
+    }
+
+    /// The message, crash location and trace from a failure block, without the code frame.
+    fn attach_block(result: &mut TestResult, text: &str) {
+        let frame_re = Regex::new(r"^\s*(?:>\s*)?\d+ \||^\s+\|").unwrap();
+        let indent = text
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| l.len() - l.trim_start().len())
+            .min()
+            .unwrap_or(0);
+        let failure: Vec<&str> = text.lines().map(|l| l.get(indent..).unwrap_or_default()).collect();
+        let failure = failure.join("\n").trim().to_string();
+        let message: Vec<&str> = failure.lines().filter(|l| !frame_re.is_match(l)).collect();
+
+        if let Some(message) = Self::failure_message(&message.join("\n")) {
+            result.error_message = Some(message);
+        }
+        if let Some((line, column, _)) = Self::stack_location(&failure, &result.file) {
+            result.line = Some(line);
+            result.column = Some(column);
+        }
+        result.stack_trace = Some(failure).filter(|f| !f.is_empty());
+    }
+
+    /// Line and column of the innermost stack frame in the test file itself, or failing
+    /// that the first frame outside `node_modules` and Node internals.
+    fn stack_location(failure: &str, test_file: &str) -> Option<(usize, usize, bool)> {
+        let frame_re = Regex::new(r"^\s*at (?:.*? \()?(.+?):(\d+):(\d+)\)?\s*$").unwrap();
+        let frames: Vec<(String, usize, usize)> = failure
+            .lines()
+            .filter_map(|line| frame_re.captures(line))
+            .filter_map(|cap| Some((cap[1].trim_start_matches("file://").to_string(), cap[2].parse().ok()?, cap[3].parse().ok()?)))
+            .collect();
+        let in_test_file = |path: &str| !test_file.is_empty() && (path == test_file || test_file.ends_with(path));
+        match frames.iter().find(|(path, _, _)| in_test_file(path)) {
+            Some((_, line, column)) => Some((*line, *column, true)),
+            None => frames
+                .iter()
+                .find(|(path, _, _)| !path.contains("node_modules") && !path.starts_with("node:"))
+                .map(|(_, line, column)| (*line, *column, false)),
+        }
+    }
+
+    /// The message above the stack trace.
+    fn failure_message(failure: &str) -> Option<String> {
+        let message: Vec<&str> = failure
+            .lines()
+            .take_while(|l| !l.trim_start().starts_with("at "))
+            .collect();
+        let message = message.join("\n").trim().to_string();
+        (!message.is_empty()).then_some(message)
+    }
+
+    fn strip_ansi(text: &str) -> String {
+        let ansi_re = Regex::new(r"\x1b\[[0-9;]*[A-Za-z]").unwrap();
+        ansi_re.replace_all(text, "").into_owned()
+    }
+
+    fn line_starts(output: &str) -> impl Iterator<Item = usize> + '_ {
+        std::iter::once(0).chain(output.match_indices('\n').map(|(i, _)| i + 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jest_json_report() {
+        let output = r#"
+> web@1.0.0 test
+> jest --json
+
+{"numFailedTests":1,"numPassedTests":1,"success":false,"testResults":[{"name":"/repo/web/src/math.test.js","status":"failed","message":"","assertionResults":[{"ancestorTitles":["math","add"],"fullName":"math add sums","title":"sums","status":"passed","duration":4,"failureMessages":[]},{"ancestorTitles":["math"],"fullName":"math subtracts","title":"subtracts","status":"failed","duration":12,"failureMessages":["Error: \u001b[2mexpect(\u001b[22mreceived\u001b[2m).toBe(\u001b[22mexpected\u001b[2m)\u001b[22m\n\nExpected: 4\nReceived: 3\n    at Object.toBe (/repo/web/node_modules/expect/build/index.js:10:5)\n    at Object.<anonymous> (/repo/web/src/math.test.js:9:19)\n    at processTicksAndRejections (node:internal/process/task_queues:95:5)"]},{"ancestorTitles":[],"title":"later","status":"pending","failureMessages":[]}]},{"name":"/repo/web/src/broken.test.js","status":"failed","message":"  ● Test suite failed to run\n\n    SyntaxError: Unexpected token (3:4)\n","assertionResults":[]}]}
+"#;
+        let results = JestParser::parse(output);
+        assert_eq!(results.len(), 4);
+        assert_eq!((results[0].name.as_str(), results[0].duration_ms), ("math › add › sums", 4));
+        assert_eq!(results[0].file, "/repo/web/src/math.test.js");
+
+        let failed = &results[1];
+        assert_eq!(failed.status, TestStatus::Failed);
+        assert_eq!((failed.line, failed.column), (Some(9), Some(19)));
+        assert_eq!(
+            failed.error_message.as_deref(),
+            Some("Error: expect(received).toBe(expected)\n\nExpected: 4\nReceived: 3")
+        );
+        assert_eq!(results[2].status, TestStatus::Skipped);
+        assert_eq!(results[3].file, "/repo/web/src/broken.test.js");
+        assert_eq!(results[3].error_message.as_deref(), Some("● Test suite failed to run\n\n    SyntaxError: Unexpected token (3:4)"));
+    }
+
+    #[test]
+    fn test_text_fallback_tracks_files() {
+        let output = "PASS src/add.test.js\n  add\n    ✓ sums (3 ms)\nFAIL src/sub.test.js\n  sub\n    ✕ subtracts (5 ms)\n    ○ skipped later\n";
+        let results = JestParser::parse(output);
+        assert_eq!(results.len(), 3);
+        let vitest = JestParser::parse(" ❯ src/sub.test.ts (2 tests | 1 failed) 9ms\n   ✓ sums 2ms\n   × subtracts 7ms\n");
+        assert_eq!((vitest[1].file.as_str(), vitest[1].name.as_str(), vitest[1].duration_ms), ("src/sub.test.ts", "subtracts", 7));
+        assert_eq!((results[0].file.as_str(), results[0].duration_ms), ("src/add.test.js", 3));
+        assert_eq!((results[1].file.as_str(), &results[1].status), ("src/sub.test.js", &TestStatus::Failed));
+        assert_eq!((results[2].name.as_str(), &results[2].status), ("later", &TestStatus::Skipped));
+    }
+
+    #[test]
+    fn test_text_failure_blocks() {
+        let output = "\
+FAIL src/a.test.js
+  sub
+    ✕ subtracts (5 ms)
+
+  ● sub › subtracts
+
+    expect(received).toBe(expected) // Object.is equality
+
+    Expected: 4
+    Received: 3
+
+       8 |   test('subtracts', () => {
+    >  9 |     expect(sub(7, 4)).toBe(4);
+         |                       ^
+      10 |   });
+
+      at Object.toBe (src/a.test.js:9:23)
+
+FAIL src/b.test.js
+  ● Test suite failed to run
+
+    Cannot find module './missing' from 'src/b.test.js'
+
+Tests:       1 failed, 1 total
+";
+        let results = JestParser::parse(output);
+        assert_eq!(results.len(), 2);
+        let failed = &results[0];
+        assert_eq!((failed.name.as_str(), failed.line, failed.column), ("sub › subtracts", Some(9), Some(23)));
+        assert_eq!(
+            failed.error_message.as_deref(),
+            Some("expect(received).toBe(expected) // Object.is equality\n\nExpected: 4\nReceived: 3")
+        );
+        assert!(failed.stack_trace.as_deref().unwrap().ends_with("at Object.toBe (src/a.test.js:9:23)"));
+        assert_eq!((results[1].file.as_str(), &results[1].status), ("src/b.test.js", &TestStatus::Failed));
+        assert_eq!(results[1].error_message.as_deref(), Some("Cannot find module './missing' from 'src/b.test.js'"));
+    }
+}
@@ -0,0 +1,309 @@
+use super::libtest::LibtestParser;
+use super::pytest::PytestParser;
+use super::test_parser::{TestResult, TestStatus};
+use anyhow::Result;
+use std::collections::BTreeMap;
+
+pub struct JunitParser;
+
+impl JunitParser {
+    /// Reads `<testcase>` elements from any `<testsuites>`/`<testsuite>` nesting. The file
+    /// comes from the `file` attribute when the tool writes one, otherwise the classname.
+    pub fn parse(xml: &str, framework: &str) -> Result<Vec<TestResult>> {
+        let document =
+            roxmltree::Document::parse(xml).map_err(|e| anyhow::anyhow!("Invalid JUnit XML: {}", e))?;
+        let root = document.root_element();
+        if !matches!(root.tag_name().name(), "testsuites" | "testsuite") {
+            return Err(anyhow::anyhow!(
+                "Expected a <testsuites> or <testsuite> root, found <{}>",
+                root.tag_name().name()
+            ));
+        }
+
+        let mut results = Vec::new();
+        for case in root.descendants().filter(|n| n.has_tag_name("testcase")) {
+            let name = case.attribute("name").unwrap_or_default();
+            let mut result = TestResult::new(name, TestStatus::Passed, framework);
+            result.file = case
+                .attribute("file")
+                .or_else(|| case.attribute("classname"))
+                .unwrap_or_default()
+                .to_string();
+            result.line = case.attribute("line").and_then(|l| l.parse().ok());
+            result.duration_ms = case
+                .attribute("time")
+                .and_then(|t| t.replace(',', "").parse::<f64>().ok())
+                .map(|s| (s * 1000.0).round() as u64)
+                .unwrap_or(0);
+
+            let child = |tag: &str| case.children().find(|n| n.has_tag_name(tag));
+            result.stdout = child("system-out").and_then(|n| n.text()).map(|t| t.to_string());
+            if let Some(failure) = child("failure").or_else(|| child("error")) {
+                result.status = TestStatus::Failed;
+                let body = failure.text().unwrap_or_default().trim().to_string();
+                let message = failure
+                    .attribute("message")
+                    .filter(|m| !m.is_empty())
+                    .map(|m| m.to_string())
+                    .or_else(|| body.lines().next().map(|l| l.to_string()))
+                    .unwrap_or_else(|| "Test failed".to_string());
+                result.error_message = Some(message);
+                if !body.is_empty() {
+                    result.stack_trace = Some(body.clone());
+                }
+                // Rust reporters put the panic in the body or captured output rather than attributes.
+                let panic = LibtestParser::parse_panic(&body)
+                    .or_else(|| result.stdout.as_deref().and_then(LibtestParser::parse_panic));
+                if let Some(panic) = panic {
+                    result.file = panic.file;
+                    result.line = Some(panic.line);
+                    result.column = Some(panic.column);
+                    result.error_message = Some(panic.message);
+                }
+            } else if let Some(skipped) = child("skipped") {
+                result.status = TestStatus::Skipped;
+                result.error_message = skipped
+                    .attribute("message")
+                    .or_else(|| skipped.text())
+                    .filter(|m| !m.trim().is_empty())
+                    .map(|m| m.trim().to_string());
+            }
+            if framework.eq_ignore_ascii_case("pytest") {
+                Self::pytest_case(&mut result, &case);
+            }
+            results.push(result);
+        }
+        Ok(results)
+    }
+}
+
+impl JunitParser {
+    /// pytest's classname is the dotted module plus any classes, e.g. `tests.test_math.TestMath`.
+    /// Splits it into the file and a node-ID style name, and reads the failure from the
+    /// traceback pytest writes as the body.
+    fn pytest_case(result: &mut TestResult, case: &roxmltree::Node) {
+        let classname = case.attribute("classname").unwrap_or_default();
+        let classes = match case.attribute("file") {
+            Some(file) => {
+                let module = file.trim_end_matches(".py").replace('/', ".");
+                classname.strip_prefix(module.as_str()).unwrap_or_default().trim_start_matches('.').to_string()
+            }
+            // Already a path, as in reports this module writes.
+            None if classname.ends_with(".py") || classname.contains('/') => String::new(),
+            None => {
+                let parts: Vec<&str> = classname.split('.').collect();
+                let split = parts
+                    .iter()
+                    .position(|p| p.starts_with(|c: char| c.is_ascii_uppercase()))
+                    .unwrap_or(parts.len());
+                result.file = format!("{}.py", parts[..split].join("/"));
+                parts[split..].join(".")
+            }
+        };
+        if !classes.is_empty() {
+            result.name = format!("{}::{}", classes.replace('.', "::"), result.name);
+        }
+
+        let failure = case.children().find(|n| n.has_tag_name("failure") || n.has_tag_name("error"));
+        if let Some(failure) = failure {
+            let message = result.error_message.take();
+            let stdout = result.stdout.take();
+            PytestParser::attach_failure(result, failure.text().unwrap_or_default());
+            if failure.attribute("message").is_some_and(|m| !m.is_empty()) || result.error_message.is_none() {
+                result.error_message = message;
+            }
+            result.stdout = stdout;
+        }
+        let skipped = case.children().find(|n| n.has_tag_name("skipped"));
+        if skipped.and_then(|s| s.attribute("type")) == Some("pytest.xfail") {
+            result.error_message = Some(match result.error_message.take() {
+                Some(reason) => format!("XFAIL: {}", reason),
+                None => "XFAIL".to_string(),
+            });
+        }
+    }
+}
+
+pub struct JunitWriter;
+
+impl JunitWriter {
+    /// One `<testsuite>` per file, in the layout CI dashboards expect.
+    pub fn write(name: &str, results: &[TestResult]) -> String {
+        let mut suites: BTreeMap<&str, Vec<&TestResult>> = BTreeMap::new();
+        for result in results {
+            let suite = if result.file.is_empty() { result.framework.as_str() } else { result.file.as_str() };
+            suites.entry(suite).or_default().push(result);
+        }
+
+        let count = |tests: &[&TestResult], status: TestStatus| tests.iter().filter(|t| t.status == status).count();
+        let seconds = |tests: &[&TestResult]| tests.iter().map(|t| t.duration_ms).sum::<u64>() as f64 / 1000.0;
+        let all: Vec<&TestResult> = results.iter().collect();
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str(&format!(
+            "<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"{}\" time=\"{:.3}\">\n",
+            Self::attribute(name),
+            all.len(),
+            count(&all, TestStatus::Failed),
+            count(&all, TestStatus::Skipped) + count(&all, TestStatus::Pending),
+            seconds(&all)
+        ));
+        for (suite, tests) in &suites {
+            xml.push_str(&format!(
+                "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"{}\" time=\"{:.3}\">\n",
+                Self::attribute(suite),
+                tests.len(),
+                count(tests, TestStatus::Failed),
+                count(tests, TestStatus::Skipped) + count(tests, TestStatus::Pending),
+                seconds(tests)
+            ));
+            for test in tests {
+                xml.push_str(&Self::testcase(suite, test));
+            }
+            xml.push_str("  </testsuite>\n");
+        }
+        xml.push_str("</testsuites>\n");
+        xml
+    }
+
+    fn testcase(classname: &str, test: &TestResult) -> String {
+        let mut attributes = format!(
+            "name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+            Self::attribute(&test.name),
+            Self::attribute(classname),
+            test.duration_ms as f64 / 1000.0
+        );
+        if !test.file.is_empty() {
+            attributes.push_str(&format!(" file=\"{}\"", Self::attribute(&test.file)));
+        }
+        if let Some(line) = test.line {
+            attributes.push_str(&format!(" line=\"{}\"", line));
+        }
+
+        let mut body = String::new();
+        match test.status {
+            TestStatus::Failed => {
+                let message = test.error_message.as_deref().unwrap_or("Test failed");
+                let details = test.stack_trace.as_deref().unwrap_or(message);
+                body.push_str(&format!(
+                    "      <failure message=\"{}\">{}</failure>\n",
+                    Self::attribute(message),
+                    Self::escape(details)
+                ));
+            }
+            TestStatus::Skipped | TestStatus::Pending => match &test.error_message {
+                Some(reason) => body.push_str(&format!("      <skipped message=\"{}\"/>\n", Self::attribute(reason))),
+                None => body.push_str("      <skipped/>\n"),
+            },
+            TestStatus::Passed => {}
+        }
+        if let Some(stdout) = test.stdout.as_deref().filter(|s| !s.is_empty()) {
+            body.push_str(&format!("      <system-out>{}</system-out>\n", Self::escape(stdout)));
+        }
+
+        if body.is_empty() {
+            format!("    <testcase {}/>\n", attributes)
+        } else {
+            format!("    <testcase {}>\n{}    </testcase>\n", attributes, body)
+        }
+    }
+
+    /// Parsers normalise whitespace in attribute values, so multi-line messages need character references.
+    fn attribute(text: &str) -> String {
+        Self::escape(text).replace('\n', "&#10;").replace('\r', "&#13;").replace('\t', "&#9;")
+    }
+
+    /// Escapes markup and drops control characters (such as ANSI colour codes) that XML 1.0 forbids.
+    fn escape(text: &str) -> String {
+        let mut escaped = String::with_capacity(text.len());
+        for c in text.chars() {
+            match c {
+                '&' => escaped.push_str("&amp;"),
+                '<' => escaped.push_str("&lt;"),
+                '>' => escaped.push_str("&gt;"),
+                '"' => escaped.push_str("&quot;"),
+                '\'' => escaped.push_str("&apos;"),
+                '\t' | '\n' | '\r' => escaped.push(c),
+                c if c.is_control() => {}
+                c => escaped.push(c),
+            }
+        }
+        escaped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_junit_round_trip() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="pytest">
+  <testsuite name="pytest" tests="3">
+    <testcase classname="tests/test_math.py" name="test_adds" time="0.012"/>
+    <testcase classname="tests/test_math.py" name="test_subs" time="1.5">
+      <failure message="AssertionError: assert 3 == 4">def test_subs():
+&gt;       assert sub(7, 4) == 4
+E       AssertionError</failure>
+      <system-out>computing &lt;7 - 4&gt;</system-out>
+    </testcase>
+    <testcase classname="tests/test_slow.py" name="test_slow"><skipped message="too slow"/></testcase>
+  </testsuite>
+</testsuites>"#;
+        let results = JunitParser::parse(xml, "pytest").unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!((results[0].file.as_str(), results[0].duration_ms), ("tests/test_math.py", 12));
+        assert_eq!(results[1].status, TestStatus::Failed);
+        assert_eq!(results[1].error_message.as_deref(), Some("AssertionError: assert 3 == 4"));
+        assert!(results[1].stack_trace.as_deref().unwrap().contains(">       assert sub(7, 4) == 4"));
+        assert_eq!(results[1].stdout.as_deref(), Some("computing <7 - 4>"));
+        assert_eq!(results[2].status, TestStatus::Skipped);
+
+        let written = JunitWriter::write("codeagent", &results);
+        assert!(written.contains("<testsuites name=\"codeagent\" tests=\"3\" failures=\"1\" errors=\"0\" skipped=\"1\" time=\"1.512\">"));
+        assert!(written.contains("<testsuite name=\"tests/test_math.py\" tests=\"2\" failures=\"1\""));
+        let reparsed = JunitParser::parse(&written, "pytest").unwrap();
+        for (before, after) in results.iter().zip(&reparsed) {
+            assert_eq!(
+                (&before.name, &before.file, &before.status, before.duration_ms, &before.error_message, &before.stdout),
+                (&after.name, &after.file, &after.status, after.duration_ms, &after.error_message, &after.stdout)
+            );
+        }
+    }
+
+    #[test]
+    fn test_pytest_junit_classnames() {
+        let xml = r#"<testsuites><testsuite name="pytest">
+<testcase classname="tests.test_math.TestMath" name="test_subs" time="0.25"><failure message="assert 3 == 4">self = &lt;tests.test_math.TestMath object&gt;
+
+    def test_subs(self):
+&gt;       assert sub(7, 4) == 4
+E       assert 3 == 4
+
+tests/test_math.py:12: AssertionError</failure></testcase>
+<testcase classname="tests.test_math" name="test_bug" time="0.001"><skipped type="pytest.xfail" message="issue 7"/></testcase>
+</testsuite></testsuites>"#;
+        let results = JunitParser::parse(xml, "pytest").unwrap();
+        assert_eq!((results[0].file.as_str(), results[0].name.as_str()), ("tests/test_math.py", "TestMath::test_subs"));
+        assert_eq!((results[0].line, results[0].error_message.as_deref()), (Some(12), Some("assert 3 == 4")));
+        assert_eq!((results[1].file.as_str(), results[1].error_message.as_deref()), ("tests/test_math.py", Some("XFAIL: issue 7")));
+    }
+
+    #[test]
+    fn test_nextest_junit_reads_rust_panics() {
+        let xml = "<testsuites name=\"nextest-run\"><testsuite name=\"demo\"><testcase name=\"tests::subs\" classname=\"demo\" time=\"0.120\"><failure type=\"test failure\">thread 'tests::subs' panicked at src/lib.rs:20:5:\nassertion failed: ok\nnote: run with `RUST_BACKTRACE=1`</failure></testcase></testsuite></testsuites>";
+        let mut results = JunitParser::parse(xml, "Cargo").unwrap();
+        assert_eq!((results[0].file.as_str(), results[0].line, results[0].column), ("src/lib.rs", Some(20), Some(5)));
+        assert_eq!(results[0].error_message.as_deref(), Some("assertion failed: ok"));
+
+        results[0].error_message = Some("assertion `left == right` failed\n  left: 3\n right: 4".to_string());
+        results[0].stack_trace = None;
+
+        results[0].stdout = Some("\x1b[31mrunning 1 test\x1b[0m".to_string());
+        let written = JunitWriter::write("demo", &results);
+        assert!(!written.contains('\x1b'));
+        let reparsed = JunitParser::parse(&written, "Cargo").unwrap();
+        assert_eq!(reparsed[0].error_message, results[0].error_message);
+    }
+}
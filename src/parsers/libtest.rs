@@ -0,0 +1,307 @@
+use super::test_parser::{TestResult, TestStatus};
+use regex::Regex;
+use serde::Deserialize;
+
+const FRAMEWORK: &str = "Cargo";
+
+#[derive(Deserialize)]
+struct LibtestEvent {
+    #[serde(rename = "type")]
+    kind: String,
+    event: String,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    exec_time: Option<f64>,
+    #[serde(default)]
+    stdout: Option<String>,
+    #[serde(default)]
+    message: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PanicInfo {
+    pub message: String,
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Output of `cargo test` in its text or `--format json` form, and of `cargo nextest run`.
+pub struct LibtestParser;
+
+impl LibtestParser {
+    pub fn parse(output: &str) -> Vec<TestResult> {
+        let json = Self::parse_json(output);
+        if !json.is_empty() {
+            return json;
+        }
+        let nextest = Self::parse_nextest(output);
+        if !nextest.is_empty() {
+            return nextest;
+        }
+        Self::parse_text(output)
+    }
+
+    /// libtest's JSON events (`-Z unstable-options --format json --report-time`), which
+    /// nextest also emits with `--message-format libtest-json`.
+    pub fn parse_json(output: &str) -> Vec<TestResult> {
+        let mut results = Vec::new();
+        for line in output.lines().map(str::trim).filter(|l| l.starts_with('{')) {
+            let Ok(event) = serde_json::from_str::<LibtestEvent>(line) else {
+                continue;
+            };
+            if event.kind != "test" {
+                continue;
+            }
+            let status = match event.event.as_str() {
+                "ok" => TestStatus::Passed,
+                "failed" => TestStatus::Failed,
+                "ignored" => TestStatus::Skipped,
+                _ => continue,
+            };
+            let Some(name) = event.name else {
+                continue;
+            };
+            // nextest prefixes names with the binary ID: `my-crate::tests$module::test`.
+            let name = name.rsplit_once('$').map(|(_, n)| n.to_string()).unwrap_or(name);
+
+            let mut result = Self::result(&name, status);
+            result.duration_ms = event.exec_time.map(|s| (s * 1000.0).round() as u64).unwrap_or(0);
+            match result.status {
+                TestStatus::Failed => {
+                    let captured = event.stdout.unwrap_or_default();
+                    Self::attach_failure(&mut result, &captured, event.message.as_deref());
+                }
+                _ => {
+                    result.error_message = event.message;
+                    result.stdout = event.stdout.filter(|s| !s.is_empty());
+                }
+            }
+            results.push(result);
+        }
+        results
+    }
+
+    pub fn parse_nextest(output: &str) -> Vec<TestResult> {
+        let status_re = Regex::new(
+            r"^\s*(?:TRY \d+ )?(PASS|FAIL|FLAKY|SKIP|TIMEOUT|LEAK|LEAK-FAIL|ABORT|SIG[A-Z]+)\s+\[\s*(?:([\d.]+)s)?\s*\]\s+(\S+)\s+(\S+)\s*$",
+        )
+        .unwrap();
+        let section_re = Regex::new(r"^\s*(?:---|───)\s*STD(OUT|ERR):\s+(\S+)\s+(\S+)\s*(?:---|───)\s*$").unwrap();
+
+        let mut results: Vec<TestResult> = Vec::new();
+        let mut sections: Vec<(String, String)> = Vec::new();
+        let mut current: Option<(String, String)> = None;
+        for line in output.lines() {
+            if let Some(cap) = status_re.captures(line) {
+                if let Some(section) = current.take() {
+                    sections.push(section);
+                }
+                let status = match &cap[1] {
+                    "PASS" | "FLAKY" | "LEAK" => TestStatus::Passed,
+                    "SKIP" => TestStatus::Skipped,
+                    _ => TestStatus::Failed,
+                };
+                let name = cap[4].to_string();
+                let mut result = Self::result(&name, status);
+                result.duration_ms = cap
+                    .get(2)
+                    .and_then(|d| d.as_str().parse::<f64>().ok())
+                    .map(|s| (s * 1000.0).round() as u64)
+                    .unwrap_or(0);
+                if result.status == TestStatus::Failed && &cap[1] != "FAIL" {
+                    result.error_message = Some(format!("Test ended with {}", &cap[1]));
+                }
+                // Retries report the same test again; the last attempt wins.
+                results.retain(|r| r.name != name);
+                results.push(result);
+            } else if let Some(cap) = section_re.captures(line) {
+                if let Some(section) = current.take() {
+                    sections.push(section);
+                }
+                current = Some((cap[3].to_string(), String::new()));
+            } else if let Some((_, text)) = current.as_mut() {
+                if line.trim_start().starts_with("------------") || line.trim_start().starts_with("Summary [") {
+                    sections.push(current.take().unwrap_or_default());
+                } else {
+                    text.push_str(line);
+                    text.push('\n');
+                }
+            }
+        }
+        if let Some(section) = current.take() {
+            sections.push(section);
+        }
+
+        for result in &mut results {
+            let captured: String = sections
+                .iter()
+                .filter(|(name, _)| *name == result.name)
+                .map(|(_, text)| text.as_str())
+                .collect();
+            if result.status == TestStatus::Failed {
+                let message = result.error_message.take();
+                Self::attach_failure(result, &captured, message.as_deref());
+            } else if !captured.trim().is_empty() {
+                result.stdout = Some(captured);
+            }
+        }
+        results
+    }
+
+    pub fn parse_text(output: &str) -> Vec<TestResult> {
+        let test_re = Regex::new(r"^test (.+?) \.\.\. (ok|FAILED|ignored(?:, (.*?))?)(?: <([\d.]+)s>)?\s*$").unwrap();
+
+        let mut results = Vec::new();
+        for line in output.lines() {
+            let Some(cap) = test_re.captures(line) else {
+                continue;
+            };
+            let status = match &cap[2] {
+                "ok" => TestStatus::Passed,
+                "FAILED" => TestStatus::Failed,
+                _ => TestStatus::Skipped,
+            };
+            let mut result = Self::result(&cap[1], status);
+            result.duration_ms = cap
+                .get(4)
+                .and_then(|d| d.as_str().parse::<f64>().ok())
+                .map(|s| (s * 1000.0).round() as u64)
+                .unwrap_or(0);
+            if let Some(reason) = cap.get(3) {
+                result.error_message = Some(reason.as_str().to_string());
+            }
+            results.push(result);
+        }
+
+        for result in results.iter_mut().filter(|r| r.status == TestStatus::Failed) {
+            let captured = Self::captured_section(output, &result.name).unwrap_or_default();
+            Self::attach_failure(result, &captured, None);
+        }
+        results
+    }
+
+    /// The text under `---- name stdout ----` in the failures summary.
+    fn captured_section(output: &str, name: &str) -> Option<String> {
+        let header = format!("---- {} stdout ----", name);
+        let start = output.find(&header)? + header.len();
+        let section = &output[start..];
+        let end = section
+            .find("\n---- ")
+            .or_else(|| section.find("\nfailures:"))
+            .unwrap_or(section.len());
+        Some(section[..end].trim_matches('\n').to_string())
+    }
+
+    pub fn parse_panic(captured: &str) -> Option<PanicInfo> {
+        // Rust 1.73+ prints the location and puts the message on the following lines;
+        // older toolchains quote the message before the location.
+        let panic_re = Regex::new(r"panicked at (?:'((?s).*?)', )?([^\s:]+(?::[^\s:]+)*?):(\d+):(\d+):?").unwrap();
+        let cap = panic_re.captures(captured)?;
+        let message = match cap.get(1) {
+            Some(message) => message.as_str().to_string(),
+            None => captured[cap.get(0)?.end()..]
+                .lines()
+                .skip_while(|l| l.trim().is_empty())
+                .take_while(|l| !l.starts_with("note: ") && !l.starts_with("stack backtrace:"))
+                .collect::<Vec<_>>()
+                .join("\n")
+                .trim_end()
+                .to_string(),
+        };
+        Some(PanicInfo {
+            message,
+            file: cap[2].to_string(),
+            line: cap[3].parse().ok()?,
+            column: cap[4].parse().ok()?,
+        })
+    }
+
+    fn attach_failure(result: &mut TestResult, captured: &str, message: Option<&str>) {
+        if let Some(panic) = Self::parse_panic(captured) {
+            result.file = panic.file;
+            result.line = Some(panic.line);
+            result.column = Some(panic.column);
+            result.error_message = Some(panic.message);
+        } else {
+            let lines: Vec<&str> = captured
+                .lines()
+                .filter(|l| !l.trim().is_empty() && !l.starts_with("note: "))
+                .collect();
+            if !lines.is_empty() {
+                result.error_message = Some(lines.join("\n"));
+            }
+        }
+        if let Some(message) = message.filter(|m| !m.is_empty()) {
+            result.error_message = Some(match result.error_message.take() {
+                Some(existing) => format!("{}\n{}", message, existing),
+                None => message.to_string(),
+            });
+        }
+        if result.error_message.is_none() {
+            result.error_message = Some("Test failed".to_string());
+        }
+        if let Some(start) = captured.find("stack backtrace:") {
+            result.stack_trace = Some(captured[start..].trim_end().to_string());
+        }
+        if !captured.trim().is_empty() {
+            result.stdout = Some(captured.to_string());
+        }
+    }
+
+    /// Doc-tests are named `src/lib.rs - path::to::item (line 12)`.
+    fn result(name: &str, status: TestStatus) -> TestResult {
+        let doc_re = Regex::new(r"^(.+?\.rs) - .*\(line (\d+)\)").unwrap();
+        let mut result = TestResult::new(name, status, FRAMEWORK);
+        if let Some(cap) = doc_re.captures(name) {
+            result.file = cap[1].to_string();
+            result.line = cap[2].parse().ok();
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_libtest_json_and_text_agree() {
+        let json = r#"{ "type": "suite", "event": "started", "test_count": 4 }
+{ "type": "test", "event": "started", "name": "math::adds" }
+{ "type": "test", "name": "math::adds", "event": "ok", "exec_time": 0.0025 }
+{ "type": "test", "name": "math::subs", "event": "failed", "exec_time": 0.01, "stdout": "computing\nthread 'math::subs' panicked at src/math.rs:12:9:\nassertion `left == right` failed\n  left: 3\n right: 4\nnote: run with `RUST_BACKTRACE=1` environment variable to display a backtrace\n" }
+{ "type": "test", "name": "math::slow", "event": "ignored", "message": "takes minutes" }
+{ "type": "test", "name": "src/lib.rs - add (line 5)", "event": "ok", "exec_time": 0.3 }
+{ "type": "suite", "event": "failed", "passed": 2, "failed": 1, "ignored": 1, "measured": 0, "filtered_out": 0, "exec_time": 0.4 }"#;
+        let text = "running 3 tests\ntest math::adds ... ok\ntest math::slow ... ignored, takes minutes\ntest math::subs ... FAILED\n\nfailures:\n\n---- math::subs stdout ----\ncomputing\nthread 'math::subs' panicked at src/math.rs:12:9:\nassertion `left == right` failed\n  left: 3\n right: 4\nnote: run with `RUST_BACKTRACE=1` environment variable to display a backtrace\n\n\nfailures:\n    math::subs\n\ntest result: FAILED. 1 passed; 1 failed; 1 ignored\n\n   Doc-tests demo\n\nrunning 1 test\ntest src/lib.rs - add (line 5) ... ok\n";
+
+        for results in [LibtestParser::parse(json), LibtestParser::parse(text)] {
+            assert_eq!(results.len(), 4);
+            let failed = results.iter().find(|r| r.status == TestStatus::Failed).unwrap();
+            assert_eq!(failed.name, "math::subs");
+            assert_eq!((failed.file.as_str(), failed.line, failed.column), ("src/math.rs", Some(12), Some(9)));
+            assert_eq!(failed.error_message.as_deref(), Some("assertion `left == right` failed\n  left: 3\n right: 4"));
+            assert!(failed.stdout.as_deref().unwrap().starts_with("computing\n"));
+
+            let skipped = results.iter().find(|r| r.status == TestStatus::Skipped).unwrap();
+            assert_eq!(skipped.error_message.as_deref(), Some("takes minutes"));
+            let doc = results.iter().find(|r| r.name.starts_with("src/lib.rs")).unwrap();
+            assert_eq!((doc.file.as_str(), doc.line), ("src/lib.rs", Some(5)));
+        }
+        assert_eq!(LibtestParser::parse(json)[0].duration_ms, 3);
+    }
+
+    #[test]
+    fn test_nextest_output_and_legacy_panics() {
+        let output = "    Starting 3 tests across 1 binary\n        PASS [   0.004s] demo tests::adds\n        SKIP [         ] demo tests::slow\n        FAIL [   0.120s] demo tests::subs\n--- STDOUT:              demo tests::subs ---\n\nrunning 1 test\nthread 'tests::subs' panicked at 'oops, nope', src/lib.rs:20:5\n\n------------\n     Summary [   0.124s] 3 tests run: 1 passed, 1 failed, 1 skipped\n";
+        let results = LibtestParser::parse(output);
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].duration_ms, 4);
+        assert_eq!(results[1].status, TestStatus::Skipped);
+        assert_eq!(results[2].status, TestStatus::Failed);
+        assert_eq!(results[2].error_message.as_deref(), Some("oops, nope"));
+        assert_eq!((results[2].file.as_str(), results[2].line), ("src/lib.rs", Some(20)));
+    }
+}
@@ -1,6 +1,10 @@
 #![allow(dead_code)]
 
 pub mod test_parser;
+pub mod libtest;
+pub mod jest;
+pub mod pytest;
+pub mod junit;
 pub mod lint_parser;
 
 pub use test_parser::{TestResult, TestStatus};
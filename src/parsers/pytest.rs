@@ -0,0 +1,447 @@
+use super::test_parser::{TestResult, TestStatus};
+use regex::Regex;
+use serde_json::Value;
+use std::collections::HashMap;
+
+const FRAMEWORK: &str = "Pytest";
+
+pub struct PytestParser;
+
+impl PytestParser {
+    /// Prefers report-log lines (`--report-log=/dev/stdout`) when the output has them.
+    pub fn parse(output: &str) -> Vec<TestResult> {
+        let report_log = Self::parse_report_log(output);
+        if !report_log.is_empty() {
+            return report_log;
+        }
+        Self::parse_text(output)
+    }
+
+    /// `tests/test_math.py::TestMath::test_adds[1-2]` → file and the rest as the name.
+    pub fn split_node_id(node_id: &str) -> (String, String) {
+        match node_id.split_once("::") {
+            Some((file, name)) => (file.to_string(), name.to_string()),
+            None => (node_id.to_string(), node_id.to_string()),
+        }
+    }
+
+    pub fn parse_text(output: &str) -> Vec<TestResult> {
+        let verbose_re = Regex::new(
+            r"^(?:\[gw\d+\]\s+\[\s*\d+%\]\s+(PASSED|FAILED|SKIPPED|XFAIL|XPASS|ERROR)\s+(\S.*?)|(\S.*?\.py::\S.*?)\s+(PASSED|FAILED|SKIPPED|XFAIL|XPASS|ERROR)(?:\s+\((.*)\))?)\s*(?:\[\s*\d+%\])?\s*$",
+        )
+        .unwrap();
+        let summary_re = Regex::new(r"^(FAILED|ERROR|XFAIL|XPASS)\s+(\S+\.py(?:::\S+)?)(?:\s+-\s+(.*))?$").unwrap();
+        let duration_re = Regex::new(r"^([\d.]+)s\s+(?:setup|call|teardown)\s+(\S.*?)\s*$").unwrap();
+
+        let mut results: Vec<TestResult> = Vec::new();
+        let mut index: HashMap<String, usize> = HashMap::new();
+        let mut durations: HashMap<String, f64> = HashMap::new();
+        let mut upsert = |node_id: &str, outcome: &str, reason: Option<&str>, results: &mut Vec<TestResult>| {
+            match index.get(node_id) {
+                // The verbose line comes first; the summary only adds the reason.
+                Some(&i) => {
+                    if results[i].error_message.is_none() {
+                        results[i].error_message = Self::outcome_message(outcome, reason);
+                    }
+                }
+                None => {
+                    index.insert(node_id.to_string(), results.len());
+                    results.push(Self::result(node_id, outcome, reason));
+                }
+            }
+        };
+
+        for line in output.lines() {
+            let line = line.trim_end();
+            if let Some(cap) = verbose_re.captures(line) {
+                let (outcome, node_id) = match cap.get(1) {
+                    Some(outcome) => (outcome.as_str(), &cap[2]),
+                    None => (&cap[4], &cap[3]),
+                };
+                upsert(node_id, outcome, cap.get(5).map(|r| r.as_str()), &mut results);
+            } else if let Some(cap) = summary_re.captures(line) {
+                upsert(&cap[2], &cap[1], cap.get(3).map(|r| r.as_str()), &mut results);
+            } else if let Some(cap) = duration_re.captures(line) {
+                *durations.entry(cap[2].to_string()).or_default() += cap[1].parse::<f64>().unwrap_or(0.0);
+            }
+        }
+
+        let sections = Self::failure_sections(output);
+        let mut claimed = vec![false; sections.len()];
+        let mut by_position: Vec<(&String, &usize)> = index.iter().collect();
+        by_position.sort_by_key(|(_, &i)| i);
+        for (node_id, &i) in by_position {
+            let result = &mut results[i];
+            if let Some(seconds) = durations.get(node_id) {
+                result.duration_ms = (seconds * 1000.0).round() as u64;
+            }
+            if result.status != TestStatus::Failed {
+                continue;
+            }
+            // Section headers use the test's domain: `TestMath.test_adds` or `test_adds[1]`,
+            // so tests sharing a name in different files are told apart by their frames.
+            let domain = result.name.replace("::", ".");
+            let candidates: Vec<usize> = (0..sections.len())
+                .filter(|&s| !claimed[s] && (sections[s].0 == domain || sections[s].0 == result.name))
+                .collect();
+            let Some(&section) = candidates
+                .iter()
+                .find(|&&s| Self::locations(&sections[s].2).iter().any(|(path, _)| Self::same_file(path, &result.file)))
+                .or(candidates.first())
+            else {
+                continue;
+            };
+            claimed[section] = true;
+            let (_, phase, traceback) = &sections[section];
+            Self::attach_failure(result, traceback);
+            if let (Some(phase), Some(message)) = (phase, result.error_message.as_mut()) {
+                *message = format!("error in {}: {}", phase, message);
+            }
+        }
+        results
+    }
+
+    /// The `____ name ____` blocks under the FAILURES and ERRORS headings, with the
+    /// phase for fixture errors.
+    fn failure_sections(output: &str) -> Vec<(String, Option<String>, String)> {
+        let header_re = Regex::new(r"^_{3,} (?:ERROR at (setup|teardown) of )?(.+?) _{3,}$").unwrap();
+        let heading_re = Regex::new(r"^={3,}.*={3,}$").unwrap();
+
+        let mut sections = Vec::new();
+        let mut current: Option<(String, Option<String>, String)> = None;
+        for line in output.lines() {
+            if let Some(cap) = header_re.captures(line) {
+                sections.extend(current.take());
+                current = Some((cap[2].to_string(), cap.get(1).map(|p| p.as_str().to_string()), String::new()));
+            } else if heading_re.is_match(line) {
+                sections.extend(current.take());
+            } else if let Some((_, _, text)) = current.as_mut() {
+                text.push_str(line);
+                text.push('\n');
+            }
+        }
+        sections.extend(current);
+        sections
+    }
+
+    /// Fills in the `E` lines, captured stdout and the full traceback from a failure. The
+    /// crash line is kept only when it is in the test's own file.
+    pub fn attach_failure(result: &mut TestResult, traceback: &str) {
+        let captured_re = Regex::new(r"^-+ Captured (\w+) (\w+) -+$").unwrap();
+
+        let mut traceback_lines = Vec::new();
+        let mut stdout = String::new();
+        let mut capturing: Option<bool> = None;
+        for line in traceback.lines() {
+            if let Some(cap) = captured_re.captures(line) {
+                capturing = Some(&cap[1] == "stdout");
+                continue;
+            }
+            match capturing {
+                Some(true) => {
+                    stdout.push_str(line);
+                    stdout.push('\n');
+                }
+                Some(false) => {}
+                None => traceback_lines.push(line),
+            }
+        }
+
+        let errors: Vec<&str> = traceback_lines
+            .iter()
+            .filter_map(|l| l.strip_prefix("E ").map(|e| e.trim_start()))
+            .collect();
+        if !errors.is_empty() {
+            result.error_message = Some(errors.join("\n"));
+        }
+        let traceback = traceback_lines.join("\n").trim_matches('\n').to_string();
+        if let Some(&(path, line)) = Self::locations(&traceback).last() {
+            if Self::same_file(path, &result.file) {
+                result.line = Some(line);
+            }
+        }
+        if !traceback.is_empty() {
+            result.stack_trace = Some(traceback);
+        }
+        if !stdout.is_empty() {
+            result.stdout = Some(stdout);
+        }
+    }
+
+    /// The `path:line:` frame locations in a traceback, innermost last.
+    fn locations(traceback: &str) -> Vec<(&str, usize)> {
+        let location_re = Regex::new(r"^(\S+\.py):(\d+): ").unwrap();
+        traceback
+            .lines()
+            .filter_map(|line| {
+                let cap = location_re.captures(line)?;
+                Some((cap.get(1)?.as_str(), cap[2].parse().ok()?))
+            })
+            .collect()
+    }
+
+    /// Tracebacks may use absolute paths where node IDs are relative to the rootdir.
+    fn same_file(path: &str, file: &str) -> bool {
+        let (path, file) = (path.replace('\\', "/"), file.replace('\\', "/"));
+        !file.is_empty() && (path == file || path.ends_with(&format!("/{}", file)) || file.ends_with(&format!("/{}", path)))
+    }
+
+    /// pytest-reportlog's JSON lines, one report per setup/call/teardown phase.
+    pub fn parse_report_log(output: &str) -> Vec<TestResult> {
+        let mut results: Vec<TestResult> = Vec::new();
+        let mut index: HashMap<String, usize> = HashMap::new();
+        for line in output.lines().map(str::trim).filter(|l| l.starts_with('{')) {
+            let Ok(report) = serde_json::from_str::<Value>(line) else {
+                continue;
+            };
+            let report_type = report["$report_type"].as_str().unwrap_or_default();
+            if report_type != "TestReport" && report_type != "CollectReport" {
+                continue;
+            }
+            let outcome = report["outcome"].as_str().unwrap_or_default();
+            let Some(node_id) = report["nodeid"].as_str() else {
+                continue;
+            };
+            if report_type == "CollectReport" && outcome != "failed" {
+                continue;
+            }
+
+            let i = *index.entry(node_id.to_string()).or_insert_with(|| {
+                let mut result = Self::result(node_id, "PASSED", None);
+                // `location` line numbers are 0-based.
+                if let Some(line) = report["location"][1].as_u64() {
+                    result.line = Some(line as usize + 1);
+                }
+                results.push(result);
+                results.len() - 1
+            });
+            let result = &mut results[i];
+            result.duration_ms += (report["duration"].as_f64().unwrap_or(0.0) * 1000.0).round() as u64;
+
+            let when = report["when"].as_str().unwrap_or("collect");
+            let xfail = report.get("wasxfail").and_then(|w| w.as_str());
+            let outcome = match (outcome, xfail) {
+                ("skipped", Some(_)) => "XFAIL",
+                ("passed", Some(_)) if when == "call" => "XPASS",
+                ("passed", _) => continue,
+                ("skipped", None) => "SKIPPED",
+                _ if when == "call" => "FAILED",
+                _ => "ERROR",
+            };
+            let longrepr = &report["longrepr"];
+            let reason = match longrepr {
+                // Skips carry `[path, line, "Skipped: reason"]`.
+                Value::Array(parts) => parts.get(2).and_then(|r| r.as_str()).map(|r| r.trim_start_matches("Skipped: ").to_string()),
+                _ => xfail.filter(|r| !r.is_empty()).map(|r| r.to_string()),
+            };
+            let fresh = Self::result(node_id, outcome, reason.as_deref());
+            result.status = fresh.status;
+            result.error_message = fresh.error_message;
+
+            if result.status == TestStatus::Failed {
+                Self::attach_longrepr(result, longrepr, when);
+            }
+            for section in report["sections"].as_array().into_iter().flatten() {
+                if let (Some(title), Some(text)) = (section[0].as_str(), section[1].as_str()) {
+                    if title.starts_with("Captured stdout") {
+                        result.stdout.get_or_insert_with(String::new).push_str(text);
+                    }
+                }
+            }
+        }
+        results
+    }
+
+    fn attach_longrepr(result: &mut TestResult, longrepr: &Value, when: &str) {
+        let crash = &longrepr["reprcrash"];
+        let location = match (crash["path"].as_str(), crash["lineno"].as_u64()) {
+            (Some(path), Some(line)) => {
+                if Self::same_file(path, &result.file) {
+                    result.line = Some(line as usize);
+                }
+                Some(format!("{}:{}: {}", path, line, crash["message"].as_str().and_then(|m| m.lines().next()).unwrap_or_default()))
+            }
+            _ => None,
+        };
+        let entries: Vec<String> = longrepr["reprtraceback"]["reprentries"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry["data"]["lines"].as_array())
+            .map(|lines| lines.iter().filter_map(|l| l.as_str()).collect::<Vec<_>>().join("\n"))
+            .collect();
+        let traceback = match (longrepr, location) {
+            (Value::String(text), _) => text.clone(),
+            (_, Some(location)) => format!("{}\n\n{}", entries.join("\n\n"), location).trim_start().to_string(),
+            (_, None) => entries.join("\n\n"),
+        };
+        let errors: Vec<&str> = traceback
+            .lines()
+            .filter_map(|l| l.strip_prefix("E ").map(|e| e.trim_start()))
+            .collect();
+        let message = if !errors.is_empty() {
+            Some(errors.join("\n"))
+        } else {
+            crash["message"].as_str().map(|m| m.to_string())
+        };
+        if let Some(message) = message {
+            result.error_message = Some(match when {
+                "setup" | "teardown" | "collect" => format!("error in {}: {}", when, message),
+                _ => message,
+            });
+        }
+        if !traceback.is_empty() {
+            result.stack_trace = Some(traceback);
+        }
+    }
+
+    fn result(node_id: &str, outcome: &str, reason: Option<&str>) -> TestResult {
+        let status = match outcome {
+            "PASSED" | "XPASS" => TestStatus::Passed,
+            "SKIPPED" | "XFAIL" => TestStatus::Skipped,
+            _ => TestStatus::Failed,
+        };
+        let (file, name) = Self::split_node_id(node_id);
+        let mut result = TestResult::new(&name, status, FRAMEWORK);
+        result.file = file;
+        result.error_message = Self::outcome_message(outcome, reason);
+        result
+    }
+
+    /// XFAIL/XPASS and ERROR would be lost in the status alone, so they lead the message.
+    fn outcome_message(outcome: &str, reason: Option<&str>) -> Option<String> {
+        let reason = reason.map(str::trim).filter(|r| !r.is_empty());
+        match (outcome, reason) {
+            ("XFAIL" | "XPASS" | "ERROR", Some(reason)) => Some(format!("{}: {}", outcome, reason)),
+            ("XFAIL" | "XPASS" | "ERROR", None) => Some(outcome.to_string()),
+            (_, reason) => reason.map(|r| r.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verbose_output_with_sections_and_durations() {
+        let output = "\
+============================= test session starts ==============================
+collected 6 items
+
+tests/test_math.py::TestMath::test_adds PASSED                           [ 16%]
+tests/test_math.py::TestMath::test_subs FAILED                           [ 33%]
+tests/test_math.py::test_div[0] FAILED                                   [ 50%]
+tests/test_math.py::test_slow SKIPPED (needs network)                    [ 66%]
+tests/test_math.py::test_known_bug XFAIL (issue 12)                      [ 83%]
+tests/test_db.py::test_query ERROR                                       [100%]
+
+==================================== ERRORS ====================================
+________________________ ERROR at setup of test_query _________________________
+
+    @pytest.fixture
+    def db():
+>       raise ConnectionError(\"no database\")
+E       ConnectionError: no database
+
+tests/conftest.py:8: ConnectionError
+=================================== FAILURES ===================================
+______________________________ TestMath.test_subs ______________________________
+
+self = <tests.test_math.TestMath object at 0x7f>
+
+    def test_subs(self):
+>       assert sub(7, 4) == 4
+E       assert 3 == 4
+E        +  where 3 = sub(7, 4)
+
+tests/test_math.py:12: AssertionError
+----------------------------- Captured stdout call -----------------------------
+subtracting 4 from 7
+_________________________________ test_div[0] __________________________________
+
+    def test_div(d):
+>       assert 1 / d
+E       ZeroDivisionError: division by zero
+
+tests/test_math.py:20: ZeroDivisionError
+============================= slowest durations ==============================
+0.25s call     tests/test_math.py::TestMath::test_subs
+0.10s setup    tests/test_math.py::TestMath::test_subs
+=========================== short test summary info ============================
+FAILED tests/test_math.py::TestMath::test_subs - assert 3 == 4
+FAILED tests/test_math.py::test_div[0] - ZeroDivisionError: division by zero
+ERROR tests/test_db.py::test_query - ConnectionError: no database
+=============== 2 failed, 1 passed, 1 skipped, 1 xfailed, 1 error in 0.40s =====
+";
+        let results = PytestParser::parse(output);
+        assert_eq!(results.len(), 6);
+        assert_eq!((results[0].file.as_str(), results[0].name.as_str()), ("tests/test_math.py", "TestMath::test_adds"));
+
+        let subs = &results[1];
+        assert_eq!((subs.line, subs.duration_ms), (Some(12), 350));
+        assert_eq!(subs.error_message.as_deref(), Some("assert 3 == 4\n+  where 3 = sub(7, 4)"));
+        assert_eq!(subs.stdout.as_deref(), Some("subtracting 4 from 7\n"));
+        assert_eq!(results[2].error_message.as_deref(), Some("ZeroDivisionError: division by zero"));
+        assert_eq!(results[2].line, Some(20));
+
+        assert_eq!((&results[3].status, results[3].error_message.as_deref()), (&TestStatus::Skipped, Some("needs network")));
+        assert_eq!(results[4].error_message.as_deref(), Some("XFAIL: issue 12"));
+        assert_eq!(results[5].status, TestStatus::Failed);
+        assert_eq!((results[5].file.as_str(), results[5].line), ("tests/test_db.py", None));
+        assert!(results[5].stack_trace.as_deref().unwrap().ends_with("tests/conftest.py:8: ConnectionError"));
+        assert_eq!(results[5].error_message.as_deref(), Some("error in setup: ConnectionError: no database"));
+    }
+
+    #[test]
+    fn test_same_test_name_in_two_files_gets_its_own_traceback() {
+        let output = "\
+tests/a.py::test_x FAILED                                                [ 50%]
+tests/b.py::test_x FAILED                                                [100%]
+
+=================================== FAILURES ===================================
+____________________________________ test_x ____________________________________
+
+    def test_x():
+>       assert load() == 2
+E       assert 1 == 2
+
+tests/b.py:7: AssertionError
+____________________________________ test_x ____________________________________
+
+    def test_x():
+>       check()
+
+tests/a.py:4: in test_x
+    check()
+    def check():
+>       raise ValueError(\"bad\")
+E       ValueError: bad
+
+tests/helpers.py:2: ValueError
+";
+        let results = PytestParser::parse(output);
+        assert_eq!((results[0].file.as_str(), results[0].line), ("tests/a.py", None));
+        assert_eq!(results[0].error_message.as_deref(), Some("ValueError: bad"));
+        assert_eq!((results[1].file.as_str(), results[1].line), ("tests/b.py", Some(7)));
+        assert_eq!(results[1].error_message.as_deref(), Some("assert 1 == 2"));
+    }
+
+    #[test]
+    fn test_report_log() {
+        let output = r#"{"pytest_version": "8.0.0", "$report_type": "SessionStart"}
+{"nodeid": "tests/test_math.py::test_adds", "location": ["tests/test_math.py", 3, "test_adds"], "when": "setup", "outcome": "passed", "duration": 0.001, "longrepr": null, "sections": [], "$report_type": "TestReport"}
+{"nodeid": "tests/test_math.py::test_adds", "location": ["tests/test_math.py", 3, "test_adds"], "when": "call", "outcome": "failed", "duration": 0.02, "longrepr": {"reprcrash": {"path": "/repo/tests/test_math.py", "lineno": 5, "message": "assert 3 == 4"}, "reprtraceback": {"reprentries": [{"type": "ReprEntry", "data": {"lines": ["    def test_adds():", ">       assert add(1, 2) == 4", "E       assert 3 == 4"]}}]}}, "sections": [["Captured stdout call", "adding\n"]], "$report_type": "TestReport"}
+{"nodeid": "tests/test_math.py::test_skip", "location": ["tests/test_math.py", 9, "test_skip"], "when": "setup", "outcome": "skipped", "duration": 0.0, "longrepr": ["tests/test_math.py", 10, "Skipped: later"], "sections": [], "$report_type": "TestReport"}
+{"nodeid": "tests/test_math.py::test_bug", "location": ["tests/test_math.py", 12, "test_bug"], "when": "call", "outcome": "skipped", "duration": 0.0, "wasxfail": "issue 7", "longrepr": null, "sections": [], "$report_type": "TestReport"}"#;
+        let results = PytestParser::parse(output);
+        assert_eq!(results.len(), 3);
+        assert_eq!((results[0].name.as_str(), &results[0].status, results[0].duration_ms), ("test_adds", &TestStatus::Failed, 21));
+        assert_eq!((results[0].file.as_str(), results[0].line), ("tests/test_math.py", Some(5)));
+        assert!(results[0].stack_trace.as_deref().unwrap().ends_with("/repo/tests/test_math.py:5: assert 3 == 4"));
+        assert_eq!(results[0].error_message.as_deref(), Some("assert 3 == 4"));
+        assert_eq!(results[0].stdout.as_deref(), Some("adding\n"));
+        assert_eq!((&results[1].status, results[1].error_message.as_deref()), (&TestStatus::Skipped, Some("later")));
+        assert_eq!(results[2].error_message.as_deref(), Some("XFAIL: issue 7"));
+    }
+}
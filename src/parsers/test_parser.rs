@@ -1,4 +1,7 @@
 use regex::Regex;
+use super::jest::JestParser;
+use super::libtest::LibtestParser;
+use super::pytest::PytestParser;
 use serde::{Serialize, Deserialize};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -19,94 +22,38 @@ pub struct TestResult {
     pub framework: String,
     pub file: String,
     pub line: Option<usize>,
+    #[serde(default)]
+    pub column: Option<usize>,
+    #[serde(default)]
+    pub stdout: Option<String>,
+}
+
+impl TestResult {
+    pub fn new(name: &str, status: TestStatus, framework: &str) -> Self {
+        TestResult {
+            name: name.to_string(),
+            status,
+            duration_ms: 0,
+            error_message: None,
+            stack_trace: None,
+            framework: framework.to_string(),
+            file: String::new(),
+            line: None,
+            column: None,
+            stdout: None,
+        }
+    }
 }
 
 pub struct TestParser;
 
 impl TestParser {
     pub fn parse_jest_output(output: &str) -> Vec<TestResult> {
-        let mut results = Vec::new();
-
-        let pass_re = Regex::new(r"✓\s+(.+?)\s+\((\d+)\s*ms\)").unwrap();
-        let fail_re = Regex::new(r"✕\s+(.+?)[\s\n]").unwrap();
-        let _error_re = Regex::new(r"●\s+(.+?)[\s\n](.+?)(?:at\s+(.+?))?(?:$|\n)").unwrap();
-
-        for cap in pass_re.captures_iter(output) {
-            if let (Some(name), Some(duration)) = (cap.get(1), cap.get(2)) {
-                let duration_ms = duration.as_str().parse::<u64>().unwrap_or(0);
-                results.push(TestResult {
-                    name: name.as_str().to_string(),
-                    status: TestStatus::Passed,
-                    duration_ms,
-                    error_message: None,
-                    stack_trace: None,
-                    framework: "Jest".to_string(),
-                    file: "test.js".to_string(),
-                    line: None,
-                });
-            }
-        }
-
-        for cap in fail_re.captures_iter(output) {
-            if let Some(name) = cap.get(1) {
-                results.push(TestResult {
-                    name: name.as_str().to_string(),
-                    status: TestStatus::Failed,
-                    duration_ms: 0,
-                    error_message: Some("Test failed".to_string()),
-                    stack_trace: None,
-                    framework: "Jest".to_string(),
-                    file: "test.js".to_string(),
-                    line: None,
-                });
-            }
-        }
-
-        results
+        JestParser::parse(output)
     }
 
     pub fn parse_pytest_output(output: &str) -> Vec<TestResult> {
-        let mut results = Vec::new();
-
-        let pass_re = Regex::new(r"([\w:]+)\s+PASSED\s*\[(\d+)%\]").unwrap();
-        let fail_re = Regex::new(r"([\w:]+)\s+FAILED").unwrap();
-        let error_re = Regex::new(r"(AssertionError|Error):\s+(.+?)(?:\n|$)").unwrap();
-
-        for cap in pass_re.captures_iter(output) {
-            if let Some(name) = cap.get(1) {
-                results.push(TestResult {
-                    name: name.as_str().to_string(),
-                    status: TestStatus::Passed,
-                    duration_ms: 0,
-                    error_message: None,
-                    stack_trace: None,
-                    framework: "Pytest".to_string(),
-                    file: "test.py".to_string(),
-                    line: None,
-                });
-            }
-        }
-
-        for cap in fail_re.captures_iter(output) {
-            if let Some(name) = cap.get(1) {
-                let error = error_re
-                    .captures(output)
-                    .and_then(|c| c.get(2).map(|m| m.as_str().to_string()));
-
-                results.push(TestResult {
-                    name: name.as_str().to_string(),
-                    status: TestStatus::Failed,
-                    duration_ms: 0,
-                    error_message: error,
-                    stack_trace: None,
-                    framework: "Pytest".to_string(),
-                    file: "test.py".to_string(),
-                    line: None,
-                });
-            }
-        }
-
-        results
+        PytestParser::parse(output)
     }
 
     pub fn parse_go_test_output(output: &str) -> Vec<TestResult> {
@@ -127,6 +74,8 @@ impl TestParser {
                     framework: "Go".to_string(),
                     file: "test.go".to_string(),
                     line: None,
+                    column: None,
+                    stdout: None,
                 });
             }
         }
@@ -142,6 +91,8 @@ impl TestParser {
                     framework: "Go".to_string(),
                     file: "test.go".to_string(),
                     line: None,
+                    column: None,
+                    stdout: None,
                 });
             }
         }
@@ -150,42 +101,7 @@ impl TestParser {
     }
 
     pub fn parse_cargo_test_output(output: &str) -> Vec<TestResult> {
-        let mut results = Vec::new();
-
-        let pass_re = Regex::new(r"test\s+([\w:]+)\s+\.\.\.\s+ok").unwrap();
-        let fail_re = Regex::new(r"test\s+([\w:]+)\s+\.\.\.\s+FAILED").unwrap();
-
-        for cap in pass_re.captures_iter(output) {
-            if let Some(name) = cap.get(1) {
-                results.push(TestResult {
-                    name: name.as_str().to_string(),
-                    status: TestStatus::Passed,
-                    duration_ms: 0,
-                    error_message: None,
-                    stack_trace: None,
-                    framework: "Cargo".to_string(),
-                    file: "test.rs".to_string(),
-                    line: None,
-                });
-            }
-        }
-
-        for cap in fail_re.captures_iter(output) {
-            if let Some(name) = cap.get(1) {
-                results.push(TestResult {
-                    name: name.as_str().to_string(),
-                    status: TestStatus::Failed,
-                    duration_ms: 0,
-                    error_message: Some("Test failed".to_string()),
-                    stack_trace: None,
-                    framework: "Cargo".to_string(),
-                    file: "test.rs".to_string(),
-                    line: None,
-                });
-            }
-        }
-
-        results
+        LibtestParser::parse(output)
     }
 
     pub fn summarize_results(results: &[TestResult]) -> TestSummary {
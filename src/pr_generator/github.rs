@@ -1,6 +1,10 @@
 use super::generator::PRMetadata;
+use crate::core::audit::{AuditEvent, AuditLog};
+use crate::core::policy::PolicyEnforcer;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GitHubPRCreateRequest {
@@ -26,19 +30,41 @@ pub struct GitHubIntegration {
     repo: String,
     base_url: String,
     client: reqwest::Client,
+    policy: Arc<PolicyEnforcer>,
 }
 
 impl GitHubIntegration {
-    pub fn new(token: String, owner: String, repo: String) -> Self {
+    pub fn new(project_root: &Path, token: String, owner: String, repo: String) -> Self {
         GitHubIntegration {
             token,
             owner,
             repo,
             base_url: "https://api.github.com".to_string(),
             client: reqwest::Client::new(),
+            policy: PolicyEnforcer::for_project(project_root),
         }
     }
 
+    pub fn with_policy(mut self, policy: Arc<PolicyEnforcer>) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    async fn send(&self, operation: &str, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        self.policy.check_github(operation)?;
+        let result = request.send().await;
+        let error = match &result {
+            Ok(response) if !response.status().is_success() => Some(format!("HTTP {}", response.status())),
+            Ok(_) => None,
+            Err(e) => Some(e.to_string()),
+        };
+        AuditLog::record(AuditEvent::GitHub {
+            operation: operation.to_string(),
+            error,
+        });
+        Ok(result?)
+    }
+
     pub async fn create_pr(
         &self,
         meta: &PRMetadata,
@@ -49,6 +75,10 @@ impl GitHubIntegration {
             "{}/repos/{}/{}/pulls",
             self.base_url, self.owner, self.repo
         );
+        let operation = format!(
+            "open pull request \"{}\" on {}/{}",
+            meta.title, self.owner, self.repo
+        );
 
         let request = GitHubPRCreateRequest {
             title: meta.title.clone(),
@@ -58,13 +88,12 @@ impl GitHubIntegration {
             draft,
         };
 
-        let response = self.client
+        let builder = self.client
             .post(&url)
             .header("Authorization", format!("Bearer {}", self.token))
             .header("Accept", "application/vnd.github.v3+json")
-            .json(&request)
-            .send()
-            .await?;
+            .json(&request);
+        let response = self.send(&operation, builder).await?;
 
         if response.status().is_success() {
             let pr: GitHubPRResponse = response.json().await?;
@@ -81,6 +110,13 @@ impl GitHubIntegration {
             "{}/repos/{}/{}/pulls/{}/requested_reviewers",
             self.base_url, self.owner, self.repo, pr_number
         );
+        let operation = format!(
+            "request reviewers {} on {}/{}#{}",
+            reviewers.join(", "),
+            self.owner,
+            self.repo,
+            pr_number
+        );
 
         #[derive(Serialize)]
         struct ReviewRequest {
@@ -91,12 +127,11 @@ impl GitHubIntegration {
             reviewers: reviewers.to_vec(),
         };
 
-        let response = self.client
+        let builder = self.client
             .post(&url)
             .header("Authorization", format!("Bearer {}", self.token))
-            .json(&request)
-            .send()
-            .await?;
+            .json(&request);
+        let response = self.send(&operation, builder).await?;
 
         if response.status().is_success() {
             tracing::info!("Added {} reviewers to PR #{}", reviewers.len(), pr_number);
@@ -111,6 +146,13 @@ impl GitHubIntegration {
             "{}/repos/{}/{}/issues/{}/labels",
             self.base_url, self.owner, self.repo, pr_number
         );
+        let operation = format!(
+            "add labels {} to {}/{}#{}",
+            labels.join(", "),
+            self.owner,
+            self.repo,
+            pr_number
+        );
 
         #[derive(Serialize)]
         struct LabelRequest {
@@ -121,12 +163,11 @@ impl GitHubIntegration {
             labels: labels.to_vec(),
         };
 
-        let response = self.client
+        let builder = self.client
             .post(&url)
             .header("Authorization", format!("Bearer {}", self.token))
-            .json(&request)
-            .send()
-            .await?;
+            .json(&request);
+        let response = self.send(&operation, builder).await?;
 
         if response.status().is_success() {
             tracing::info!("Added {} labels to PR #{}", labels.len(), pr_number);
@@ -141,6 +182,7 @@ impl GitHubIntegration {
             "{}/repos/{}/{}/pulls/{}",
             self.base_url, self.owner, self.repo, pr_number
         );
+        let operation = format!("close {}/{}#{}", self.owner, self.repo, pr_number);
 
         #[derive(Serialize)]
         struct CloseRequest {
@@ -151,12 +193,11 @@ impl GitHubIntegration {
             state: "closed".to_string(),
         };
 
-        let response = self.client
+        let builder = self.client
             .patch(&url)
             .header("Authorization", format!("Bearer {}", self.token))
-            .json(&request)
-            .send()
-            .await?;
+            .json(&request);
+        let response = self.send(&operation, builder).await?;
 
         if response.status().is_success() {
             tracing::info!("Closed PR #{}", pr_number);
@@ -1,5 +1,14 @@
-use ratatui::prelude::*;
 use std::collections::VecDeque;
+use std::path::Path;
+use crate::index::SymbolIndex;
+use crate::utils::file_index::FileIndex;
+use crate::utils::fuzzy::{FuzzyMatch, FuzzyMatcher};
+use crate::utils::search::{SearchOptions, SearchResult, SemanticSearch};
+use crate::utils::FileUtils;
+
+const FINDER_MAX_RESULTS: usize = 50;
+const FINDER_PREVIEW_LINES: usize = 40;
+const SEARCH_MAX_RESULTS: usize = 500;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum InputMode {
@@ -7,6 +16,7 @@ pub enum InputMode {
     Insert,
     Command,
     Search,
+    FileFinder,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -56,7 +66,7 @@ pub struct App {
     pub command_history: VecDeque<String>,
     pub command_index: usize,
     pub search_query: String,
-    pub search_results: Vec<(usize, usize)>,
+    pub search_results: Vec<SearchResult>,
     pub current_result: usize,
     
     pub chat_messages: Vec<ChatMessage>,
@@ -72,9 +82,38 @@ pub struct App {
     pub show_help: bool,
     pub ollama_model: String,
     pub available_models: Vec<String>,
+    pub loaded_models: Vec<String>,
+    pub active_model_context: Option<u64>,
+    pub model_pull: Option<ModelPullState>,
+    pub pending_model_action: Option<ModelAction>,
+    pub pending_semantic_query: Option<String>,
+    pub symbol_index: Option<SymbolIndex>,
+    pub file_index: Option<FileIndex>,
+    pub file_finder: Option<FileFinder>,
     pub is_loading: bool,
 }
 
+pub struct FileFinder {
+    pub query: String,
+    pub matches: Vec<FuzzyMatch>,
+    pub selected: usize,
+    pub preview: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModelAction {
+    Switch(String),
+    Delete(String),
+    Refresh,
+}
+
+#[derive(Debug, Clone)]
+pub struct ModelPullState {
+    pub model: String,
+    pub status: String,
+    pub percent: Option<u16>,
+}
+
 #[derive(Clone)]
 pub struct ChatMessage {
     pub author: String,
@@ -195,6 +234,14 @@ impl App {
             show_help: false,
             ollama_model: String::new(),
             available_models: Vec::new(),
+            loaded_models: Vec::new(),
+            active_model_context: None,
+            model_pull: None,
+            pending_model_action: None,
+            pending_semantic_query: None,
+            symbol_index: None,
+            file_index: None,
+            file_finder: None,
             is_loading: false,
         }
     }
@@ -240,6 +287,30 @@ impl App {
                 self.chat_messages.clear();
                 "✓ Cleared".to_string()
             }
+            "models" => {
+                self.pending_model_action = Some(ModelAction::Refresh);
+                "✓ Refreshing models...".to_string()
+            }
+            cmd if cmd.starts_with("model ") => {
+                let model = cmd.trim_start_matches("model ").trim().to_string();
+                let response = if self.available_models.contains(&model) {
+                    format!("✓ Switching to {}", model)
+                } else {
+                    format!("⬇ Pulling {} before switching...", model)
+                };
+                self.pending_model_action = Some(ModelAction::Switch(model));
+                response
+            }
+            cmd if cmd.starts_with("find ") => {
+                let query = cmd.trim_start_matches("find ").trim().to_string();
+                self.pending_semantic_query = Some(query.clone());
+                format!("🔎 Searching for \"{}\"...", query)
+            }
+            cmd if cmd.starts_with("rmmodel ") => {
+                let model = cmd.trim_start_matches("rmmodel ").trim().to_string();
+                self.pending_model_action = Some(ModelAction::Delete(model.clone()));
+                format!("✓ Deleting {}", model)
+            }
             _ => {
                 self.add_notification(
                     "Processing".to_string(),
@@ -255,10 +326,15 @@ impl App {
         "┌─ CodeAgent Help ─────────────────────┐\n\
          │ i       - Insert mode               │\n\
          │ :       - Command palette           │\n\
-         │ /       - Search                    │\n\
+         │ /       - Search (regex)            │\n\
+         │ n / N   - Next / previous match     │\n\
          │ Tab     - Switch tabs               │\n\
          │ Ctrl-s  - Save file                 │\n\
          │ Ctrl-p  - Find file                 │\n\
+         │ m       - Next model                │\n\
+         │ :model  - Switch/pull model         │\n\
+         │ :find   - Semantic code search      │\n\
+         │ g       - Go to definition          │\n\
          │ ?       - Toggle help               │\n\
          │ q       - Quit                      │\n\
          └─────────────────────────────────────┘".to_string()
@@ -284,6 +360,300 @@ impl App {
         };
     }
 
+    pub fn open_file(&mut self, path: &str, line: usize) {
+        let full_path = Path::new(&self.project_path).join(path);
+
+        let tab_index = match self.editor_tabs.iter().position(|t| t.path == path) {
+            Some(idx) => idx,
+            None => {
+                let content = match FileUtils::read_file(&full_path) {
+                    Ok(content) => content,
+                    Err(e) => {
+                        self.add_notification(
+                            "Open failed".to_string(),
+                            format!("{}: {}", path, e),
+                            NotificationLevel::Error,
+                        );
+                        return;
+                    }
+                };
+
+                let name = full_path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_else(|| path.to_string());
+                self.editor_tabs.push(EditorTab {
+                    name,
+                    path: path.to_string(),
+                    content: content
+                        .lines()
+                        .map(|l| CodeLine {
+                            content: l.to_string(),
+                            syntax_type: SyntaxType::Normal,
+                            breakpoint: false,
+                        })
+                        .collect(),
+                    cursor_x: 0,
+                    cursor_y: 0,
+                    scroll_x: 0,
+                    scroll_y: 0,
+                    modified: false,
+                });
+                self.editor_tabs.len() - 1
+            }
+        };
+
+        self.active_editor_tab = tab_index;
+        self.active_tab = Tab::Editor;
+        let tab = &mut self.editor_tabs[tab_index];
+        tab.cursor_y = line.saturating_sub(1).min(tab.content.len().saturating_sub(1));
+        tab.cursor_x = 0;
+        tab.scroll_y = tab.cursor_y.saturating_sub(5);
+        self.update_editor_status();
+    }
+
+    pub fn move_editor_cursor(&mut self, dx: isize, dy: isize) {
+        let Some(tab) = self.editor_tabs.get_mut(self.active_editor_tab) else {
+            return;
+        };
+        if tab.content.is_empty() {
+            return;
+        }
+
+        tab.cursor_y = tab
+            .cursor_y
+            .saturating_add_signed(dy)
+            .min(tab.content.len() - 1);
+        let line_len = tab.content[tab.cursor_y].content.chars().count();
+        tab.cursor_x = tab.cursor_x.saturating_add_signed(dx).min(line_len.saturating_sub(1));
+
+        if tab.cursor_y < tab.scroll_y {
+            tab.scroll_y = tab.cursor_y;
+        } else if tab.cursor_y >= tab.scroll_y + 30 {
+            tab.scroll_y = tab.cursor_y - 29;
+        }
+        self.update_editor_status();
+    }
+
+    pub fn word_under_cursor(&self) -> Option<String> {
+        let tab = self.editor_tabs.get(self.active_editor_tab)?;
+        let line: Vec<char> = tab.content.get(tab.cursor_y)?.content.chars().collect();
+        let is_ident = |c: &char| c.is_alphanumeric() || *c == '_';
+
+        let cursor = tab.cursor_x.min(line.len().saturating_sub(1));
+        if !line.get(cursor).is_some_and(is_ident) {
+            return None;
+        }
+
+        let start = (0..=cursor).rev().take_while(|&i| is_ident(&line[i])).last()?;
+        let end = (cursor..line.len()).take_while(|&i| is_ident(&line[i])).last()?;
+        Some(line[start..=end].iter().collect())
+    }
+
+    pub fn jump_to_definition(&mut self) {
+        let Some(word) = self.word_under_cursor() else {
+            return;
+        };
+
+        let index = self
+            .symbol_index
+            .get_or_insert_with(|| SymbolIndex::load(Path::new(&self.project_path)));
+        if let Err(e) = index.refresh() {
+            tracing::warn!("Symbol index refresh failed: {}", e);
+        }
+
+        let target = index
+            .find_definitions(&word)
+            .first()
+            .map(|s| (s.file.clone(), s.line));
+
+        match target {
+            Some((file, line)) => self.open_file(&file, line),
+            None => self.add_notification(
+                "Go to definition".to_string(),
+                format!("No definition found for {}", word),
+                NotificationLevel::Warning,
+            ),
+        }
+    }
+
+    fn update_editor_status(&mut self) {
+        if let Some(tab) = self.editor_tabs.get(self.active_editor_tab) {
+            self.status_bar.file_info = tab.path.clone();
+            self.status_bar.position = format!("{}:{}", tab.cursor_y + 1, tab.cursor_x + 1);
+        }
+    }
+
+    pub fn run_search(&mut self) {
+        self.search_results.clear();
+        self.current_result = 0;
+        if self.search_query.is_empty() {
+            return;
+        }
+
+        let options = SearchOptions {
+            smart_case: true,
+            before_context: 1,
+            after_context: 1,
+            max_results: Some(SEARCH_MAX_RESULTS),
+            ..SearchOptions::default()
+        };
+        let root = Path::new(&self.project_path);
+
+        match SemanticSearch::search_project_with_options(&self.search_query, root, &options) {
+            Ok(results) if results.is_empty() => self.add_notification(
+                "Search".to_string(),
+                format!("No matches for /{}", self.search_query),
+                NotificationLevel::Warning,
+            ),
+            Ok(results) => {
+                self.add_notification(
+                    "Search".to_string(),
+                    format!(
+                        "{} matches in {} files (n/N to navigate)",
+                        results.len(),
+                        SemanticSearch::group_by_file(results.clone()).len()
+                    ),
+                    NotificationLevel::Info,
+                );
+                self.search_results = results;
+                self.jump_to_result(0);
+            }
+            Err(e) => self.add_notification(
+                "Search".to_string(),
+                format!("Invalid pattern: {}", e),
+                NotificationLevel::Error,
+            ),
+        }
+    }
+
+    pub fn next_result(&mut self, delta: isize) {
+        if self.search_results.is_empty() {
+            return;
+        }
+        let len = self.search_results.len() as isize;
+        let next = (self.current_result as isize + delta).rem_euclid(len) as usize;
+        self.jump_to_result(next);
+    }
+
+    pub fn clear_search(&mut self) {
+        self.search_results.clear();
+        self.current_result = 0;
+    }
+
+    fn jump_to_result(&mut self, idx: usize) {
+        let Some(result) = self.search_results.get(idx).cloned() else {
+            return;
+        };
+        self.current_result = idx;
+
+        let relative = result
+            .file
+            .strip_prefix(&self.project_path)
+            .unwrap_or(&result.file)
+            .to_string_lossy()
+            .to_string();
+        self.open_file(&relative, result.line_number);
+
+        if let Some(tab) = self.editor_tabs.get_mut(self.active_editor_tab) {
+            let first_line = result.content.lines().next().unwrap_or_default();
+            tab.cursor_x = first_line
+                .get(..result.match_position)
+                .map(|prefix| prefix.chars().count())
+                .unwrap_or(0);
+        }
+        self.update_editor_status();
+    }
+
+    pub fn open_file_finder(&mut self) {
+        match self.file_index.as_mut() {
+            Some(index) => index.refresh_if_stale(),
+            None => self.file_index = Some(FileIndex::build(Path::new(&self.project_path))),
+        }
+
+        self.file_finder = Some(FileFinder {
+            query: String::new(),
+            matches: Vec::new(),
+            selected: 0,
+            preview: Vec::new(),
+        });
+        self.input_mode = InputMode::FileFinder;
+        self.status_bar.mode = "FIND FILE".to_string();
+        self.update_file_finder();
+    }
+
+    pub fn close_file_finder(&mut self) {
+        self.file_finder = None;
+        self.input_mode = InputMode::Normal;
+        self.status_bar.mode = "NORMAL".to_string();
+    }
+
+    pub fn update_file_finder(&mut self) {
+        let (Some(finder), Some(index)) = (self.file_finder.as_mut(), self.file_index.as_ref()) else {
+            return;
+        };
+
+        finder.matches = FuzzyMatcher::rank(&finder.query, index.files(), FINDER_MAX_RESULTS);
+        finder.selected = 0;
+        self.load_finder_preview();
+    }
+
+    pub fn move_finder_selection(&mut self, delta: isize) {
+        if let Some(finder) = self.file_finder.as_mut() {
+            if finder.matches.is_empty() {
+                return;
+            }
+            let len = finder.matches.len() as isize;
+            finder.selected = (finder.selected as isize + delta).rem_euclid(len) as usize;
+        }
+        self.load_finder_preview();
+    }
+
+    pub fn accept_file_finder(&mut self) {
+        let selected = self
+            .file_finder
+            .as_ref()
+            .and_then(|f| f.matches.get(f.selected))
+            .map(|m| m.path.clone());
+
+        self.close_file_finder();
+        if let Some(path) = selected {
+            self.open_file(&path, 1);
+        }
+    }
+
+    fn load_finder_preview(&mut self) {
+        let root = Path::new(&self.project_path).to_path_buf();
+        if let Some(finder) = self.file_finder.as_mut() {
+            finder.preview = finder
+                .matches
+                .get(finder.selected)
+                .and_then(|m| FileUtils::read_file(&root.join(&m.path)).ok())
+                .map(|content| {
+                    content
+                        .lines()
+                        .take(FINDER_PREVIEW_LINES)
+                        .map(|l| l.to_string())
+                        .collect()
+                })
+                .unwrap_or_default();
+        }
+    }
+
+    pub fn cycle_model(&mut self) {
+        if self.available_models.is_empty() {
+            return;
+        }
+
+        let next = self
+            .available_models
+            .iter()
+            .position(|m| m == &self.ollama_model)
+            .map(|idx| (idx + 1) % self.available_models.len())
+            .unwrap_or(0);
+        self.pending_model_action = Some(ModelAction::Switch(self.available_models[next].clone()));
+    }
+
     pub fn get_spinner(&self) -> &str {
         match (self.animation_frame / 4) % 4 {
             0 => "▁",
@@ -4,6 +4,9 @@ use crate::tui::app::{App, InputMode, Tab};
 pub fn handle_input(app: &mut App, key_code: KeyCode, modifiers: KeyModifiers) {
     match app.input_mode {
         InputMode::Normal => match key_code {
+            KeyCode::Char('p') if modifiers.contains(KeyModifiers::CONTROL) => {
+                app.open_file_finder();
+            }
             KeyCode::Char('i') => {
                 app.input_mode = InputMode::Insert;
                 app.status_bar.mode = "INSERT".to_string();
@@ -14,6 +17,7 @@ pub fn handle_input(app: &mut App, key_code: KeyCode, modifiers: KeyModifiers) {
                 app.input.push(':');
             }
             KeyCode::Char('/') => {
+                app.search_query.clear();
                 app.input_mode = InputMode::Search;
                 app.status_bar.mode = "SEARCH".to_string();
             }
@@ -23,6 +27,33 @@ pub fn handle_input(app: &mut App, key_code: KeyCode, modifiers: KeyModifiers) {
             KeyCode::Char('?') => {
                 app.show_help = !app.show_help;
             }
+            KeyCode::Char('m') => {
+                app.cycle_model();
+            }
+            KeyCode::Char('n') if !app.search_results.is_empty() => {
+                app.next_result(1);
+            }
+            KeyCode::Char('N') if !app.search_results.is_empty() => {
+                app.next_result(-1);
+            }
+            KeyCode::Esc if !app.search_results.is_empty() => {
+                app.clear_search();
+            }
+            KeyCode::Char('g') if app.active_tab == Tab::Editor => {
+                app.jump_to_definition();
+            }
+            KeyCode::Char('j') | KeyCode::Down if app.active_tab == Tab::Editor => {
+                app.move_editor_cursor(0, 1);
+            }
+            KeyCode::Char('k') | KeyCode::Up if app.active_tab == Tab::Editor => {
+                app.move_editor_cursor(0, -1);
+            }
+            KeyCode::Char('h') | KeyCode::Left if app.active_tab == Tab::Editor => {
+                app.move_editor_cursor(-1, 0);
+            }
+            KeyCode::Char('l') | KeyCode::Right if app.active_tab == Tab::Editor => {
+                app.move_editor_cursor(1, 0);
+            }
             KeyCode::Tab => {
                 app.cycle_tab();
             }
@@ -31,15 +62,11 @@ pub fn handle_input(app: &mut App, key_code: KeyCode, modifiers: KeyModifiers) {
                     app.cycle_tab();
                 }
             }
-            KeyCode::Char('j') => {
-                if app.selected_file < app.file_tree.len() - 1 {
-                    app.selected_file += 1;
-                }
+            KeyCode::Char('j') if app.selected_file + 1 < app.file_tree.len() => {
+                app.selected_file += 1;
             }
-            KeyCode::Char('k') => {
-                if app.selected_file > 0 {
-                    app.selected_file -= 1;
-                }
+            KeyCode::Char('k') if app.selected_file > 0 => {
+                app.selected_file -= 1;
             }
             _ => {}
         },
@@ -57,10 +84,8 @@ pub fn handle_input(app: &mut App, key_code: KeyCode, modifiers: KeyModifiers) {
                 app.input_mode = InputMode::Normal;
                 app.status_bar.mode = "NORMAL".to_string();
             }
-            KeyCode::Char(c) => {
-                if app.input.len() < 1000 {
-                    app.input.push(c);
-                }
+            KeyCode::Char(c) if app.input.len() < 1000 => {
+                app.input.push(c);
             }
             KeyCode::Tab if modifiers.contains(KeyModifiers::SHIFT) => {
                 for _ in 0..3 {
@@ -81,10 +106,8 @@ pub fn handle_input(app: &mut App, key_code: KeyCode, modifiers: KeyModifiers) {
                 app.input_mode = InputMode::Normal;
                 app.status_bar.mode = "NORMAL".to_string();
             }
-            KeyCode::Backspace => {
-                if app.input.len() > 1 {
-                    app.input.pop();
-                }
+            KeyCode::Backspace if app.input.len() > 1 => {
+                app.input.pop();
             }
             KeyCode::Esc => {
                 app.input.clear();
@@ -94,12 +117,10 @@ pub fn handle_input(app: &mut App, key_code: KeyCode, modifiers: KeyModifiers) {
             KeyCode::Char(c) => {
                 app.input.push(c);
             }
-            KeyCode::Up => {
-                if app.command_index < app.command_history.len() - 1 {
-                    app.command_index += 1;
-                    if let Some(cmd) = app.command_history.get(app.command_index) {
-                        app.input = format!(":{}", cmd);
-                    }
+            KeyCode::Up if app.command_index + 1 < app.command_history.len() => {
+                app.command_index += 1;
+                if let Some(cmd) = app.command_history.get(app.command_index) {
+                    app.input = format!(":{}", cmd);
                 }
             }
             KeyCode::Down => {
@@ -118,6 +139,7 @@ pub fn handle_input(app: &mut App, key_code: KeyCode, modifiers: KeyModifiers) {
             KeyCode::Enter => {
                 app.input_mode = InputMode::Normal;
                 app.status_bar.mode = "NORMAL".to_string();
+                app.run_search();
             }
             KeyCode::Backspace => {
                 app.search_query.pop();
@@ -132,5 +154,38 @@ pub fn handle_input(app: &mut App, key_code: KeyCode, modifiers: KeyModifiers) {
             }
             _ => {}
         },
+        InputMode::FileFinder => match key_code {
+            KeyCode::Esc => {
+                app.close_file_finder();
+            }
+            KeyCode::Enter => {
+                app.accept_file_finder();
+            }
+            KeyCode::Down | KeyCode::Tab => {
+                app.move_finder_selection(1);
+            }
+            KeyCode::Up | KeyCode::BackTab => {
+                app.move_finder_selection(-1);
+            }
+            KeyCode::Char('n') if modifiers.contains(KeyModifiers::CONTROL) => {
+                app.move_finder_selection(1);
+            }
+            KeyCode::Char('p') if modifiers.contains(KeyModifiers::CONTROL) => {
+                app.move_finder_selection(-1);
+            }
+            KeyCode::Backspace => {
+                if let Some(finder) = app.file_finder.as_mut() {
+                    finder.query.pop();
+                }
+                app.update_file_finder();
+            }
+            KeyCode::Char(c) => {
+                if let Some(finder) = app.file_finder.as_mut() {
+                    finder.query.push(c);
+                }
+                app.update_file_finder();
+            }
+            _ => {}
+        },
     }
 }
@@ -75,6 +75,10 @@ fn draw_main_content(f: &mut Frame, app: &App, area: Rect) {
         draw_notification(f, notif);
     }
 
+    if let Some(ref finder) = app.file_finder {
+        draw_file_finder(f, finder);
+    }
+
     if app.show_help {
         draw_help_modal(f, app);
     }
@@ -90,7 +94,7 @@ fn draw_sidebar(f: &mut Frame, app: &App, area: Rect) {
         .constraints([
             Constraint::Length(3),
             Constraint::Min(6),
-            Constraint::Length(8),
+            Constraint::Length(12),
         ])
         .split(area);
 
@@ -176,6 +180,22 @@ fn draw_ollama_models(f: &mut Frame, app: &App, area: Rect) {
         Line::from(""),
     ];
 
+    if let Some(ref pull) = app.model_pull {
+        model_lines.push(Line::from(Span::styled(
+            format!("⬇ {}", pull.model),
+            Style::default().fg(Color::Yellow).bold(),
+        )));
+        let status = match pull.percent {
+            Some(percent) => {
+                let filled = (percent as usize * 16) / 100;
+                format!("[{}{}] {}%", "█".repeat(filled), "░".repeat(16 - filled), percent)
+            }
+            None => pull.status.clone(),
+        };
+        model_lines.push(Line::from(Span::styled(status, Style::default().fg(Color::Yellow))));
+        model_lines.push(Line::from(""));
+    }
+
     if app.available_models.is_empty() {
         model_lines.push(Line::from(Span::styled(
             "No models",
@@ -183,6 +203,12 @@ fn draw_ollama_models(f: &mut Frame, app: &App, area: Rect) {
         )));
     } else {
         model_lines.push(Line::from(format!("Active: {}", app.ollama_model)));
+        if let Some(context) = app.active_model_context {
+            model_lines.push(Line::from(Span::styled(
+                format!("Context: {} tokens", context),
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
         model_lines.push(Line::from(""));
         for model in &app.available_models {
             let style = if model == &app.ollama_model {
@@ -190,7 +216,8 @@ fn draw_ollama_models(f: &mut Frame, app: &App, area: Rect) {
             } else {
                 Style::default().fg(Color::DarkGray)
             };
-            model_lines.push(Line::from(Span::styled(format!("▪ {}", model), style)));
+            let loaded = if app.loaded_models.contains(model) { " ●" } else { "" };
+            model_lines.push(Line::from(Span::styled(format!("▪ {}{}", model, loaded), style)));
         }
     }
 
@@ -271,6 +298,7 @@ fn draw_input_box(f: &mut Frame, app: &App, area: Rect) {
             InputMode::Insert => Color::Green,
             InputMode::Command => Color::Yellow,
             InputMode::Search => Color::Magenta,
+            InputMode::FileFinder => Color::Cyan,
             InputMode::Normal => Color::Gray,
         }
     };
@@ -280,6 +308,8 @@ fn draw_input_box(f: &mut Frame, app: &App, area: Rect) {
             "⏳ Processing request... Press Ctrl+C to cancel",
             Style::default().fg(Color::Magenta).italic(),
         )
+    } else if app.input_mode == InputMode::Search {
+        Span::styled(format!("/{}", app.search_query), Style::default().fg(Color::Magenta))
     } else if app.input.is_empty() && app.input_mode == InputMode::Normal {
         Span::styled(" press i to chat • ? for help ", Style::default().fg(Color::DarkGray))
     } else {
@@ -309,11 +339,75 @@ fn draw_input_box(f: &mut Frame, app: &App, area: Rect) {
 fn draw_editor_tab(f: &mut Frame, app: &App, area: Rect) {
     let editor_layout = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Length(2), Constraint::Min(10)])
+        .constraints(if app.search_results.is_empty() {
+            vec![Constraint::Length(2), Constraint::Min(10)]
+        } else {
+            vec![Constraint::Length(2), Constraint::Min(10), Constraint::Length(10)]
+        })
         .split(area);
 
     draw_editor_tabs(f, app, editor_layout[0]);
     draw_code_editor(f, app, editor_layout[1]);
+    if !app.search_results.is_empty() {
+        draw_search_results(f, app, editor_layout[2]);
+    }
+}
+
+fn draw_search_results(f: &mut Frame, app: &App, area: Rect) {
+    let root = std::path::Path::new(&app.project_path);
+    let mut lines = Vec::new();
+    let mut current_line = 0;
+    let mut last_file = None;
+
+    for (idx, result) in app.search_results.iter().enumerate() {
+        if last_file != Some(&result.file) {
+            let relative = result.file.strip_prefix(root).unwrap_or(&result.file);
+            lines.push(Line::from(Span::styled(
+                relative.display().to_string(),
+                Style::default().fg(Color::Cyan).bold(),
+            )));
+            last_file = Some(&result.file);
+        }
+
+        let first_line = result.content.lines().next().unwrap_or_default();
+        let start = result.match_position.min(first_line.len());
+        let end = (start + result.match_length).min(first_line.len());
+        let (before, matched, after) = match (first_line.get(..start), first_line.get(start..end), first_line.get(end..)) {
+            (Some(b), Some(m), Some(a)) => (b, m, a),
+            _ => (first_line, "", ""),
+        };
+
+        let is_current = idx == app.current_result;
+        if is_current {
+            current_line = lines.len();
+        }
+        let marker = if is_current { "▶" } else { " " };
+        lines.push(Line::from(vec![
+            Span::styled(format!("{}{:>5}: ", marker, result.line_number), Style::default().fg(Color::DarkGray)),
+            Span::raw(before.trim_start().to_string()),
+            Span::styled(matched.to_string(), Style::default().fg(Color::Black).bg(Color::Yellow)),
+            Span::raw(after.to_string()),
+        ]));
+    }
+
+    let visible = area.height.saturating_sub(2) as usize;
+    let scroll = current_line.saturating_sub(visible / 2) as u16;
+
+    let results = Paragraph::new(lines)
+        .scroll((scroll, 0))
+        .block(
+            Block::default()
+                .title(format!(
+                    " 🔎 /{} [{}/{}] ",
+                    app.search_query,
+                    app.current_result + 1,
+                    app.search_results.len()
+                ))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Magenta))
+        );
+
+    f.render_widget(results, area);
 }
 
 fn draw_editor_tabs(f: &mut Frame, app: &App, area: Rect) {
@@ -346,19 +440,28 @@ fn draw_code_editor(f: &mut Frame, app: &App, area: Rect) {
     let editor = &app.editor_tabs[app.active_editor_tab];
 
     let mut lines = Vec::new();
-    for (idx, code_line) in editor.content.iter().enumerate() {
+    for (idx, code_line) in editor.content.iter().enumerate().skip(editor.scroll_y) {
+        let is_cursor_line = idx == editor.cursor_y;
         let line_num = Span::styled(
-            format!("{:>3} ", idx + 1),
-            Style::default().fg(Color::DarkGray),
+            format!("{:>4} ", idx + 1),
+            if is_cursor_line {
+                Style::default().fg(Color::Yellow)
+            } else {
+                Style::default().fg(Color::DarkGray)
+            },
         );
-        let content = Span::raw(&code_line.content);
+        let content = if is_cursor_line {
+            Span::styled(&code_line.content, Style::default().bg(Color::DarkGray))
+        } else {
+            Span::raw(&code_line.content)
+        };
         lines.push(Line::from(vec![line_num, content]));
     }
 
     let code = Paragraph::new(lines)
         .block(
             Block::default()
-                .title(" 📝 Editor ")
+                .title(format!(" 📝 {} ", editor.path))
                 .borders(Borders::ALL)
                 .border_style(Style::default().fg(Color::Cyan))
         )
@@ -459,22 +562,124 @@ fn draw_notification(f: &mut Frame, notif: &crate::tui::app::Notification) {
     f.render_widget(popup, popup_area);
 }
 
+fn draw_file_finder(f: &mut Frame, finder: &crate::tui::app::FileFinder) {
+    let size = f.size();
+    let width = (size.width * 4 / 5).max(60);
+    let height = (size.height * 3 / 4).max(16);
+    let area = Rect::new(
+        size.width.saturating_sub(width) / 2,
+        size.height.saturating_sub(height) / 2,
+        width.min(size.width),
+        height.min(size.height),
+    );
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
+        .split(area);
+    let left = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(3)])
+        .split(columns[0]);
+
+    let query = Paragraph::new(Line::from(vec![
+        Span::styled("> ", Style::default().fg(Color::Cyan).bold()),
+        Span::raw(&finder.query),
+    ]))
+    .block(
+        Block::default()
+            .title(format!(" 🔍 Find file ({}) ", finder.matches.len()))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan)),
+    )
+    .style(Style::default().bg(Color::Black));
+
+    let items: Vec<ListItem> = finder
+        .matches
+        .iter()
+        .enumerate()
+        .map(|(idx, m)| {
+            let base = if idx == finder.selected {
+                Style::default().fg(Color::Black).bg(Color::Cyan)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            let spans: Vec<Span> = m
+                .path
+                .chars()
+                .enumerate()
+                .map(|(i, c)| {
+                    let style = if m.positions.contains(&i) {
+                        base.fg(Color::Yellow).bold()
+                    } else {
+                        base
+                    };
+                    Span::styled(c.to_string(), style)
+                })
+                .collect();
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::DarkGray)),
+        )
+        .style(Style::default().bg(Color::Black));
+
+    let preview_title = finder
+        .matches
+        .get(finder.selected)
+        .map(|m| format!(" {} ", m.path))
+        .unwrap_or_else(|| " Preview ".to_string());
+    let preview_lines: Vec<Line> = finder
+        .preview
+        .iter()
+        .enumerate()
+        .map(|(idx, l)| {
+            Line::from(vec![
+                Span::styled(format!("{:>4} ", idx + 1), Style::default().fg(Color::DarkGray)),
+                Span::raw(l.as_str()),
+            ])
+        })
+        .collect();
+    let preview = Paragraph::new(preview_lines)
+        .block(
+            Block::default()
+                .title(preview_title)
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::DarkGray)),
+        )
+        .style(Style::default().fg(Color::White).bg(Color::Black));
+
+    f.render_widget(Clear, area);
+    f.render_widget(query, left[0]);
+    f.render_widget(list, left[1]);
+    f.render_widget(preview, columns[1]);
+}
+
 fn draw_help_modal(f: &mut Frame, _app: &App) {
     let content = vec![
         Line::from(Span::styled("❓ HELP", Style::default().fg(Color::Yellow).bold())),
         Line::from(""),
         Line::from("i        - Insert mode"),
         Line::from(":        - Command palette"),
-        Line::from("/        - Search"),
+        Line::from("/        - Search (regex)"),
+        Line::from("n / N    - Next / prev match"),
         Line::from("Tab      - Next tab"),
         Line::from("Shift+Tab - Prev tab"),
         Line::from("Ctrl+s   - Save"),
         Line::from("Ctrl+p   - Find file"),
+        Line::from("m        - Next model"),
+        Line::from("g        - Go to definition"),
+        Line::from(":model X - Switch/pull model"),
         Line::from("q        - Quit"),
     ];
 
     let width = 40u16;
-    let height = 12u16;
+    let height = 16u16;
     let x = (f.size().width.saturating_sub(width)) / 2;
     let y = (f.size().height.saturating_sub(height)) / 2;
 
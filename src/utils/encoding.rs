@@ -0,0 +1,171 @@
+use anyhow::Result;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextEncoding {
+    Utf8,
+    Utf8Bom,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    Lf,
+    CrLf,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextFormat {
+    pub encoding: TextEncoding,
+    pub line_ending: LineEnding,
+    pub trailing_newline: bool,
+    /// The unterminated final line when there is no trailing newline.
+    pub last_line: String,
+}
+
+impl Default for TextFormat {
+    fn default() -> Self {
+        TextFormat {
+            encoding: TextEncoding::Utf8,
+            line_ending: LineEnding::Lf,
+            trailing_newline: true,
+            last_line: String::new(),
+        }
+    }
+}
+
+impl TextFormat {
+    pub fn detect(bytes: &[u8]) -> Self {
+        let encoding = if bytes.starts_with(&[0xEF, 0xBB, 0xBF]) {
+            TextEncoding::Utf8Bom
+        } else if bytes.starts_with(&[0xFF, 0xFE]) {
+            TextEncoding::Utf16Le
+        } else if bytes.starts_with(&[0xFE, 0xFF]) {
+            TextEncoding::Utf16Be
+        } else if std::str::from_utf8(bytes).is_ok() {
+            TextEncoding::Utf8
+        } else {
+            TextEncoding::Latin1
+        };
+
+        let text = Self::decode_with(encoding, bytes);
+        let crlf = text.matches("\r\n").count();
+        let lf = text.matches('\n').count() - crlf;
+
+        TextFormat {
+            encoding,
+            line_ending: if crlf > lf { LineEnding::CrLf } else { LineEnding::Lf },
+            trailing_newline: text.is_empty() || text.ends_with('\n'),
+            last_line: if text.ends_with('\n') {
+                String::new()
+            } else {
+                text.rsplit('\n').next().unwrap_or_default().to_string()
+            },
+        }
+    }
+
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        Self::decode_with(self.encoding, bytes)
+    }
+
+    fn decode_with(encoding: TextEncoding, bytes: &[u8]) -> String {
+        match encoding {
+            TextEncoding::Utf8 => String::from_utf8_lossy(bytes).into_owned(),
+            TextEncoding::Utf8Bom => String::from_utf8_lossy(&bytes[3..]).into_owned(),
+            TextEncoding::Utf16Le | TextEncoding::Utf16Be => {
+                let units: Vec<u16> = bytes[2..]
+                    .chunks_exact(2)
+                    .map(|pair| match encoding {
+                        TextEncoding::Utf16Le => u16::from_le_bytes([pair[0], pair[1]]),
+                        _ => u16::from_be_bytes([pair[0], pair[1]]),
+                    })
+                    .collect();
+                String::from_utf16_lossy(&units)
+            }
+            TextEncoding::Latin1 => bytes.iter().map(|&b| b as char).collect(),
+        }
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        let mut normalized = text.replace("\r\n", "\n");
+        if self.trailing_newline && !normalized.is_empty() && !normalized.ends_with('\n') {
+            normalized.push('\n');
+        } else if !self.trailing_newline {
+            // Only undo the newline an edit added to the unchanged last line.
+            let unchanged = normalized
+                .strip_suffix('\n')
+                .is_some_and(|body| body.rsplit('\n').next() == Some(self.last_line.as_str()));
+            if unchanged {
+                normalized.pop();
+            }
+        }
+
+        match self.line_ending {
+            LineEnding::Lf => normalized,
+            LineEnding::CrLf => normalized.replace('\n', "\r\n"),
+        }
+    }
+
+    pub fn encode(&self, text: &str) -> Result<Vec<u8>> {
+        let text = self.apply(text);
+        Ok(match self.encoding {
+            TextEncoding::Utf8 => text.into_bytes(),
+            TextEncoding::Utf8Bom => [&[0xEF, 0xBB, 0xBF][..], text.as_bytes()].concat(),
+            TextEncoding::Utf16Le => [0xFF, 0xFE]
+                .into_iter()
+                .chain(text.encode_utf16().flat_map(|u| u.to_le_bytes()))
+                .collect(),
+            TextEncoding::Utf16Be => [0xFE, 0xFF]
+                .into_iter()
+                .chain(text.encode_utf16().flat_map(|u| u.to_be_bytes()))
+                .collect(),
+            TextEncoding::Latin1 => text
+                .chars()
+                .map(|c| {
+                    u8::try_from(c as u32)
+                        .map_err(|_| anyhow::anyhow!("Character {:?} cannot be written as Latin-1", c))
+                })
+                .collect::<Result<Vec<u8>>>()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crlf_and_missing_trailing_newline_round_trip() {
+        let original = b"line one\r\nline two";
+        let format = TextFormat::detect(original);
+        assert_eq!(format.line_ending, LineEnding::CrLf);
+        assert!(!format.trailing_newline);
+
+        let encoded = format.encode("line zero\nline one\nline two\n").unwrap();
+        assert_eq!(encoded, b"line zero\r\nline one\r\nline two");
+        let encoded = format.encode("line one\nline two\nline three\n").unwrap();
+        assert_eq!(encoded, b"line one\r\nline two\r\nline three\r\n");
+        let encoded = format.encode("line one\nline two\n\n").unwrap();
+        assert_eq!(encoded, b"line one\r\nline two\r\n\r\n");
+    }
+
+    #[test]
+    fn test_bom_utf16_and_latin1_detection() {
+        let bom = TextFormat::detect(b"\xEF\xBB\xBFfn main() {}\n");
+        assert_eq!(bom.encoding, TextEncoding::Utf8Bom);
+        assert_eq!(bom.decode(b"\xEF\xBB\xBFfn main() {}\n"), "fn main() {}\n");
+
+        let utf16: Vec<u8> = [0xFF, 0xFE].into_iter().chain("hé\n".encode_utf16().flat_map(|u| u.to_le_bytes())).collect();
+        let format = TextFormat::detect(&utf16);
+        assert_eq!(format.encoding, TextEncoding::Utf16Le);
+        assert_eq!(format.decode(&utf16), "hé\n");
+        assert_eq!(format.encode("hé\n").unwrap(), utf16);
+
+        let latin1 = b"caf\xE9\n";
+        let format = TextFormat::detect(latin1);
+        assert_eq!(format.encoding, TextEncoding::Latin1);
+        assert_eq!(format.decode(latin1), "café\n");
+        assert_eq!(format.encode("café\n").unwrap(), latin1.to_vec());
+    }
+}
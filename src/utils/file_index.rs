@@ -0,0 +1,52 @@
+use super::walker::ProjectWalker;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const STALE_AFTER: Duration = Duration::from_secs(30);
+
+pub struct FileIndex {
+    root: PathBuf,
+    files: Vec<String>,
+    built_at: Instant,
+}
+
+impl FileIndex {
+    pub fn build(root: &Path) -> Self {
+        let mut walker = ProjectWalker::for_project(root);
+        let options = walker.options_mut();
+        options.include_hidden = true;
+        options.skip_binary = false;
+        options.max_file_size = None;
+
+        let files = walker
+            .walk()
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|path| {
+                path.strip_prefix(root)
+                    .ok()
+                    .map(|p| p.to_string_lossy().replace('\\', "/"))
+            })
+            .collect();
+
+        FileIndex {
+            root: root.to_path_buf(),
+            files,
+            built_at: Instant::now(),
+        }
+    }
+
+    pub fn refresh_if_stale(&mut self) {
+        if self.built_at.elapsed() > STALE_AFTER {
+            *self = Self::build(&self.root);
+        }
+    }
+
+    pub fn files(&self) -> &[String] {
+        &self.files
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+}
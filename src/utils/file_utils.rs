@@ -1,32 +1,322 @@
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use anyhow::Result;
-use walkdir::WalkDir;
+use sha2::{Digest, Sha256};
+use super::encoding::TextFormat;
+use super::walker::ProjectWalker;
+use crate::core::audit::{AuditEvent, AuditLog};
+use crate::core::policy::PolicyEnforcer;
+use std::sync::Arc;
+
+pub const SOURCE_EXTENSIONS: &[&str] = &[
+    "rs", "py", "js", "jsx", "ts", "tsx", "mjs", "cjs", "go", "java", "c", "h", "cpp", "hpp", "rb",
+];
 
 pub struct FileUtils;
 
+#[derive(Debug, Clone)]
+pub struct WriteOptions {
+    pub preserve_format: bool,
+    pub backup_root: Option<PathBuf>,
+    pub policy: Option<Arc<PolicyEnforcer>>,
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        WriteOptions {
+            preserve_format: true,
+            backup_root: None,
+            policy: None,
+        }
+    }
+}
+
+impl WriteOptions {
+    /// Agent writes into a project, checked against its `.codeagent.yml` policy.
+    pub fn for_project(project_root: &Path) -> Self {
+        WriteOptions {
+            policy: Some(PolicyEnforcer::for_project(project_root)),
+            ..WriteOptions::default()
+        }
+    }
+
+    /// The tool's own indexes, caches and config, which the project policy does not govern.
+    pub fn internal() -> Self {
+        WriteOptions::default()
+    }
+
+    pub fn with_backups(project_root: &Path) -> Self {
+        WriteOptions {
+            backup_root: Some(project_root.to_path_buf()),
+            ..Self::for_project(project_root)
+        }
+    }
+}
+
+struct StagedWrite {
+    target: PathBuf,
+    /// None stages a removal.
+    temp: Option<PathBuf>,
+    original: Option<Vec<u8>>,
+}
+
 impl FileUtils {
     pub fn find_files(root: &Path, extension: &str) -> Result<Vec<PathBuf>> {
-        let mut files = Vec::new();
-        
-        for entry in WalkDir::new(root)
-            .into_iter()
-            .filter_map(|e| e.ok())
-            .filter(|e| e.path().is_file())
-        {
-            if entry.path().extension().map_or(false, |ext| ext == extension) {
-                files.push(entry.path().to_path_buf());
-            }
-        }
-        
-        Ok(files)
+        ProjectWalker::for_project(root)
+            .with_extensions(&[extension])
+            .walk()
+    }
+
+    pub fn find_source_files(root: &Path) -> Result<Vec<PathBuf>> {
+        ProjectWalker::for_project(root)
+            .with_extensions(SOURCE_EXTENSIONS)
+            .walk()
+    }
+
+    /// The nearest directory at or above `path` holding `.codeagent.yml` or `.git`;
+    /// `path` itself (or its directory, for a file) when there is none.
+    pub fn project_root(path: &Path) -> PathBuf {
+        let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let start = if path.is_file() {
+            path.parent().map(Path::to_path_buf).unwrap_or_default()
+        } else {
+            path
+        };
+        start
+            .ancestors()
+            .find(|dir| dir.join(".codeagent.yml").exists() || dir.join(".git").exists())
+            .unwrap_or(&start)
+            .to_path_buf()
+    }
+
+    pub fn hash_bytes(bytes: &[u8]) -> String {
+        format!("{:x}", Sha256::digest(bytes))
+    }
+
+    pub fn hash_file(path: &Path) -> Result<String> {
+        Ok(Self::hash_bytes(&std::fs::read(path)?))
     }
 
     pub fn read_file(path: &Path) -> Result<String> {
-        Ok(std::fs::read_to_string(path)?)
+        Ok(Self::read_text(path)?.0)
+    }
+
+    pub fn read_text(path: &Path) -> Result<(String, TextFormat)> {
+        let bytes = std::fs::read(path)?;
+        let format = TextFormat::detect(&bytes);
+        Ok((format.decode(&bytes), format))
     }
 
+    /// Checked against the policy of whichever project contains `path`.
     pub fn write_file(path: &Path, content: &str) -> Result<()> {
-        std::fs::write(path, content)?;
+        let options = WriteOptions {
+            policy: PolicyEnforcer::covering(path),
+            ..WriteOptions::default()
+        };
+        Self::write_file_with(path, content, &options)
+    }
+
+    pub fn write_file_with(path: &Path, content: &str, options: &WriteOptions) -> Result<()> {
+        if let Some(policy) = &options.policy {
+            policy.check_writes(&[path.to_path_buf()])?;
+        }
+        let staged = Self::stage(path, content, options)?;
+        if let Err(e) = Self::commit(&staged, options) {
+            if let Some(temp) = &staged.temp {
+                let _ = std::fs::remove_file(temp);
+            }
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    pub fn write_files_atomic(files: &[(PathBuf, String)], options: &WriteOptions) -> Result<()> {
+        Self::update_files_atomic(files, &[], options)
+    }
+
+    /// Writes and removals as one operation: if any of them fails, every file already
+    /// changed is restored.
+    pub fn update_files_atomic(files: &[(PathBuf, String)], removals: &[PathBuf], options: &WriteOptions) -> Result<()> {
+        if let Some(policy) = &options.policy {
+            let paths: Vec<PathBuf> = files
+                .iter()
+                .map(|(path, _)| path.clone())
+                .chain(removals.iter().cloned())
+                .collect();
+            policy.check_writes(&paths)?;
+        }
+        let mut staged = Vec::with_capacity(files.len() + removals.len());
+        let staging = files
+            .iter()
+            .map(|(path, content)| (path, Self::stage(path, content, options)))
+            .chain(removals.iter().map(|path| {
+                let removal = std::fs::read(path).map(|original| StagedWrite {
+                    target: path.clone(),
+                    temp: None,
+                    original: Some(original),
+                });
+                (path, removal.map_err(anyhow::Error::from))
+            }));
+        for (path, result) in staging {
+            match result {
+                Ok(write) => staged.push(write),
+                Err(e) => {
+                    for write in &staged {
+                        if let Some(temp) = &write.temp {
+                            let _ = std::fs::remove_file(temp);
+                        }
+                    }
+                    return Err(anyhow::anyhow!("Could not stage {}: {}", path.display(), e));
+                }
+            }
+        }
+
+        for (idx, write) in staged.iter().enumerate() {
+            if let Err(e) = Self::commit(write, options) {
+                for done in &staged[..idx] {
+                    let _ = match &done.original {
+                        Some(bytes) => std::fs::write(&done.target, bytes),
+                        None => std::fs::remove_file(&done.target),
+                    };
+                }
+                for pending in &staged[idx..] {
+                    if let Some(temp) = &pending.temp {
+                        let _ = std::fs::remove_file(temp);
+                    }
+                }
+                return Err(anyhow::anyhow!(
+                    "Could not update {}: {}; all files were restored",
+                    write.target.display(),
+                    e
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn stage(path: &Path, content: &str, options: &WriteOptions) -> Result<StagedWrite> {
+        let target = if path.is_symlink() {
+            std::fs::canonicalize(path)?
+        } else {
+            path.to_path_buf()
+        };
+        if let Some(parent) = target.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let original = std::fs::read(&target).ok();
+        let bytes = match &original {
+            Some(existing) if options.preserve_format => TextFormat::detect(existing).encode(content)?,
+            _ => content.as_bytes().to_vec(),
+        };
+
+        let name = target
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let temp = target.with_file_name(format!(".{}.{}.codeagent-tmp", name, std::process::id()));
+
+        let result = (|| -> Result<()> {
+            let mut file = std::fs::File::create(&temp)?;
+            file.write_all(&bytes)?;
+            file.sync_all()?;
+            if original.is_some() {
+                std::fs::set_permissions(&temp, std::fs::metadata(&target)?.permissions())?;
+            }
+            Ok(())
+        })();
+        if let Err(e) = result {
+            let _ = std::fs::remove_file(&temp);
+            return Err(e);
+        }
+
+        Ok(StagedWrite {
+            target,
+            temp: Some(temp),
+            original,
+        })
+    }
+
+    fn commit(write: &StagedWrite, options: &WriteOptions) -> Result<()> {
+        if let (Some(root), Some(original)) = (&options.backup_root, &write.original) {
+            Self::backup(root, &write.target, original)?;
+        }
+
+        match &write.temp {
+            Some(temp) => std::fs::rename(temp, &write.target)?,
+            None => std::fs::remove_file(&write.target)?,
+        }
+        Self::audit_write(&write.target, write.original.as_deref());
+        Ok(())
+    }
+
+    // The agent's own bookkeeping under .codeagent/ is not audited.
+    fn audit_write(target: &Path, original: Option<&[u8]>) {
+        if target.components().any(|c| c.as_os_str() == ".codeagent") {
+            return;
+        }
+        AuditLog::record(AuditEvent::FileWrite {
+            path: target.display().to_string(),
+            before_hash: original.map(Self::hash_bytes),
+            after_hash: Self::hash_file(target).ok(),
+        });
+    }
+
+    fn backup(root: &Path, target: &Path, original: &[u8]) -> Result<()> {
+        let relative = target.strip_prefix(root).unwrap_or(target);
+        let relative = relative.strip_prefix("/").unwrap_or(relative);
+        let backup = root
+            .join(".codeagent")
+            .join("backups")
+            .join(chrono::Local::now().format("%Y%m%d-%H%M%S").to_string())
+            .join(relative);
+        if let Some(parent) = backup.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&backup, original)?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project_root_is_found_from_a_nested_file() {
+        let root = std::env::temp_dir().join(format!("codeagent-root-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("src/nested")).unwrap();
+        std::fs::write(root.join(".codeagent.yml"), "model_provider: ollama\n").unwrap();
+        std::fs::write(root.join("src/nested/lib.rs"), "").unwrap();
+
+        let root = std::fs::canonicalize(&root).unwrap();
+        assert_eq!(FileUtils::project_root(&root.join("src/nested/lib.rs")), root);
+        assert_eq!(FileUtils::project_root(&root.join("src")), root);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_failed_removal_leaves_every_file_untouched() {
+        let root = std::env::temp_dir().join(format!("codeagent-atomic-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("kept.txt"), "old\n").unwrap();
+        std::fs::write(root.join("gone.txt"), "bye\n").unwrap();
+
+        let writes = vec![(root.join("kept.txt"), "new\n".to_string())];
+        let missing = FileUtils::update_files_atomic(&writes, &[root.join("missing.txt")], &WriteOptions::default());
+        assert!(missing.is_err());
+        assert_eq!(std::fs::read_to_string(root.join("kept.txt")).unwrap(), "old\n");
+
+        FileUtils::update_files_atomic(&writes, &[root.join("gone.txt")], &WriteOptions::default()).unwrap();
+        assert_eq!(std::fs::read_to_string(root.join("kept.txt")).unwrap(), "new\n");
+        assert!(!root.join("gone.txt").exists());
+        let leftovers: Vec<_> = std::fs::read_dir(&root).unwrap().collect();
+        assert_eq!(leftovers.len(), 1);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,109 @@
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuzzyMatch {
+    pub path: String,
+    pub score: i64,
+    pub positions: Vec<usize>,
+}
+
+pub struct FuzzyMatcher;
+
+impl FuzzyMatcher {
+    pub fn score(query: &str, candidate: &str) -> Option<FuzzyMatch> {
+        if query.is_empty() {
+            return Some(FuzzyMatch {
+                path: candidate.to_string(),
+                score: 0,
+                positions: Vec::new(),
+            });
+        }
+
+        let chars: Vec<char> = candidate.chars().collect();
+        let lower: Vec<char> = chars.iter().map(|c| c.to_ascii_lowercase()).collect();
+        let filename_start = candidate.rfind('/').map(|i| candidate[..=i].chars().count()).unwrap_or(0);
+
+        let mut positions = Vec::new();
+        let mut score: i64 = 0;
+        let mut search_from = 0;
+        let mut previous: Option<usize> = None;
+
+        for q in query.chars().filter(|c| !c.is_whitespace()) {
+            let q_lower = q.to_ascii_lowercase();
+            let idx = (search_from..lower.len()).find(|&i| lower[i] == q_lower)?;
+
+            score += 1;
+            if previous == Some(idx.wrapping_sub(1)) {
+                score += 15;
+            }
+            if Self::is_segment_start(&chars, idx) {
+                score += 10;
+            }
+            if idx >= filename_start {
+                score += 5;
+            }
+            if chars[idx] == q {
+                score += 1;
+            }
+
+            positions.push(idx);
+            previous = Some(idx);
+            search_from = idx + 1;
+        }
+
+        if let Some(&first) = positions.first() {
+            score -= (first as i64).min(20) / 4;
+        }
+        score -= (chars.len() as i64) / 10;
+
+        Some(FuzzyMatch {
+            path: candidate.to_string(),
+            score,
+            positions,
+        })
+    }
+
+    pub fn rank(query: &str, candidates: &[String], limit: usize) -> Vec<FuzzyMatch> {
+        let mut matches: Vec<FuzzyMatch> = candidates
+            .iter()
+            .filter_map(|c| Self::score(query, c))
+            .collect();
+
+        matches.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.path.len().cmp(&b.path.len())));
+        matches.truncate(limit);
+        matches
+    }
+
+    fn is_segment_start(chars: &[char], idx: usize) -> bool {
+        if idx == 0 {
+            return true;
+        }
+        let prev = chars[idx - 1];
+        matches!(prev, '/' | '_' | '-' | '.' | ' ')
+            || (prev.is_lowercase() && chars[idx].is_uppercase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefers_segment_and_filename_matches() {
+        let candidates = vec![
+            "src/tui/handler.rs".to_string(),
+            "src/utils/file_utils.rs".to_string(),
+            "src/futures/lib.rs".to_string(),
+        ];
+
+        let ranked = FuzzyMatcher::rank("fu", &candidates, 10);
+        assert_eq!(ranked[0].path, "src/utils/file_utils.rs");
+
+        let ranked = FuzzyMatcher::rank("tuihan", &candidates, 10);
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(ranked[0].path, "src/tui/handler.rs");
+    }
+
+    #[test]
+    fn test_non_subsequence_does_not_match() {
+        assert!(FuzzyMatcher::score("xyz", "src/main.rs").is_none());
+    }
+}
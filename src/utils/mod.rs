@@ -1,6 +1,11 @@
 #![allow(dead_code)]
 
+pub mod encoding;
+pub mod file_index;
 pub mod file_utils;
+pub mod fuzzy;
+pub mod replace;
 pub mod search;
+pub mod walker;
 
-pub use file_utils::FileUtils;
+pub use file_utils::{FileUtils, WriteOptions};
@@ -0,0 +1,315 @@
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use regex::Regex;
+use crate::interactive::mode::{InteractiveMode, UserChoice};
+use super::search::SearchOptions;
+use super::walker::ProjectWalker;
+use super::{FileUtils, WriteOptions};
+
+#[derive(Debug, Clone)]
+pub struct ReplaceHunk {
+    pub start_line: usize,
+    pub byte_range: (usize, usize),
+    pub before: String,
+    pub after: String,
+    pub accepted: bool,
+}
+
+impl ReplaceHunk {
+    /// `new_start` is where the hunk lands once the accepted hunks above it are applied.
+    pub fn diff(&self, new_start: usize) -> String {
+        // Hunks span whole lines without the final newline, so splitting counts them exactly.
+        let mut diff = format!(
+            "@@ -{},{} +{},{} @@\n",
+            self.start_line,
+            self.before.split('\n').count(),
+            new_start,
+            self.after.split('\n').count()
+        );
+        for line in self.before.split('\n') {
+            diff.push_str(&format!("-{}\n", line));
+        }
+        for line in self.after.split('\n') {
+            diff.push_str(&format!("+{}\n", line));
+        }
+        diff
+    }
+
+    pub fn line_delta(&self) -> isize {
+        self.after.split('\n').count() as isize - self.before.split('\n').count() as isize
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FileReplacement {
+    pub path: PathBuf,
+    pub original: String,
+    pub hunks: Vec<ReplaceHunk>,
+}
+
+impl FileReplacement {
+    pub fn accepted_count(&self) -> usize {
+        self.hunks.iter().filter(|h| h.accepted).count()
+    }
+
+    pub fn updated_content(&self) -> String {
+        let mut updated = String::with_capacity(self.original.len());
+        let mut last = 0;
+        for hunk in self.hunks.iter().filter(|h| h.accepted) {
+            updated.push_str(&self.original[last..hunk.byte_range.0]);
+            updated.push_str(&hunk.after);
+            last = hunk.byte_range.1;
+        }
+        updated.push_str(&self.original[last..]);
+        updated
+    }
+
+    pub fn diff(&self, root: &Path) -> String {
+        let relative = self.path.strip_prefix(root).unwrap_or(&self.path).display();
+        let mut diff = format!("--- a/{}\n+++ b/{}\n", relative, relative);
+        for (idx, _) in self.hunks.iter().enumerate().filter(|(_, h)| h.accepted) {
+            diff.push_str(&self.hunk_diff(idx));
+        }
+        diff
+    }
+
+    /// One hunk's diff, shifted by the lines that accepted hunks above it add or remove.
+    pub fn hunk_diff(&self, idx: usize) -> String {
+        let offset: isize = self.hunks[..idx].iter().filter(|h| h.accepted).map(|h| h.line_delta()).sum();
+        let hunk = &self.hunks[idx];
+        hunk.diff(hunk.start_line.saturating_add_signed(offset))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ReplacePlan {
+    pub root: PathBuf,
+    pub pattern: String,
+    pub replacement: String,
+    pub files: Vec<FileReplacement>,
+}
+
+impl ReplacePlan {
+    pub fn hunk_count(&self) -> usize {
+        self.files.iter().map(|f| f.hunks.len()).sum()
+    }
+
+    pub fn accepted_count(&self) -> usize {
+        self.files.iter().map(|f| f.accepted_count()).sum()
+    }
+
+    pub fn diff(&self) -> String {
+        self.files
+            .iter()
+            .filter(|f| f.accepted_count() > 0)
+            .map(|f| f.diff(&self.root))
+            .collect()
+    }
+}
+
+pub struct SearchReplace;
+
+impl SearchReplace {
+    pub fn plan_project(
+        pattern: &str,
+        replacement: &str,
+        root: &Path,
+        options: &SearchOptions,
+    ) -> Result<ReplacePlan> {
+        let files = ProjectWalker::for_project(root).walk()?;
+        Self::plan(pattern, replacement, root, &files, options)
+    }
+
+    pub fn plan(
+        pattern: &str,
+        replacement: &str,
+        root: &Path,
+        files: &[PathBuf],
+        options: &SearchOptions,
+    ) -> Result<ReplacePlan> {
+        let regex = options.build_regex(pattern)?;
+        let mut planned = Vec::new();
+
+        for path in files {
+            let Ok(content) = FileUtils::read_file(path) else {
+                continue;
+            };
+            if let Some(file) = Self::plan_text(path, content, &regex, replacement) {
+                planned.push(file);
+            }
+        }
+
+        Ok(ReplacePlan {
+            root: root.to_path_buf(),
+            pattern: pattern.to_string(),
+            replacement: replacement.to_string(),
+            files: planned,
+        })
+    }
+
+    pub fn plan_text(
+        path: &Path,
+        content: String,
+        regex: &Regex,
+        replacement: &str,
+    ) -> Option<FileReplacement> {
+        let mut matches: Vec<(usize, usize, String)> = Vec::new();
+        for caps in regex.captures_iter(&content) {
+            let whole = caps.get(0)?;
+            let mut expanded = String::new();
+            caps.expand(replacement, &mut expanded);
+            if whole.as_str() != expanded {
+                matches.push((whole.start(), whole.end(), expanded));
+            }
+        }
+        if matches.is_empty() {
+            return None;
+        }
+
+        let line_start = |pos: usize| content[..pos].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let line_end = |pos: usize| content[pos..].find('\n').map(|i| pos + i).unwrap_or(content.len());
+
+        let mut hunks: Vec<ReplaceHunk> = Vec::new();
+        let mut group: Vec<&(usize, usize, String)> = Vec::new();
+        let mut group_range = (0, 0);
+
+        let flush = |group: &mut Vec<&(usize, usize, String)>, range: (usize, usize), hunks: &mut Vec<ReplaceHunk>| {
+            if group.is_empty() {
+                return;
+            }
+            let mut after = String::new();
+            let mut last = range.0;
+            for (start, end, expanded) in group.iter() {
+                after.push_str(&content[last..*start]);
+                after.push_str(expanded);
+                last = *end;
+            }
+            after.push_str(&content[last..range.1]);
+
+            hunks.push(ReplaceHunk {
+                start_line: content[..range.0].matches('\n').count() + 1,
+                byte_range: range,
+                before: content[range.0..range.1].to_string(),
+                after,
+                accepted: true,
+            });
+            group.clear();
+        };
+
+        for m in &matches {
+            let range = (line_start(m.0), line_end(m.1.max(m.0)));
+            if !group.is_empty() && range.0 <= group_range.1 {
+                group_range.1 = group_range.1.max(range.1);
+            } else {
+                flush(&mut group, group_range, &mut hunks);
+                group_range = range;
+            }
+            group.push(m);
+        }
+        flush(&mut group, group_range, &mut hunks);
+
+        Some(FileReplacement {
+            path: path.to_path_buf(),
+            original: content,
+            hunks,
+        })
+    }
+
+    pub fn review(plan: &mut ReplacePlan) -> bool {
+        let root = plan.root.clone();
+        for file in plan.files.iter_mut() {
+            let relative = file.path.strip_prefix(&root).unwrap_or(&file.path).display().to_string();
+            let total = file.hunks.len();
+
+            for idx in 0..total {
+                loop {
+                    InteractiveMode::display_diff(&format!(
+                        "{} (change {}/{})\n{}",
+                        relative,
+                        idx + 1,
+                        total,
+                        file.hunk_diff(idx)
+                    ));
+                    match InteractiveMode::prompt_for_approval("Apply this change?") {
+                        UserChoice::Approve => {
+                            file.hunks[idx].accepted = true;
+                            break;
+                        }
+                        UserChoice::Reject | UserChoice::RollBack => {
+                            file.hunks[idx].accepted = false;
+                            break;
+                        }
+                        UserChoice::ReviewChanges => {
+                            InteractiveMode::display_diff(&file.diff(&root));
+                        }
+                        UserChoice::Abort => return false,
+                        UserChoice::Continue => {}
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    pub fn apply(plan: &ReplacePlan, options: &WriteOptions) -> Result<usize> {
+        let changed: Vec<&FileReplacement> = plan.files.iter().filter(|f| f.accepted_count() > 0).collect();
+
+        for file in &changed {
+            let current = FileUtils::read_file(&file.path)?;
+            if current != file.original {
+                return Err(anyhow::anyhow!(
+                    "{} changed since the preview was generated; nothing was written",
+                    file.path.display()
+                ));
+            }
+        }
+
+        let writes: Vec<(PathBuf, String)> = changed
+            .iter()
+            .map(|f| (f.path.clone(), f.updated_content()))
+            .collect();
+        FileUtils::write_files_atomic(&writes, options)?;
+
+        tracing::info!("Search and replace updated {} files", writes.len());
+        Ok(writes.len())
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_groups_and_hunk_grouping() {
+        let content = "let a = old_api(1);\nlet b = old_api(2) + old_api(3);\n\nfn keep() {}\nold_api(4);\n".to_string();
+        let regex = Regex::new(r"old_api\((\d+)\)").unwrap();
+        let file = SearchReplace::plan_text(Path::new("a.rs"), content, &regex, "new_api(${1}, None)").unwrap();
+
+        assert_eq!(file.hunks.len(), 3);
+        assert_eq!(file.hunks[1].start_line, 2);
+        assert_eq!(file.hunks[1].after, "let b = new_api(2, None) + new_api(3, None);");
+        assert!(file.hunk_diff(0).contains("-let a = old_api(1);\n+let a = new_api(1, None);"));
+    }
+
+    #[test]
+    fn test_hunk_headers_follow_earlier_line_changes() {
+        let content = "a = foo\nb\nc = foo\n".to_string();
+        let regex = Regex::new("foo").unwrap();
+        let mut file = SearchReplace::plan_text(Path::new("a.py"), content, &regex, "x\ny").unwrap();
+
+        assert!(file.diff(Path::new("")).contains("@@ -1,1 +1,2 @@\n-a = foo\n+a = x\n+y\n@@ -3,1 +4,2 @@\n"));
+        file.hunks[0].accepted = false;
+        assert!(file.hunk_diff(1).starts_with("@@ -3,1 +3,2 @@"));
+    }
+
+    #[test]
+    fn test_rejected_hunks_are_left_untouched() {
+        let content = "x = foo\ny = foo\nz = foo\n".to_string();
+        let regex = Regex::new("foo").unwrap();
+        let mut file = SearchReplace::plan_text(Path::new("a.py"), content, &regex, "bar").unwrap();
+        file.hunks[1].accepted = false;
+
+        assert_eq!(file.updated_content(), "x = bar\ny = foo\nz = bar\n");
+    }
+}
@@ -1,13 +1,61 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use anyhow::Result;
-use regex::Regex;
+use regex::{Regex, RegexBuilder};
+use crate::index::SemanticIndex;
+use super::walker::ProjectWalker;
+use super::FileUtils;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct SearchResult {
     pub file: PathBuf,
     pub line_number: usize,
+    pub end_line: usize,
     pub content: String,
     pub match_position: usize,
+    pub match_length: usize,
+    pub context_before: Vec<String>,
+    pub context_after: Vec<String>,
+    pub score: Option<f32>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SearchOptions {
+    pub case_insensitive: bool,
+    pub smart_case: bool,
+    pub whole_word: bool,
+    pub fixed_string: bool,
+    pub multiline: bool,
+    pub before_context: usize,
+    pub after_context: usize,
+    pub max_results: Option<usize>,
+}
+
+impl SearchOptions {
+    pub fn build_regex(&self, pattern: &str) -> Result<Regex> {
+        let mut expr = if self.fixed_string {
+            regex::escape(pattern)
+        } else {
+            pattern.to_string()
+        };
+        if self.whole_word {
+            expr = format!(r"\b(?:{})\b", expr);
+        }
+
+        let case_insensitive = self.case_insensitive
+            || (self.smart_case && !pattern.chars().any(|c| c.is_uppercase()));
+
+        Ok(RegexBuilder::new(&expr)
+            .case_insensitive(case_insensitive)
+            .multi_line(self.multiline)
+            .dot_matches_new_line(self.multiline)
+            .build()?)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FileMatches {
+    pub file: PathBuf,
+    pub results: Vec<SearchResult>,
 }
 
 pub struct SemanticSearch;
@@ -17,47 +65,204 @@ impl SemanticSearch {
         pattern: &str,
         files: &[PathBuf],
     ) -> Result<Vec<SearchResult>> {
-        let regex = Regex::new(pattern)?;
+        Self::search_with_options(pattern, files, &SearchOptions::default())
+    }
+
+    pub fn search_keyword(query: &str, files: &[PathBuf]) -> Result<Vec<SearchResult>> {
+        let options = SearchOptions {
+            fixed_string: true,
+            ..SearchOptions::default()
+        };
+        Self::search_with_options(query, files, &options)
+    }
+
+    pub fn search_with_options(
+        pattern: &str,
+        files: &[PathBuf],
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchResult>> {
+        let regex = options.build_regex(pattern)?;
         let mut results = Vec::new();
 
         for file_path in files {
-            if let Ok(content) = std::fs::read_to_string(file_path) {
-                for (line_num, line) in content.lines().enumerate() {
-                    if let Some(mat) = regex.find(line) {
-                        results.push(SearchResult {
-                            file: file_path.clone(),
-                            line_number: line_num + 1,
-                            content: line.to_string(),
-                            match_position: mat.start(),
-                        });
-                    }
-                }
+            let Ok(content) = FileUtils::read_file(file_path) else {
+                continue;
+            };
+
+            let remaining = options.max_results.map(|max| max.saturating_sub(results.len()));
+            if remaining == Some(0) {
+                break;
             }
+
+            results.extend(Self::search_content(&regex, file_path, &content, options, remaining));
         }
 
         Ok(results)
     }
 
-    pub fn search_keyword(query: &str, files: &[PathBuf]) -> Result<Vec<SearchResult>> {
+    pub fn search_text(
+        pattern: &str,
+        file: &Path,
+        content: &str,
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchResult>> {
+        let regex = options.build_regex(pattern)?;
+        Ok(Self::search_content(&regex, file, content, options, options.max_results))
+    }
+
+    fn search_content(
+        regex: &Regex,
+        file: &Path,
+        content: &str,
+        options: &SearchOptions,
+        limit: Option<usize>,
+    ) -> Vec<SearchResult> {
+        let lines: Vec<&str> = content.lines().collect();
+        let mut line_starts = Vec::with_capacity(lines.len() + 1);
+        let mut offset = 0;
+        for line in content.split_inclusive('\n') {
+            line_starts.push(offset);
+            offset += line.len();
+        }
+        if line_starts.is_empty() {
+            line_starts.push(0);
+        }
+
+        let line_of = |byte: usize| match line_starts.binary_search(&byte) {
+            Ok(idx) => idx,
+            Err(idx) => idx.saturating_sub(1),
+        };
+
         let mut results = Vec::new();
+        let matches: Box<dyn Iterator<Item = (usize, usize)>> = if options.multiline {
+            Box::new(regex.find_iter(content).map(|m| (m.start(), m.end())))
+        } else {
+            Box::new(lines.iter().enumerate().flat_map(|(idx, line)| {
+                let base = line_starts.get(idx).copied().unwrap_or(0);
+                regex.find_iter(line).map(move |m| (base + m.start(), base + m.end()))
+            }))
+        };
 
-        for file_path in files {
-            if let Ok(content) = std::fs::read_to_string(file_path) {
-                for (line_num, line) in content.lines().enumerate() {
-                    if line.contains(query) {
-                        if let Some(pos) = line.find(query) {
-                            results.push(SearchResult {
-                                file: file_path.clone(),
-                                line_number: line_num + 1,
-                                content: line.to_string(),
-                                match_position: pos,
-                            });
-                        }
-                    }
-                }
+        for (start, end) in matches {
+            if limit.is_some_and(|max| results.len() >= max) {
+                break;
+            }
+            if start == end && start >= content.len() {
+                continue;
             }
+
+            let start_line = line_of(start);
+            let end_line = line_of(end.saturating_sub(1).max(start)).max(start_line);
+            let end_line = end_line.min(lines.len().saturating_sub(1));
+            let matched_lines = lines.get(start_line..=end_line).unwrap_or_default();
+
+            let before_start = start_line.saturating_sub(options.before_context);
+            let after_end = (end_line + 1 + options.after_context).min(lines.len());
+
+            results.push(SearchResult {
+                file: file.to_path_buf(),
+                line_number: start_line + 1,
+                end_line: end_line + 1,
+                content: matched_lines.join("\n"),
+                match_position: start - line_starts[start_line],
+                match_length: end - start,
+                context_before: lines[before_start..start_line].iter().map(|l| l.to_string()).collect(),
+                context_after: lines[(end_line + 1).min(lines.len())..after_end]
+                    .iter()
+                    .map(|l| l.to_string())
+                    .collect(),
+                score: None,
+            });
         }
 
-        Ok(results)
+        results
+    }
+
+    pub fn group_by_file(results: Vec<SearchResult>) -> Vec<FileMatches> {
+        let mut groups: Vec<FileMatches> = Vec::new();
+
+        for result in results {
+            match groups.iter_mut().find(|g| g.file == result.file) {
+                Some(group) => group.results.push(result),
+                None => groups.push(FileMatches {
+                    file: result.file.clone(),
+                    results: vec![result],
+                }),
+            }
+        }
+
+        groups
+    }
+
+    pub fn search_project(pattern: &str, root: &Path) -> Result<Vec<SearchResult>> {
+        Self::search_project_with_options(pattern, root, &SearchOptions::default())
+    }
+
+    pub fn search_project_with_options(
+        pattern: &str,
+        root: &Path,
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchResult>> {
+        let files = ProjectWalker::for_project(root).walk()?;
+        Self::search_with_options(pattern, &files, options)
+    }
+
+    pub async fn search_semantic(
+        index: &SemanticIndex,
+        query: &str,
+        k: usize,
+    ) -> Result<Vec<SearchResult>> {
+        index.search_semantic(query, k).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "fn a() {\n    let token = parse(token);\n}\nfn b() {\n    Token::new()\n}\n";
+
+    #[test]
+    fn test_every_match_with_context() {
+        let options = SearchOptions {
+            before_context: 1,
+            after_context: 1,
+            ..SearchOptions::default()
+        };
+        let results = SemanticSearch::search_text("token", Path::new("a.rs"), SAMPLE, &options).unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].line_number, 2);
+        assert_eq!(results[0].match_position, 8);
+        assert_eq!(results[1].match_position, 22);
+        assert_eq!(results[0].context_before, vec!["fn a() {"]);
+        assert_eq!(results[0].context_after, vec!["}"]);
+    }
+
+    #[test]
+    fn test_case_word_and_limit_options() {
+        let options = SearchOptions {
+            case_insensitive: true,
+            whole_word: true,
+            max_results: Some(2),
+            ..SearchOptions::default()
+        };
+        let results = SemanticSearch::search_text("token", Path::new("a.rs"), SAMPLE, &options).unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.line_number == 2));
+    }
+
+    #[test]
+    fn test_multiline_pattern_spans_lines() {
+        let options = SearchOptions {
+            multiline: true,
+            ..SearchOptions::default()
+        };
+        let results = SemanticSearch::search_text(r"fn b\(\) \{\n\s+Token", Path::new("a.rs"), SAMPLE, &options).unwrap();
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].line_number, 4);
+        assert_eq!(results[0].end_line, 5);
+        assert_eq!(results[0].content, "fn b() {\n    Token::new()");
     }
 }
@@ -0,0 +1,316 @@
+use crate::config::yaml_parser::YamlConfig;
+use crate::core::policy::PolicyEnforcer;
+use anyhow::Result;
+use globset::{Glob, GlobMatcher};
+use ignore::{WalkBuilder, WalkState};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 1024 * 1024;
+const ALWAYS_SKIPPED_DIRS: &[&str] = &[".git", ".codeagent"];
+const BINARY_SNIFF_BYTES: usize = 8192;
+
+#[derive(Debug, Clone)]
+struct GlobRule {
+    matcher: GlobMatcher,
+    negated: bool,
+    basename_only: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GlobRules {
+    rules: Vec<GlobRule>,
+}
+
+impl GlobRules {
+    pub fn new(patterns: &[String]) -> Result<Self> {
+        let mut rules = Vec::with_capacity(patterns.len());
+
+        for raw in patterns {
+            let raw = raw.trim();
+            if raw.is_empty() || raw.starts_with('#') {
+                continue;
+            }
+
+            let (negated, pattern) = match raw.strip_prefix('!') {
+                Some(rest) => (true, rest),
+                None => (false, raw),
+            };
+            let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+            let basename_only = !pattern.contains('/');
+            let pattern = pattern.trim_start_matches('/');
+
+            let matcher = Glob::new(pattern)?.compile_matcher();
+            rules.push(GlobRule {
+                matcher,
+                negated,
+                basename_only,
+            });
+        }
+
+        Ok(GlobRules { rules })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    pub fn matches_file(&self, relative_path: &str) -> bool {
+        let relative_path = relative_path.trim_start_matches("./");
+        let basename = relative_path.rsplit('/').next().unwrap_or(relative_path);
+
+        let mut excluded = false;
+        for rule in &self.rules {
+            let hit = if rule.basename_only {
+                rule.matcher.is_match(basename)
+            } else {
+                rule.matcher.is_match(relative_path)
+            };
+            if hit {
+                excluded = !rule.negated;
+            }
+        }
+        excluded
+    }
+
+    pub fn matches_dir(&self, relative_path: &str) -> bool {
+        let relative_path = relative_path.trim_start_matches("./").trim_end_matches('/');
+
+        let mut excluded = false;
+        for rule in &self.rules {
+            let hit = if rule.basename_only {
+                relative_path.split('/').any(|segment| rule.matcher.is_match(segment))
+            } else {
+                rule.matcher.is_match(relative_path)
+            };
+            if hit {
+                excluded = !rule.negated;
+            }
+        }
+        excluded
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WalkOptions {
+    pub extensions: Vec<String>,
+    pub excluded_files: Vec<String>,
+    pub excluded_dirs: Vec<String>,
+    pub max_file_size: Option<u64>,
+    pub skip_binary: bool,
+    pub include_hidden: bool,
+    pub respect_ignore_files: bool,
+}
+
+impl Default for WalkOptions {
+    fn default() -> Self {
+        WalkOptions {
+            extensions: Vec::new(),
+            excluded_files: Vec::new(),
+            excluded_dirs: Vec::new(),
+            max_file_size: Some(DEFAULT_MAX_FILE_SIZE),
+            skip_binary: true,
+            include_hidden: false,
+            respect_ignore_files: true,
+        }
+    }
+}
+
+pub struct ProjectWalker {
+    root: PathBuf,
+    options: WalkOptions,
+    policy: Option<Arc<PolicyEnforcer>>,
+}
+
+impl ProjectWalker {
+    pub fn new(root: &Path) -> Self {
+        ProjectWalker {
+            root: root.to_path_buf(),
+            options: WalkOptions::default(),
+            policy: None,
+        }
+    }
+
+    pub fn from_config(root: &Path, config: &YamlConfig) -> Self {
+        let mut walker = Self::new(root);
+        walker.options.excluded_files = config.excluded_files.clone();
+        walker.options.excluded_dirs = config.excluded_dirs.clone();
+        walker
+    }
+
+    pub fn for_project(root: &Path) -> Self {
+        let config = YamlConfig::load(&root.join(".codeagent.yml")).unwrap_or_default();
+        let mut walker = Self::from_config(root, &config);
+        walker.policy = Some(PolicyEnforcer::for_project(root));
+        walker
+    }
+
+    pub fn with_extensions(mut self, extensions: &[&str]) -> Self {
+        self.options.extensions = extensions.iter().map(|e| e.to_string()).collect();
+        self
+    }
+
+    pub fn with_options(mut self, options: WalkOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn options_mut(&mut self) -> &mut WalkOptions {
+        &mut self.options
+    }
+
+    pub fn walk(&self) -> Result<Vec<PathBuf>> {
+        let file_rules = Arc::new(GlobRules::new(&self.options.excluded_files)?);
+        let dir_rules = Arc::new(GlobRules::new(&self.options.excluded_dirs)?);
+        let root = Arc::new(self.root.clone());
+        let options = Arc::new(self.options.clone());
+        let found = Arc::new(Mutex::new(Vec::new()));
+
+        let mut builder = WalkBuilder::new(&self.root);
+        builder
+            .hidden(!self.options.include_hidden)
+            .git_ignore(self.options.respect_ignore_files)
+            .git_global(self.options.respect_ignore_files)
+            .git_exclude(self.options.respect_ignore_files)
+            .ignore(self.options.respect_ignore_files)
+            .parents(self.options.respect_ignore_files)
+            .require_git(false);
+
+        let filter_root = Arc::clone(&root);
+        let filter_dirs = Arc::clone(&dir_rules);
+        builder.filter_entry(move |entry| {
+            if !entry.file_type().is_some_and(|t| t.is_dir()) || entry.depth() == 0 {
+                return true;
+            }
+            if ALWAYS_SKIPPED_DIRS.iter().any(|d| entry.file_name() == *d) {
+                return false;
+            }
+            let relative = relative_string(&filter_root, entry.path());
+            !filter_dirs.matches_dir(&relative)
+        });
+
+        builder.build_parallel().run(|| {
+            let root = Arc::clone(&root);
+            let options = Arc::clone(&options);
+            let file_rules = Arc::clone(&file_rules);
+            let policy = self.policy.clone();
+            let found = Arc::clone(&found);
+
+            Box::new(move |result| {
+                let Ok(entry) = result else {
+                    return WalkState::Continue;
+                };
+                if !entry.file_type().is_some_and(|t| t.is_file()) {
+                    return WalkState::Continue;
+                }
+
+                let path = entry.path();
+                if !options.extensions.is_empty() {
+                    let matches_extension = path
+                        .extension()
+                        .and_then(|e| e.to_str())
+                        .is_some_and(|e| options.extensions.iter().any(|x| x == e));
+                    if !matches_extension {
+                        return WalkState::Continue;
+                    }
+                }
+
+                if file_rules.matches_file(&relative_string(&root, path)) {
+                    return WalkState::Continue;
+                }
+
+                if policy.as_ref().is_some_and(|p| !p.can_read(path)) {
+                    return WalkState::Continue;
+                }
+
+                if let Some(max_size) = options.max_file_size {
+                    if entry.metadata().map(|m| m.len() > max_size).unwrap_or(false) {
+                        return WalkState::Continue;
+                    }
+                }
+
+                if options.skip_binary && is_binary(path) {
+                    return WalkState::Continue;
+                }
+
+                if let Ok(mut files) = found.lock() {
+                    files.push(path.to_path_buf());
+                }
+                WalkState::Continue
+            })
+        });
+
+        let mut files = Arc::try_unwrap(found)
+            .map_err(|_| anyhow::anyhow!("Walker still running"))?
+            .into_inner()
+            .map_err(|_| anyhow::anyhow!("Walker result lock poisoned"))?;
+        files.sort();
+        Ok(files)
+    }
+}
+
+pub fn is_binary(path: &Path) -> bool {
+    let Ok(mut file) = std::fs::File::open(path) else {
+        return false;
+    };
+
+    let mut buffer = [0u8; BINARY_SNIFF_BYTES];
+    let read = file.read(&mut buffer).unwrap_or(0);
+    buffer[..read].contains(&0)
+}
+
+fn relative_string(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(patterns: &[&str]) -> GlobRules {
+        GlobRules::new(&patterns.iter().map(|p| p.to_string()).collect::<Vec<_>>()).unwrap()
+    }
+
+    #[test]
+    fn test_basename_globs_and_negation() {
+        let rules = rules(&["*.min.js", "*.lock", "!Cargo.lock"]);
+
+        assert!(rules.matches_file("static/app.min.js"));
+        assert!(rules.matches_file("yarn.lock"));
+        assert!(!rules.matches_file("Cargo.lock"));
+        assert!(!rules.matches_file("src/app.js"));
+    }
+
+    #[test]
+    fn test_dir_rules_match_segments_not_substrings() {
+        let rules = rules(&["target", "node_modules", "docs/generated/**"]);
+
+        assert!(rules.matches_dir("target"));
+        assert!(rules.matches_dir("web/node_modules"));
+        assert!(!rules.matches_dir("src/target_utils"));
+        assert!(rules.matches_dir("docs/generated/api"));
+        assert!(!rules.matches_dir("docs/guide"));
+    }
+
+    #[test]
+    fn test_build_dirs_are_skipped_when_config_omits_excluded_dirs() {
+        let root = std::env::temp_dir().join(format!("codeagent-walker-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        for dir in ["src", "target/debug", "web/node_modules/lib"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        std::fs::write(root.join(".codeagent.yml"), "model_provider: ollama\n").unwrap();
+        std::fs::write(root.join("src/main.rs"), "fn main() {}\n").unwrap();
+        std::fs::write(root.join("target/debug/build.rs"), "fn built() {}\n").unwrap();
+        std::fs::write(root.join("web/node_modules/lib/index.js"), "module.exports = 1;\n").unwrap();
+
+        let files = ProjectWalker::for_project(&root).walk().unwrap();
+        let relative: Vec<_> = files.iter().map(|p| p.strip_prefix(&root).unwrap().to_path_buf()).collect();
+        assert_eq!(relative, vec![PathBuf::from("src/main.rs")]);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}